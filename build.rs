@@ -0,0 +1,21 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
+/// Writes the versions of every migration in `migrations/` so `/readyz` can
+/// tell whether the database is behind this build.
+fn main() {
+    println!("cargo:rerun-if-changed=migrations");
+
+    let mut versions: Vec<String> = fs::read_dir("migrations")
+        .expect("migrations directory")
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .map(|name| name.split('_').next().unwrap_or_default().replace('-', ""))
+        .collect();
+    versions.sort();
+
+    let out = Path::new(&env::var("OUT_DIR").unwrap()).join("migrations.rs");
+    fs::write(out, format!("const MIGRATION_VERSIONS: &[&str] = &{:?};\n", versions)).unwrap();
+}
\ No newline at end of file
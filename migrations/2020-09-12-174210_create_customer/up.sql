@@ -0,0 +1,7 @@
+CREATE TABLE customer (
+    customer_id SERIAL PRIMARY KEY,
+    first_name TEXT NOT NULL,
+    last_name TEXT NOT NULL,
+    email TEXT UNIQUE,
+    phone TEXT
+);
@@ -0,0 +1,2 @@
+DROP TABLE sms_message;
+DROP TABLE notification_preference;
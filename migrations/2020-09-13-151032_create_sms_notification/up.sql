@@ -0,0 +1,26 @@
+CREATE TABLE notification_preference (
+    notification_preference_id SERIAL PRIMARY KEY,
+    customer_id INT UNIQUE NOT NULL,
+    email_enabled INT NOT NULL DEFAULT(1),
+    sms_enabled INT NOT NULL DEFAULT(0),
+    CONSTRAINT fk_customer
+        FOREIGN KEY(customer_id) 
+        REFERENCES customer(customer_id)
+);
+
+CREATE TABLE sms_message (
+    sms_message_id SERIAL PRIMARY KEY,
+    customer_id INT NOT NULL,
+    phone TEXT NOT NULL,
+    body TEXT NOT NULL,
+    status TEXT NOT NULL DEFAULT('pending'),
+    provider_message_id TEXT,
+    error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_customer
+        FOREIGN KEY(customer_id) 
+        REFERENCES customer(customer_id)
+);
+
+CREATE INDEX sms_message_provider_message_id ON sms_message(provider_message_id);
@@ -0,0 +1,27 @@
+CREATE TABLE webhook_subscription (
+    webhook_subscription_id SERIAL PRIMARY KEY,
+    url TEXT NOT NULL,
+    secret TEXT NOT NULL,
+    event_types TEXT[] NOT NULL,
+    is_active INT NOT NULL DEFAULT(1),
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE TABLE webhook_delivery (
+    webhook_delivery_id SERIAL PRIMARY KEY,
+    webhook_subscription_id INT NOT NULL,
+    event_type TEXT NOT NULL,
+    payload JSONB NOT NULL,
+    status TEXT NOT NULL DEFAULT('pending'),
+    attempts INT NOT NULL DEFAULT(0),
+    next_attempt_at TIMESTAMP,
+    last_status_code INT,
+    last_error TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    CONSTRAINT fk_webhook_subscription
+        FOREIGN KEY(webhook_subscription_id) 
+        REFERENCES webhook_subscription(webhook_subscription_id)
+);
+
+CREATE INDEX webhook_delivery_due ON webhook_delivery(status, next_attempt_at);
@@ -0,0 +1,12 @@
+CREATE TABLE audit_log (
+    audit_log_id SERIAL PRIMARY KEY,
+    actor TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    entity_id INT NOT NULL,
+    action TEXT NOT NULL,
+    before JSONB,
+    after JSONB,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX audit_log_entity ON audit_log(entity_type, entity_id);
@@ -0,0 +1,2 @@
+ALTER TABLE service DROP COLUMN deleted_at;
+ALTER TABLE staff DROP COLUMN deleted_at;
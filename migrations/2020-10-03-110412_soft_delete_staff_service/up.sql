@@ -0,0 +1,2 @@
+ALTER TABLE staff ADD COLUMN deleted_at TIMESTAMP;
+ALTER TABLE service ADD COLUMN deleted_at TIMESTAMP;
@@ -0,0 +1 @@
+ALTER TABLE store_address ALTER COLUMN zip TYPE INT USING left(zip, 5)::int;
\ No newline at end of file
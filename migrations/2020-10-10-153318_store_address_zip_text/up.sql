@@ -0,0 +1 @@
+ALTER TABLE store_address ALTER COLUMN zip TYPE VARCHAR USING lpad(zip::text, 5, '0');
\ No newline at end of file
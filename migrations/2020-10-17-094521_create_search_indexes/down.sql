@@ -0,0 +1,8 @@
+DROP INDEX customer_search_trgm_idx;
+DROP INDEX customer_search_tsv_idx;
+DROP INDEX service_search_trgm_idx;
+DROP INDEX service_search_tsv_idx;
+DROP INDEX staff_search_trgm_idx;
+DROP INDEX staff_search_tsv_idx;
+
+DROP EXTENSION IF EXISTS pg_trgm;
\ No newline at end of file
@@ -0,0 +1,12 @@
+CREATE EXTENSION IF NOT EXISTS pg_trgm;
+
+-- the search query in src/search/model.rs repeats these expressions so the
+-- planner can use the indexes; change both together
+CREATE INDEX staff_search_tsv_idx ON staff USING GIN (to_tsvector('simple', first_name || ' ' || last_name || ' ' || email || ' ' || coalesce(phone, '')));
+CREATE INDEX staff_search_trgm_idx ON staff USING GIN ((first_name || ' ' || last_name || ' ' || email || ' ' || coalesce(phone, '')) gin_trgm_ops);
+
+CREATE INDEX service_search_tsv_idx ON service USING GIN (to_tsvector('simple', name || ' ' || coalesce(description, '') || ' ' || coalesce(category, '')));
+CREATE INDEX service_search_trgm_idx ON service USING GIN ((name || ' ' || coalesce(description, '') || ' ' || coalesce(category, '')) gin_trgm_ops);
+
+CREATE INDEX customer_search_tsv_idx ON customer USING GIN (to_tsvector('simple', first_name || ' ' || last_name || ' ' || coalesce(email, '') || ' ' || coalesce(phone, '')));
+CREATE INDEX customer_search_trgm_idx ON customer USING GIN ((first_name || ' ' || last_name || ' ' || coalesce(email, '') || ' ' || coalesce(phone, '')) gin_trgm_ops);
\ No newline at end of file
@@ -0,0 +1,15 @@
+DROP INDEX service_search_tsv_idx;
+DROP INDEX service_search_trgm_idx;
+
+ALTER TABLE service ADD COLUMN category VARCHAR;
+
+UPDATE service
+SET category = service_category.name
+FROM service_category
+WHERE service.service_category_id = service_category.service_category_id;
+
+ALTER TABLE service DROP COLUMN service_category_id;
+DROP TABLE service_category;
+
+CREATE INDEX service_search_tsv_idx ON service USING GIN (to_tsvector('simple', name || ' ' || coalesce(description, '') || ' ' || coalesce(category, '')));
+CREATE INDEX service_search_trgm_idx ON service USING GIN ((name || ' ' || coalesce(description, '') || ' ' || coalesce(category, '')) gin_trgm_ops);
\ No newline at end of file
@@ -0,0 +1,35 @@
+CREATE TABLE service_category (
+    service_category_id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    description TEXT,
+    display_order INT NOT NULL DEFAULT 0,
+    is_active INT NOT NULL DEFAULT 1
+);
+
+CREATE UNIQUE INDEX service_category_name_idx ON service_category (lower(name));
+
+-- spellings that differ only in case or surrounding spaces become one
+-- category, named after the oldest service that used it
+INSERT INTO service_category (name, display_order)
+SELECT name, row_number() OVER (ORDER BY lower(name))
+FROM (
+    SELECT DISTINCT ON (lower(trim(category))) trim(category) AS name
+    FROM service
+    WHERE trim(coalesce(category, '')) <> ''
+    ORDER BY lower(trim(category)), service_id
+) spellings;
+
+ALTER TABLE service ADD COLUMN service_category_id INT REFERENCES service_category ON DELETE SET NULL;
+CREATE INDEX service_service_category_id_idx ON service (service_category_id);
+
+UPDATE service
+SET service_category_id = service_category.service_category_id
+FROM service_category
+WHERE lower(trim(service.category)) = lower(service_category.name);
+
+DROP INDEX service_search_tsv_idx;
+DROP INDEX service_search_trgm_idx;
+ALTER TABLE service DROP COLUMN category;
+
+CREATE INDEX service_search_tsv_idx ON service USING GIN (to_tsvector('simple', name || ' ' || coalesce(description, '')));
+CREATE INDEX service_search_trgm_idx ON service USING GIN ((name || ' ' || coalesce(description, '')) gin_trgm_ops);
\ No newline at end of file
@@ -0,0 +1 @@
+DROP TABLE service_add_on;
\ No newline at end of file
@@ -0,0 +1,11 @@
+CREATE TABLE service_add_on (
+    service_add_on_id SERIAL PRIMARY KEY,
+    service_id INT NOT NULL REFERENCES service,
+    name TEXT NOT NULL,
+    price FLOAT NOT NULL,
+    extra_duration TIME,
+    is_active INT NOT NULL DEFAULT 1,
+    deleted_at TIMESTAMP
+);
+
+CREATE INDEX service_add_on_service_id_idx ON service_add_on (service_id);
\ No newline at end of file
@@ -0,0 +1,3 @@
+DROP TABLE appointment_segment_add_on;
+DROP TABLE appointment_segment;
+DROP TABLE appointment;
\ No newline at end of file
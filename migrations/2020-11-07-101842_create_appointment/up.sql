@@ -0,0 +1,39 @@
+CREATE TABLE appointment (
+    appointment_id SERIAL PRIMARY KEY,
+    customer_id INT NOT NULL REFERENCES customer,
+    status TEXT NOT NULL DEFAULT 'booked',
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    price FLOAT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX appointment_customer_id_idx ON appointment (customer_id);
+CREATE INDEX appointment_start_time_idx ON appointment (start_time);
+
+-- one service variant with one staff member; a visit is its segments in
+-- position order
+CREATE TABLE appointment_segment (
+    appointment_segment_id SERIAL PRIMARY KEY,
+    appointment_id INT NOT NULL REFERENCES appointment ON DELETE CASCADE,
+    position INT NOT NULL,
+    staff_id INT NOT NULL REFERENCES staff,
+    service_variant_id INT NOT NULL REFERENCES service_variant,
+    price FLOAT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    blocked_from TIMESTAMP NOT NULL,
+    blocked_until TIMESTAMP NOT NULL,
+    UNIQUE (appointment_id, position)
+);
+
+CREATE INDEX appointment_segment_staff_id_idx ON appointment_segment (staff_id, blocked_from);
+
+CREATE TABLE appointment_segment_add_on (
+    appointment_segment_add_on_id SERIAL PRIMARY KEY,
+    appointment_segment_id INT NOT NULL REFERENCES appointment_segment ON DELETE CASCADE,
+    service_add_on_id INT NOT NULL REFERENCES service_add_on,
+    price FLOAT NOT NULL
+);
+
+CREATE INDEX appointment_segment_add_on_segment_idx ON appointment_segment_add_on (appointment_segment_id);
\ No newline at end of file
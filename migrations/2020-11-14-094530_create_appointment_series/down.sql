@@ -0,0 +1,7 @@
+DROP INDEX appointment_series_occurrence_idx;
+
+ALTER TABLE appointment
+    DROP COLUMN occurrence_time,
+    DROP COLUMN appointment_series_id;
+
+DROP TABLE appointment_series;
\ No newline at end of file
@@ -0,0 +1,23 @@
+-- a recurring visit; its occurrences are booked as ordinary appointments
+-- once they come within the booking horizon
+CREATE TABLE appointment_series (
+    appointment_series_id SERIAL PRIMARY KEY,
+    customer_id INT NOT NULL REFERENCES customer,
+    status TEXT NOT NULL DEFAULT 'active',
+    start_time TIMESTAMP NOT NULL,
+    rrule TEXT NOT NULL,
+    segments JSONB NOT NULL,
+    excluded_times TIMESTAMP[] NOT NULL DEFAULT '{}',
+    materialized_until TIMESTAMP,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX appointment_series_customer_id_idx ON appointment_series (customer_id);
+
+ALTER TABLE appointment
+    ADD COLUMN appointment_series_id INT REFERENCES appointment_series,
+    ADD COLUMN occurrence_time TIMESTAMP;
+
+-- an occurrence is booked at most once, however often materialization runs
+CREATE UNIQUE INDEX appointment_series_occurrence_idx ON appointment (appointment_series_id, occurrence_time)
+    WHERE status = 'booked';
\ No newline at end of file
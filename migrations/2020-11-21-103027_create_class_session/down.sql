@@ -0,0 +1,5 @@
+DROP TABLE class_enrollment;
+DROP TABLE class_session;
+
+ALTER TABLE service_variant DROP COLUMN capacity;
+ALTER TABLE service DROP COLUMN capacity;
\ No newline at end of file
@@ -0,0 +1,33 @@
+-- customers one slot takes; more than one makes the service a group class
+ALTER TABLE service ADD COLUMN capacity INT NOT NULL DEFAULT 1;
+ALTER TABLE service_variant ADD COLUMN capacity INT;
+
+-- one scheduled slot of a group class, taught by one staff member
+CREATE TABLE class_session (
+    class_session_id SERIAL PRIMARY KEY,
+    service_variant_id INT NOT NULL REFERENCES service_variant,
+    staff_id INT NOT NULL REFERENCES staff,
+    status TEXT NOT NULL DEFAULT 'scheduled',
+    capacity INT NOT NULL,
+    price FLOAT NOT NULL,
+    start_time TIMESTAMP NOT NULL,
+    end_time TIMESTAMP NOT NULL,
+    blocked_from TIMESTAMP NOT NULL,
+    blocked_until TIMESTAMP NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE INDEX class_session_staff_id_idx ON class_session (staff_id, blocked_from);
+CREATE INDEX class_session_start_time_idx ON class_session (start_time);
+
+CREATE TABLE class_enrollment (
+    class_enrollment_id SERIAL PRIMARY KEY,
+    class_session_id INT NOT NULL REFERENCES class_session,
+    customer_id INT NOT NULL REFERENCES customer,
+    status TEXT NOT NULL DEFAULT 'enrolled',
+    price FLOAT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX class_enrollment_customer_idx ON class_enrollment (class_session_id, customer_id)
+    WHERE status = 'enrolled';
\ No newline at end of file
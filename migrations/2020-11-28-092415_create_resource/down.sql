@@ -0,0 +1,5 @@
+DROP TABLE appointment_segment_resource;
+ALTER TABLE appointment_series DROP COLUMN store_id;
+ALTER TABLE appointment DROP COLUMN store_id;
+DROP TABLE service_resource;
+DROP TABLE resource;
\ No newline at end of file
@@ -0,0 +1,30 @@
+-- a room, chair or piece of equipment at one store that a booking holds
+CREATE TABLE resource (
+    resource_id SERIAL PRIMARY KEY,
+    store_id INT NOT NULL REFERENCES store,
+    name VARCHAR NOT NULL,
+    resource_type TEXT NOT NULL,
+    is_active INT NOT NULL DEFAULT 1,
+    deleted_at TIMESTAMP
+);
+
+CREATE INDEX resource_store_id_idx ON resource (store_id, resource_type);
+
+-- every booking of the service holds one resource of each listed type
+CREATE TABLE service_resource (
+    service_resource_id SERIAL PRIMARY KEY,
+    service_id INT NOT NULL REFERENCES service,
+    resource_type TEXT NOT NULL,
+    UNIQUE (service_id, resource_type)
+);
+
+ALTER TABLE appointment ADD COLUMN store_id INT REFERENCES store;
+ALTER TABLE appointment_series ADD COLUMN store_id INT REFERENCES store;
+
+CREATE TABLE appointment_segment_resource (
+    appointment_segment_resource_id SERIAL PRIMARY KEY,
+    appointment_segment_id INT NOT NULL REFERENCES appointment_segment,
+    resource_id INT NOT NULL REFERENCES resource
+);
+
+CREATE INDEX appointment_segment_resource_resource_id_idx ON appointment_segment_resource (resource_id);
\ No newline at end of file
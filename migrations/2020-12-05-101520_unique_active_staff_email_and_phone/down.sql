@@ -0,0 +1,4 @@
+DROP INDEX staff_phone_key;
+DROP INDEX staff_email_key;
+ALTER TABLE staff ADD CONSTRAINT staff_phone_key UNIQUE (phone);
+ALTER TABLE staff ADD CONSTRAINT staff_email_key UNIQUE (email);
\ No newline at end of file
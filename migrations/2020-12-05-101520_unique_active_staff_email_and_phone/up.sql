@@ -0,0 +1,5 @@
+-- a soft deleted member keeps their row, but not their email or phone
+ALTER TABLE staff DROP CONSTRAINT staff_email_key;
+ALTER TABLE staff DROP CONSTRAINT staff_phone_key;
+CREATE UNIQUE INDEX staff_email_key ON staff (email) WHERE deleted_at IS NULL;
+CREATE UNIQUE INDEX staff_phone_key ON staff (phone) WHERE deleted_at IS NULL;
\ No newline at end of file
@@ -1,19 +1,88 @@
+use actix_web::error::{JsonPayloadError, PathError, QueryPayloadError};
 use actix_web::http::StatusCode;
-use actix_web::{HttpResponse, ResponseError};
-use diesel::result::Error as DieselError;
+use actix_web::{web, HttpRequest, HttpResponse, ResponseError};
+use diesel::result::{DatabaseErrorInformation, DatabaseErrorKind, Error as DieselError};
 use serde::Deserialize;
 use serde_json::json;
+use std::collections::BTreeMap;
 use std::fmt;
 
 #[derive(Debug, Deserialize)]
 pub struct ApiError {
     pub status_code: u16,
+    #[serde(default)]
+    pub code: String,
     pub message: String,
+    #[serde(default)]
+    pub errors: Option<BTreeMap<String, Vec<String>>>,
 }
 
 impl ApiError {
     pub fn new(status_code: u16, message: String) -> ApiError {
-        ApiError { status_code, message }
+        ApiError::coded(status_code, default_code(status_code), message)
+    }
+
+    pub fn coded(status_code: u16, code: &str, message: String) -> ApiError {
+        ApiError { status_code, code: code.to_string(), message, errors: None }
+    }
+
+    pub fn validation(errors: BTreeMap<String, Vec<String>>) -> ApiError {
+        ApiError {
+            status_code: 422,
+            code: "request.validation_failed".to_string(),
+            message: "Validation failed".to_string(),
+            errors: Some(errors)
+        }
+    }
+
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status_code).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Builds the JSON envelope. Generic codes such as `not_found` are
+    /// prefixed with `scope` (the resource the request was for), so a
+    /// missing store surfaces as `store.not_found`.
+    pub fn to_response(&self, scope: Option<&str>, request_id: Option<&str>) -> HttpResponse {
+        let status_code = self.status();
+
+        let (code, message) = match status_code.is_server_error() {
+            true => ("internal".to_string(), "Internal server error".to_string()),
+            false => (self.scoped_code(scope), self.message.clone()),
+        };
+
+        let mut body = json!({ "code": code, "message": message });
+
+        if let Some(errors) = &self.errors {
+            body["errors"] = json!(errors);
+        }
+
+        if let Some(request_id) = request_id {
+            body["request_id"] = json!(request_id);
+        }
+
+        HttpResponse::build(status_code).json(body)
+    }
+
+    fn scoped_code(&self, scope: Option<&str>) -> String {
+        match scope {
+            Some(scope) if !scope.is_empty() && !self.code.contains('.') => format!("{}.{}", scope, self.code),
+            _ => self.code.clone(),
+        }
+    }
+}
+
+fn default_code(status_code: u16) -> &'static str {
+    match status_code {
+        400 => "bad_request",
+        401 => "unauthorized",
+        403 => "forbidden",
+        404 => "not_found",
+        409 => "conflict",
+        413 => "payload_too_large",
+        415 => "unsupported_media_type",
+        422 => "unprocessable",
+        502 => "bad_gateway",
+        _ => "internal",
     }
 }
 
@@ -26,29 +95,87 @@ impl fmt::Display for ApiError {
 impl From<DieselError> for ApiError {
     fn from(error: DieselError) -> ApiError {
         match error {
-            DieselError::DatabaseError(_, err) => ApiError::new(409, err.message().to_string()),
+            DieselError::DatabaseError(kind, info) => ApiError::from_database(kind, info.as_ref()),
             DieselError::NotFound => ApiError::new(404, "Record not found".to_string()),
             err => ApiError::new(500, format!("Diesel error: {}", err)),
         }
     }
 }
 
+impl ApiError {
+    fn from_database(kind: DatabaseErrorKind, info: &dyn DatabaseErrorInformation) -> ApiError {
+        let table = info.table_name().unwrap_or("record");
+        let constraint = info.constraint_name().unwrap_or_default();
+        let column = key_columns(info).unwrap_or_else(|| constraint.to_string());
+
+        match kind {
+            DatabaseErrorKind::UniqueViolation => {
+                ApiError::coded(409, &format!("{}.{}_taken", table, column), format!("{} is already taken", column))
+            },
+            DatabaseErrorKind::ForeignKeyViolation if info.message().starts_with("update or delete") => {
+                let referenced = info.message().split('"').nth(1).unwrap_or(table);
+                ApiError::coded(409, &format!("{}.in_use", referenced), format!("{} is still referenced by {}", referenced, table))
+            },
+            DatabaseErrorKind::ForeignKeyViolation => {
+                ApiError::coded(422, &format!("{}.{}_unknown", table, column), format!("{} does not reference an existing record", column))
+            },
+            _ if info.message().contains("violates check constraint") => {
+                ApiError::coded(422, &format!("{}.check_failed", table), format!("Check {} failed", constraint))
+            },
+            _ if info.message().starts_with("null value in column") => {
+                let column = info.column_name().unwrap_or("value");
+                ApiError::coded(422, &format!("{}.{}_required", table, column), format!("{} is required", column))
+            },
+            _ => ApiError::new(500, format!("Database error: {}", info.message())),
+        }
+    }
+}
+
+/// Reads the offending columns out of a detail line such as
+/// `Key (email)=(a@b.com) already exists.`
+fn key_columns(info: &dyn DatabaseErrorInformation) -> Option<String> {
+    let columns = info.details()?.strip_prefix("Key (")?.split(")=").next()?;
+    Some(columns.replace(", ", "_"))
+}
+
 impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.status()
+    }
+
     fn error_response(&self) -> HttpResponse {
-        let status_code = match StatusCode::from_u16(self.status_code) {
-            Ok(status_code) => status_code,
-            Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
-        };
+        self.to_response(None, None)
+    }
+}
 
-        let message = match status_code.as_u16() < 500 {
-            true => self.message.clone(),
-            false => {
-                error!("{}", self.message);
-                "Internal server error".to_string()
-            },
+pub fn json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|err, _| {
+        let api_error = match err {
+            JsonPayloadError::Overflow => ApiError::coded(413, "request.body_too_large", "Request body is too large".to_string()),
+            JsonPayloadError::ContentType => ApiError::coded(415, "request.content_type", "Expected an application/json body".to_string()),
+            JsonPayloadError::Deserialize(err) if err.is_data() => ApiError::coded(422, "request.invalid_body", err.to_string()),
+            JsonPayloadError::Deserialize(err) => ApiError::coded(400, "request.malformed_json", err.to_string()),
+            JsonPayloadError::Payload(err) => ApiError::coded(400, "request.payload", err.to_string()),
         };
 
-        HttpResponse::build(status_code)
-            .json(json!({ "message": message }))
-    }
+        api_error.into()
+    })
+}
+
+pub fn path_config() -> web::PathConfig {
+    web::PathConfig::default().error_handler(|err, _| {
+        let PathError::Deserialize(err) = err;
+        ApiError::coded(400, "request.invalid_path", err.to_string()).into()
+    })
+}
+
+pub fn query_config() -> web::QueryConfig {
+    web::QueryConfig::default().error_handler(|err, _| {
+        let QueryPayloadError::Deserialize(err) = err;
+        ApiError::coded(400, "request.invalid_query", err.to_string()).into()
+    })
+}
+
+pub async fn route_not_found(req: HttpRequest) -> Result<HttpResponse, ApiError> {
+    Err(ApiError::coded(404, "route.not_found", format!("No route for {} {}", req.method(), req.path())))
 }
\ No newline at end of file
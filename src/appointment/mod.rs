@@ -0,0 +1,7 @@
+pub mod model;
+pub mod recurrence;
+pub mod repository;
+pub mod v1;
+
+pub use model::*;
+pub use repository::*;
\ No newline at end of file
@@ -0,0 +1,607 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::config::CONFIG;
+use crate::{schema::appointment, schema::appointment_segment, schema::appointment_segment_add_on, schema::appointment_segment_resource, schema::appointment_series};
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::service::{BlockExtraTime, ServiceAddOn, ServiceQuote, ServiceVariant};
+use crate::staff::StaffHours;
+use crate::validation::{FieldErrors, Validate};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Utc};
+use diesel::pg::{Pg, PgConnection};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+
+use super::recurrence::Recurrence;
+use super::repository::{AppointmentRepository, PgAppointmentRepository};
+
+pub const APPOINTMENT_BOOKED: &str = "booked";
+pub const APPOINTMENT_CANCELLED: &str = "cancelled";
+pub const APPOINTMENT_COMPLETED: &str = "completed";
+
+pub const SERIES_ACTIVE: &str = "active";
+pub const SERIES_CANCELLED: &str = "cancelled";
+
+const APPOINTMENT_SORT_FIELDS: [&str; 2] = ["start_time", "appointment_id"];
+
+/// Minutes between the start times `availability` tries.
+const DEFAULT_INTERVAL_MINUTES: i64 = 15;
+
+#[derive(Deserialize)]
+pub struct AppointmentId {
+    pub appointment_id: i32
+}
+
+#[derive(Deserialize)]
+pub struct AppointmentSeriesId {
+    pub appointment_series_id: i32
+}
+
+#[derive(Deserialize)]
+pub struct SeriesOccurrencePath {
+    pub appointment_series_id: i32,
+    pub occurrence_time: NaiveDateTime
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SeriesScopeQuery {
+    /// `this` (default), `following` or `all`
+    pub scope: Option<String>
+}
+
+/// Which occurrences of a series an edit or cancellation applies to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SeriesScope {
+    /// Only the occurrence named in the path
+    This,
+    /// That occurrence and every later one
+    Following,
+    /// Every occurrence that has not started yet
+    All
+}
+
+impl SeriesScopeQuery {
+    pub fn scope(&self) -> Result<SeriesScope, ApiError> {
+        match self.scope.as_deref() {
+            None | Some("this") => Ok(SeriesScope::This),
+            Some("following") => Ok(SeriesScope::Following),
+            Some("all") => Ok(SeriesScope::All),
+            Some(_) => {
+                let mut errors = FieldErrors::default();
+                errors.add("scope", "must be this, following or all");
+                errors.into_result().map(|_| SeriesScope::This)
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AppointmentQuery {
+    pub customer_id: Option<i32>,
+    /// Appointments with at least one segment for this staff member
+    pub staff_id: Option<i32>,
+    /// `booked` or `cancelled`
+    pub status: Option<String>,
+    /// Appointments starting at or after this time
+    pub from: Option<NaiveDateTime>,
+    /// Appointments starting before this time
+    pub to: Option<NaiveDateTime>
+}
+
+impl AppointmentQuery {
+    fn filtered(&self) -> appointment::BoxedQuery<'static, Pg> {
+        let mut query = appointment::table.into_boxed();
+
+        if let Some(customer) = self.customer_id {
+            query = query.filter(appointment::customer_id.eq(customer));
+        }
+
+        if let Some(staff) = self.staff_id {
+            query = query.filter(appointment::appointment_id.eq_any(
+                appointment_segment::table
+                    .filter(appointment_segment::staff_id.eq(staff))
+                    .select(appointment_segment::appointment_id)
+            ));
+        }
+
+        if let Some(current_status) = &self.status {
+            query = query.filter(appointment::status.eq(current_status.clone()));
+        }
+
+        if let Some(from) = self.from {
+            query = query.filter(appointment::start_time.ge(from));
+        }
+
+        if let Some(to) = self.to {
+            query = query.filter(appointment::start_time.lt(to));
+        }
+
+        query
+    }
+
+    pub(super) fn find_page(&self, page: &Pagination, conn: &PgConnection) -> Result<(Vec<Appointment>, i64), ApiError> {
+        let sort = page.sort(&APPOINTMENT_SORT_FIELDS)?;
+        let total = self.filtered().count().get_result::<i64>(conn)?;
+
+        let query = match (sort.field.as_str(), sort.descending) {
+            ("start_time", false) => self.filtered().order(appointment::start_time.asc()),
+            ("start_time", true) => self.filtered().order(appointment::start_time.desc()),
+            (_, true) => self.filtered().order(appointment::appointment_id.desc()),
+            (_, false) => self.filtered().order(appointment::appointment_id.asc()),
+        };
+
+        let appointments = query
+            .then_order_by(appointment::appointment_id.asc())
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<Appointment>(conn)?;
+
+        Ok((appointments, total))
+    }
+}
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[primary_key(appointment_id)]
+#[table_name = "appointment"]
+pub struct Appointment {
+    pub appointment_id: i32,
+    pub customer_id: i32,
+    pub status: String,
+    /// Start of the first segment
+    pub start_time: NaiveDateTime,
+    /// End of the last segment
+    pub end_time: NaiveDateTime,
+    /// Every segment with its add-ons
+    pub price: f64,
+    pub created_at: NaiveDateTime,
+    /// Set when the appointment is one occurrence of a recurring series
+    pub appointment_series_id: Option<i32>,
+    /// The occurrence of the series this appointment stands for, even after
+    /// it was moved to another time
+    pub occurrence_time: Option<NaiveDateTime>,
+    /// The store whose resources the visit holds
+    pub store_id: Option<i32>
+}
+
+#[derive(Insertable)]
+#[table_name = "appointment"]
+pub struct AppointmentCreate {
+    pub customer_id: i32,
+    pub status: String,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub price: f64,
+    pub appointment_series_id: Option<i32>,
+    pub occurrence_time: Option<NaiveDateTime>,
+    pub store_id: Option<i32>
+}
+
+/// One service variant performed by one staff member. `blocked_from` and
+/// `blocked_until` add the service's before and after padding; that whole
+/// span is what other bookings for the staff member may not overlap.
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[belongs_to(Appointment)]
+#[primary_key(appointment_segment_id)]
+#[table_name = "appointment_segment"]
+pub struct AppointmentSegment {
+    pub appointment_segment_id: i32,
+    pub appointment_id: i32,
+    pub position: i32,
+    pub staff_id: i32,
+    pub service_variant_id: i32,
+    /// Variant price plus add-ons
+    pub price: f64,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub blocked_from: NaiveDateTime,
+    pub blocked_until: NaiveDateTime
+}
+
+#[derive(Insertable)]
+#[table_name = "appointment_segment"]
+pub struct AppointmentSegmentCreate {
+    pub appointment_id: i32,
+    pub position: i32,
+    pub staff_id: i32,
+    pub service_variant_id: i32,
+    pub price: f64,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub blocked_from: NaiveDateTime,
+    pub blocked_until: NaiveDateTime
+}
+
+/// An add-on booked with a segment, at the price it had when booked.
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[belongs_to(AppointmentSegment)]
+#[primary_key(appointment_segment_add_on_id)]
+#[table_name = "appointment_segment_add_on"]
+pub struct AppointmentSegmentAddOn {
+    pub appointment_segment_add_on_id: i32,
+    pub appointment_segment_id: i32,
+    pub service_add_on_id: i32,
+    pub price: f64
+}
+
+#[derive(Insertable)]
+#[table_name = "appointment_segment_add_on"]
+pub struct AppointmentSegmentAddOnCreate {
+    pub appointment_segment_id: i32,
+    pub service_add_on_id: i32,
+    pub price: f64
+}
+
+/// A resource a segment holds for its whole blocked span.
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[belongs_to(AppointmentSegment)]
+#[primary_key(appointment_segment_resource_id)]
+#[table_name = "appointment_segment_resource"]
+pub struct AppointmentSegmentResource {
+    pub appointment_segment_resource_id: i32,
+    pub appointment_segment_id: i32,
+    pub resource_id: i32
+}
+
+#[derive(Insertable)]
+#[table_name = "appointment_segment_resource"]
+pub struct AppointmentSegmentResourceCreate {
+    pub appointment_segment_id: i32,
+    pub resource_id: i32
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FullAppointmentSegment {
+    pub segment: AppointmentSegment,
+    pub add_ons: Vec<AppointmentSegmentAddOn>,
+    pub resources: Vec<AppointmentSegmentResource>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FullAppointment {
+    pub appointment: Appointment,
+    pub segments: Vec<FullAppointmentSegment>
+}
+
+#[derive(Serialize, Deserialize, Clone, ToSchema)]
+pub struct BookSegment {
+    pub service_variant_id: i32,
+    pub staff_id: i32,
+    #[serde(default)]
+    pub add_on_ids: Vec<i32>
+}
+
+/// A visit of one or more services back to back. Each segment starts when
+/// the one before it ends, later if the same staff member needs their
+/// padding in between.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AppointmentBook {
+    pub customer_id: i32,
+    /// Required when a service needs resources; they are taken from this store
+    #[serde(default)]
+    pub store_id: Option<i32>,
+    pub start_time: NaiveDateTime,
+    pub segments: Vec<BookSegment>
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct AvailabilityRequest {
+    pub date: NaiveDate,
+    /// Required when a service needs resources
+    #[serde(default)]
+    pub store_id: Option<i32>,
+    pub segments: Vec<BookSegment>,
+    /// Minutes between candidate start times (default 15)
+    pub interval_minutes: Option<i64>
+}
+
+impl Validate for AppointmentBook {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.segments.is_empty() {
+            errors.add("segments", "must contain at least one service");
+        }
+    }
+}
+
+impl Validate for AvailabilityRequest {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.segments.is_empty() {
+            errors.add("segments", "must contain at least one service");
+        }
+
+        if let Some(interval) = self.interval_minutes {
+            if !(5..=240).contains(&interval) {
+                errors.add("interval_minutes", "must be between 5 and 240");
+            }
+        }
+    }
+}
+
+/// A visit that repeats on a recurrence rule. Occurrences are booked as
+/// ordinary appointments once they fall within the booking horizon; one that
+/// conflicts with the calendars then is skipped and reported, not booked.
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[primary_key(appointment_series_id)]
+#[table_name = "appointment_series"]
+pub struct AppointmentSeries {
+    pub appointment_series_id: i32,
+    pub customer_id: i32,
+    /// `active` or `cancelled`
+    pub status: String,
+    /// The first occurrence; later ones keep its time of day
+    pub start_time: NaiveDateTime,
+    /// e.g. `FREQ=WEEKLY;INTERVAL=4;BYDAY=TU`
+    pub rrule: String,
+    #[schema(value_type = Vec<BookSegment>)]
+    pub segments: Value,
+    /// Occurrences cancelled on their own
+    pub excluded_times: Vec<NaiveDateTime>,
+    /// Occurrences up to this time have been booked or skipped
+    pub materialized_until: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub store_id: Option<i32>
+}
+
+#[derive(Insertable)]
+#[table_name = "appointment_series"]
+pub struct AppointmentSeriesCreate {
+    pub customer_id: i32,
+    pub status: String,
+    pub start_time: NaiveDateTime,
+    pub rrule: String,
+    pub segments: Value,
+    pub store_id: Option<i32>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FullAppointmentSeries {
+    pub series: AppointmentSeries,
+    /// Booked and cancelled occurrences so far, in start time order
+    pub appointments: Vec<Appointment>
+}
+
+/// e.g. `{ "customer_id": 1, "start_time": "2020-11-03T10:00:00",
+/// "rrule": "FREQ=WEEKLY;INTERVAL=4;BYDAY=TU", "segments": [...] }`
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SeriesBook {
+    pub customer_id: i32,
+    #[serde(default)]
+    pub store_id: Option<i32>,
+    pub start_time: NaiveDateTime,
+    pub rrule: String,
+    pub segments: Vec<BookSegment>
+}
+
+/// New time and services for the occurrences in scope. With `following`
+/// and `all`, `start_time` becomes the first occurrence of the changed
+/// series and `rrule` replaces the rule when given.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct SeriesChange {
+    pub start_time: NaiveDateTime,
+    pub rrule: Option<String>,
+    pub segments: Vec<BookSegment>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SkippedOccurrence {
+    pub appointment_series_id: i32,
+    pub occurrence_time: NaiveDateTime,
+    /// Same shape as a 409 or 422 response's `errors`
+    #[schema(value_type = Object)]
+    pub errors: BTreeMap<String, Vec<String>>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct MaterializeReport {
+    /// Series that had occurrences to book
+    pub series: usize,
+    pub booked: usize,
+    pub skipped: Vec<SkippedOccurrence>
+}
+
+impl Validate for SeriesBook {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.segments.is_empty() {
+            errors.add("segments", "must contain at least one service");
+        }
+
+        if let Err(message) = self.rrule.parse::<Recurrence>() {
+            errors.add("rrule", &message);
+        }
+    }
+}
+
+impl Validate for SeriesChange {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if self.segments.is_empty() {
+            errors.add("segments", "must contain at least one service");
+        }
+
+        if let Some(Err(message)) = self.rrule.as_ref().map(|rrule| rrule.parse::<Recurrence>()) {
+            errors.add("rrule", &message);
+        }
+    }
+}
+
+impl AppointmentSeries {
+    pub fn recurrence(&self) -> Result<Recurrence, ApiError> {
+        self.rrule.parse()
+            .map_err(|message| ApiError::new(500, format!("Series {} has an invalid rule: {}", self.appointment_series_id, message)))
+    }
+
+    /// The visit booked for the occurrence at `start_time`.
+    pub fn booking(&self, start_time: NaiveDateTime) -> Result<AppointmentBook, ApiError> {
+        let segments = serde_json::from_value(self.segments.clone())
+            .map_err(|err| ApiError::new(500, format!("Series {} has invalid segments: {}", self.appointment_series_id, err)))?;
+
+        Ok(AppointmentBook { customer_id: self.customer_id, store_id: self.store_id, start_time, segments })
+    }
+
+    /// Whether the rule puts an occurrence at `occurrence_time` that has not
+    /// been cancelled on its own.
+    pub fn has_occurrence(&self, occurrence_time: NaiveDateTime) -> Result<bool, ApiError> {
+        let occurs = self.recurrence()?
+            .occurrences(self.start_time, occurrence_time)
+            .last() == Some(&occurrence_time);
+
+        Ok(occurs && !self.excluded_times.contains(&occurrence_time))
+    }
+}
+
+/// How far ahead series occurrences are booked, from now.
+pub fn series_horizon() -> NaiveDateTime {
+    Utc::now().naive_utc() + Duration::days(CONFIG.appointment.series_horizon_days)
+}
+
+/// A requested segment with its staff member and computed times, before it
+/// is stored.
+pub struct PlannedSegment {
+    pub staff_id: i32,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub blocked_from: NaiveDateTime,
+    pub blocked_until: NaiveDateTime,
+    pub quote: ServiceQuote,
+    /// One resource of each type is held for the blocked span
+    pub resource_types: Vec<String>
+}
+
+/// The span a staff member is blocked for one planned segment.
+#[derive(Clone, Copy)]
+pub struct BlockedSpan {
+    pub index: usize,
+    pub staff_id: i32,
+    pub from: NaiveDateTime,
+    pub until: NaiveDateTime
+}
+
+impl PlannedSegment {
+    pub fn new(staff_id: i32, variant: &ServiceVariant, blocked_time: &BlockExtraTime, add_ons: Vec<ServiceAddOn>, start_time: NaiveDateTime) -> PlannedSegment {
+        let quote = ServiceQuote::new(variant, blocked_time, add_ons, Some(start_time));
+
+        PlannedSegment {
+            staff_id,
+            start_time,
+            end_time: quote.end_time.unwrap_or(start_time),
+            blocked_from: quote.blocked_from.unwrap_or(start_time),
+            blocked_until: quote.blocked_until.unwrap_or(start_time),
+            quote,
+            resource_types: vec![]
+        }
+    }
+
+    pub fn span(&self, index: usize) -> BlockedSpan {
+        BlockedSpan { index, staff_id: self.staff_id, from: self.blocked_from, until: self.blocked_until }
+    }
+}
+
+/// Time a staff member is already committed to, by a booked appointment
+/// segment or a scheduled class session.
+pub struct BusySpan {
+    pub staff_id: i32,
+    pub from: NaiveDateTime,
+    pub until: NaiveDateTime
+}
+
+impl BlockedSpan {
+    pub fn shifted(&self, by: Duration) -> BlockedSpan {
+        BlockedSpan { from: self.from + by, until: self.until + by, ..*self }
+    }
+
+    /// Reports the span under `segments[index]` if it falls outside the staff
+    /// member's hours that day or overlaps one of their other bookings.
+    pub fn check(&self, hours: &[StaffHours], busy: &[BusySpan], errors: &mut FieldErrors) {
+        let key = format!("segments[{}]", self.index);
+
+        for message in self.conflicts(hours, busy) {
+            errors.add(&key, message);
+        }
+    }
+
+    pub fn conflicts(&self, hours: &[StaffHours], busy: &[BusySpan]) -> Vec<&'static str> {
+        let mut messages = vec![];
+        let day_of_week = self.from.weekday().num_days_from_sunday() as i32;
+
+        let working = hours.iter()
+            .filter(|day| day.staff_id == self.staff_id && day.day_of_week == day_of_week)
+            .any(|day| match (day.start_time, day.end_time) {
+                (Some(start), Some(end)) => {
+                    self.from.date() == self.until.date() && self.from.time() >= start && self.until.time() <= end
+                },
+                _ => false,
+            });
+
+        if !working {
+            messages.push("is outside the staff member's hours");
+        }
+
+        let overlaps = busy.iter()
+            .any(|other| other.staff_id == self.staff_id && other.from < self.until && other.until > self.from);
+
+        if overlaps {
+            messages.push("overlaps another booking for the staff member");
+        }
+
+        messages
+    }
+}
+
+impl AvailabilityRequest {
+    pub fn interval(&self) -> Duration {
+        Duration::minutes(self.interval_minutes.unwrap_or(DEFAULT_INTERVAL_MINUTES))
+    }
+}
+
+/// Shortcuts that run `AppointmentRepository` on a connection from the server pool.
+impl Appointment {
+    pub fn find_all(appointment_query: AppointmentQuery, page: &Pagination) -> Result<(Vec<FullAppointment>, i64), ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).find_all(appointment_query, page)
+    }
+
+    pub fn find(id: i32) -> QueryResult<FullAppointment> {
+        PgAppointmentRepository::new(&db::establish_connection()).find(id)
+    }
+
+    pub fn book(booking: AppointmentBook, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).book(booking, actor)
+    }
+
+    pub fn cancel(id: i32, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).cancel(id, actor)
+    }
+
+    pub fn complete(id: i32, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).complete(id, actor)
+    }
+
+    pub fn availability(request: AvailabilityRequest) -> Result<Vec<NaiveDateTime>, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).availability(request)
+    }
+}
+
+/// Shortcuts that run `AppointmentRepository` on a connection from the server pool.
+impl AppointmentSeries {
+    pub fn find(id: i32) -> QueryResult<FullAppointmentSeries> {
+        PgAppointmentRepository::new(&db::establish_connection()).find_series(id)
+    }
+
+    pub fn book(request: SeriesBook, actor: &Actor) -> Result<FullAppointmentSeries, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).book_series(request, actor)
+    }
+
+    pub fn change(id: i32, occurrence_time: NaiveDateTime, scope: SeriesScope, change: SeriesChange, actor: &Actor) -> Result<FullAppointmentSeries, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).change_series(id, occurrence_time, scope, change, actor)
+    }
+
+    pub fn cancel(id: i32, occurrence_time: NaiveDateTime, scope: SeriesScope, actor: &Actor) -> Result<FullAppointmentSeries, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).cancel_series(id, occurrence_time, scope, actor)
+    }
+
+    pub fn materialize(actor: &Actor) -> Result<MaterializeReport, ApiError> {
+        PgAppointmentRepository::new(&db::establish_connection()).materialize_series(series_horizon(), actor)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,287 @@
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, Weekday};
+use std::fmt;
+use std::str::FromStr;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun)
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly
+}
+
+/// The subset of an iCalendar RRULE a salon needs, e.g.
+/// `FREQ=WEEKLY;INTERVAL=4;BYDAY=TU;COUNT=6`.
+///
+/// Occurrences keep the time of day of the series start. Weeks start on
+/// Monday, and a monthly rule skips months without the start's day.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub frequency: Frequency,
+    pub interval: u32,
+    /// Weekly rules only; the start's weekday when empty
+    pub by_day: Vec<Weekday>,
+    pub count: Option<u32>,
+    pub until: Option<NaiveDateTime>
+}
+
+impl FromStr for Recurrence {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Recurrence, String> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = vec![];
+        let mut count = None;
+        let mut until = None;
+
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let mut pair = part.splitn(2, '=');
+            let name = pair.next().unwrap_or_default().to_uppercase();
+            let value = pair.next().ok_or_else(|| format!("{} has no value", name))?.trim();
+
+            match name.as_str() {
+                "FREQ" => frequency = Some(match value {
+                    "DAILY" => Frequency::Daily,
+                    "WEEKLY" => Frequency::Weekly,
+                    "MONTHLY" => Frequency::Monthly,
+                    other => return Err(format!("FREQ={} is not supported, expected DAILY, WEEKLY or MONTHLY", other)),
+                }),
+                "INTERVAL" => interval = value.parse::<u32>().ok()
+                    .filter(|interval| *interval >= 1)
+                    .ok_or_else(|| format!("INTERVAL={} must be a whole number of at least 1", value))?,
+                "BYDAY" => {
+                    for day in value.split(',') {
+                        let weekday = WEEKDAYS.iter()
+                            .find(|(code, _)| *code == day.trim())
+                            .map(|(_, weekday)| *weekday)
+                            .ok_or_else(|| format!("BYDAY={} is not a list of MO, TU, WE, TH, FR, SA or SU", value))?;
+
+                        if !by_day.contains(&weekday) {
+                            by_day.push(weekday);
+                        }
+                    }
+                },
+                "COUNT" => count = Some(value.parse::<u32>().ok()
+                    .filter(|count| *count >= 1)
+                    .ok_or_else(|| format!("COUNT={} must be a whole number of at least 1", value))?),
+                "UNTIL" => until = Some(parse_until(value).ok_or_else(|| format!("UNTIL={} must look like 20201231 or 20201231T170000", value))?),
+                other => return Err(format!("{} is not supported", other)),
+            }
+        }
+
+        let frequency = frequency.ok_or_else(|| "FREQ is required".to_string())?;
+
+        if count.is_some() && until.is_some() {
+            return Err("COUNT and UNTIL cannot both be set".to_string());
+        }
+
+        if !by_day.is_empty() && frequency != Frequency::Weekly {
+            return Err("BYDAY is only supported with FREQ=WEEKLY".to_string());
+        }
+
+        by_day.sort_by_key(|weekday| weekday.num_days_from_monday());
+
+        Ok(Recurrence { frequency, interval, by_day, count, until })
+    }
+}
+
+/// A date alone means the end of that day.
+fn parse_until(value: &str) -> Option<NaiveDateTime> {
+    let value = value.trim_end_matches('Z');
+
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S").ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y%m%d").ok().map(|date| date.and_hms(23, 59, 59)))
+}
+
+impl fmt::Display for Recurrence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+        };
+
+        write!(f, "FREQ={}", frequency)?;
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+
+        if !self.by_day.is_empty() {
+            let days: Vec<&str> = self.by_day.iter()
+                .filter_map(|weekday| WEEKDAYS.iter().find(|(_, day)| day == weekday).map(|(code, _)| *code))
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%S"))?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Recurrence {
+    /// Occurrences of a series starting at `start`, in order, up to and
+    /// including `through`. `COUNT` counts from `start` whatever `through` is.
+    pub fn occurrences(&self, start: NaiveDateTime, through: NaiveDateTime) -> Vec<NaiveDateTime> {
+        let last = match self.until {
+            Some(until) if until < through => until,
+            _ => through,
+        };
+
+        let mut occurrences = vec![];
+        let interval = i64::from(self.interval);
+
+        for period in 0.. {
+            // the first moment of the period, and the occurrences in it
+            let (period_start, candidates) = match self.frequency {
+                Frequency::Daily => {
+                    let day = start + Duration::days(period * interval);
+                    (day, vec![day])
+                },
+                Frequency::Weekly => {
+                    let week_start = start.date() - Duration::days(i64::from(start.weekday().num_days_from_monday())) + Duration::weeks(period * interval);
+                    let days = match self.by_day.is_empty() {
+                        true => vec![start.weekday()],
+                        false => self.by_day.clone(),
+                    };
+
+                    let candidates = days.iter()
+                        .map(|day| (week_start + Duration::days(i64::from(day.num_days_from_monday()))).and_time(start.time()))
+                        .collect();
+
+                    (week_start.and_hms(0, 0, 0), candidates)
+                },
+                Frequency::Monthly => {
+                    let month = i64::from(start.month0()) + period * interval;
+                    let year = start.year() + (month / 12) as i32;
+                    let month = (month % 12) as u32 + 1;
+
+                    let candidates = NaiveDate::from_ymd_opt(year, month, start.day())
+                        .map(|date| vec![date.and_time(start.time())])
+                        .unwrap_or_default();
+
+                    (NaiveDate::from_ymd(year, month, 1).and_hms(0, 0, 0), candidates)
+                },
+            };
+
+            if period_start > last {
+                break;
+            }
+
+            for candidate in candidates.into_iter().filter(|candidate| *candidate >= start) {
+                if candidate > last || self.count.is_some_and(|count| occurrences.len() as u32 >= count) {
+                    return occurrences;
+                }
+
+                occurrences.push(candidate);
+            }
+        }
+
+        occurrences
+    }
+
+    /// The same rule for a series moved `days` later (or earlier), so
+    /// `BYDAY=TU` moved one day becomes `BYDAY=WE`.
+    pub fn moved(&self, days: i64) -> Recurrence {
+        let mut by_day: Vec<Weekday> = self.by_day.iter()
+            .map(|weekday| {
+                let shifted = (i64::from(weekday.num_days_from_monday()) + days).rem_euclid(7);
+                WEEKDAYS[shifted as usize].1
+            })
+            .collect();
+        by_day.sort_by_key(|weekday| weekday.num_days_from_monday());
+
+        Recurrence { by_day, ..self.clone() }
+    }
+
+    /// Splits the series at `occurrence`: the rule for the occurrences before
+    /// it, and the rule for the rest when they start over from `occurrence`.
+    pub fn split(&self, start: NaiveDateTime, occurrence: NaiveDateTime) -> (Recurrence, Recurrence) {
+        let before_until = occurrence - Duration::seconds(1);
+        let earlier = self.occurrences(start, before_until).len() as u32;
+
+        let before = Recurrence { count: None, until: Some(before_until), ..self.clone() };
+        let after = Recurrence { count: self.count.map(|count| count.saturating_sub(earlier).max(1)), ..self.clone() };
+
+        (before, after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(value: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M").unwrap()
+    }
+
+    #[test]
+    fn every_four_weeks_on_tuesday() {
+        let rule: Recurrence = "RRULE:FREQ=WEEKLY;INTERVAL=4;BYDAY=TU;COUNT=3".parse().unwrap();
+        let occurrences = rule.occurrences(at("2020-11-03T10:00"), at("2021-12-31T00:00"));
+
+        assert_eq!(occurrences, vec![at("2020-11-03T10:00"), at("2020-12-01T10:00"), at("2020-12-29T10:00")]);
+        assert_eq!(rule.to_string(), "FREQ=WEEKLY;INTERVAL=4;BYDAY=TU;COUNT=3");
+    }
+
+    #[test]
+    fn weekly_days_before_the_start_wait_for_the_next_week() {
+        let rule: Recurrence = "FREQ=WEEKLY;BYDAY=FR,MO;UNTIL=20201110".parse().unwrap();
+        let occurrences = rule.occurrences(at("2020-11-04T09:00"), at("2021-01-01T00:00"));
+
+        assert_eq!(occurrences, vec![at("2020-11-06T09:00"), at("2020-11-09T09:00")]);
+    }
+
+    #[test]
+    fn monthly_skips_short_months() {
+        let rule: Recurrence = "FREQ=MONTHLY;COUNT=3".parse().unwrap();
+        let occurrences = rule.occurrences(at("2021-01-31T12:00"), at("2022-01-01T00:00"));
+
+        assert_eq!(occurrences, vec![at("2021-01-31T12:00"), at("2021-03-31T12:00"), at("2021-05-31T12:00")]);
+    }
+
+    #[test]
+    fn split_carries_the_remaining_count() {
+        let rule: Recurrence = "FREQ=DAILY;COUNT=5".parse().unwrap();
+        let (before, after) = rule.split(at("2020-11-01T10:00"), at("2020-11-03T10:00"));
+
+        assert_eq!(before.occurrences(at("2020-11-01T10:00"), at("2021-01-01T00:00")).len(), 2);
+        assert_eq!(after.occurrences(at("2020-11-03T15:00"), at("2021-01-01T00:00")).len(), 3);
+    }
+
+    #[test]
+    fn moved_shifts_the_weekdays() {
+        let rule: Recurrence = "FREQ=WEEKLY;BYDAY=MO,SU".parse().unwrap();
+
+        assert_eq!(rule.moved(1).to_string(), "FREQ=WEEKLY;BYDAY=MO,TU");
+        assert_eq!(rule.moved(-8).to_string(), "FREQ=WEEKLY;BYDAY=SA,SU");
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        assert!("FREQ=YEARLY".parse::<Recurrence>().is_err());
+        assert!("FREQ=DAILY;COUNT=2;UNTIL=20201231".parse::<Recurrence>().is_err());
+        assert!("FREQ=MONTHLY;BYDAY=MO".parse::<Recurrence>().is_err());
+        assert!("INTERVAL=2".parse::<Recurrence>().is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,930 @@
+use crate::api_error::ApiError;
+use crate::audit::{self, Actor};
+use crate::{
+    schema::appointment,
+    schema::appointment_segment,
+    schema::appointment_segment_add_on,
+    schema::appointment_segment_resource,
+    schema::appointment_series,
+    schema::block_extra_time,
+    schema::class_session,
+    schema::resource,
+    schema::service,
+    schema::service_resource,
+    schema::service_variant,
+    schema::staff,
+    schema::staff_hours,
+    schema::staff_service
+};
+use crate::diesel::prelude::*;
+use crate::metrics;
+use crate::pagination::Pagination;
+use crate::class::CLASS_SCHEDULED;
+use crate::resource::{Resource, ResourceHold, ResourcePool};
+use crate::service::{BlockExtraTime, PgServiceRepository, Service, ServiceAddOn, ServiceRepository, ServiceVariant};
+use crate::staff::StaffHours;
+use crate::validation::FieldErrors;
+use crate::webhook;
+use chrono::{Duration, NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use tracing::debug_span;
+
+use super::model::*;
+use super::recurrence::Recurrence;
+
+/// Appointments and the staff calendars they are booked against.
+///
+/// Every call runs on the connection the repository was built with, so
+/// callers choose the database and can wrap several calls in one
+/// transaction.
+pub trait AppointmentRepository {
+    fn find_all(&self, appointment_query: AppointmentQuery, page: &Pagination) -> Result<(Vec<FullAppointment>, i64), ApiError>;
+
+    fn find(&self, id: i32) -> QueryResult<FullAppointment>;
+
+    /// Works out each segment's price and times from `start_time`, checking
+    /// that the variants exist, the staff perform them and the add-ons
+    /// belong to them. Calendars are not checked.
+    fn plan(&self, start_time: NaiveDateTime, segments: &[BookSegment]) -> Result<Vec<PlannedSegment>, ApiError>;
+
+    /// Books every segment, or nothing with a 409 naming the segments that
+    /// fall outside their staff member's hours or overlap another booking.
+    fn book(&self, booking: AppointmentBook, actor: &Actor) -> Result<FullAppointment, ApiError>;
+
+    fn cancel(&self, id: i32, actor: &Actor) -> Result<FullAppointment, ApiError>;
+
+    /// Marks a booked appointment as having taken place. Its time stays
+    /// blocked, and a cancelled appointment cannot be completed.
+    fn complete(&self, id: i32, actor: &Actor) -> Result<FullAppointment, ApiError>;
+
+    /// Start times on `request.date` at which every segment can be booked.
+    fn availability(&self, request: AvailabilityRequest) -> Result<Vec<NaiveDateTime>, ApiError>;
+
+    fn find_series(&self, id: i32) -> QueryResult<FullAppointmentSeries>;
+
+    /// Creates the series and books its occurrences within the horizon, or
+    /// nothing with a 409 naming every occurrence that does not fit.
+    fn book_series(&self, request: SeriesBook, actor: &Actor) -> Result<FullAppointmentSeries, ApiError>;
+
+    /// Moves the occurrences in `scope`. `following` splits the series at
+    /// `occurrence_time` and returns the new one; `this` and `all` keep it.
+    fn change_series(&self, id: i32, occurrence_time: NaiveDateTime, scope: SeriesScope, change: SeriesChange, actor: &Actor) -> Result<FullAppointmentSeries, ApiError>;
+
+    fn cancel_series(&self, id: i32, occurrence_time: NaiveDateTime, scope: SeriesScope, actor: &Actor) -> Result<FullAppointmentSeries, ApiError>;
+
+    /// Books occurrences of every active series up to `through`, skipping
+    /// and reporting the ones that no longer fit the calendars.
+    fn materialize_series(&self, through: NaiveDateTime, actor: &Actor) -> Result<MaterializeReport, ApiError>;
+}
+
+pub struct PgAppointmentRepository<'a> {
+    conn: &'a PgConnection
+}
+
+impl<'a> PgAppointmentRepository<'a> {
+    pub fn new(conn: &'a PgConnection) -> PgAppointmentRepository<'a> {
+        PgAppointmentRepository { conn }
+    }
+}
+
+impl<'a> AppointmentRepository for PgAppointmentRepository<'a> {
+    fn find_all(&self, appointment_query: AppointmentQuery, page: &Pagination) -> Result<(Vec<FullAppointment>, i64), ApiError> {
+        let span = debug_span!("Appointment::find_all");
+        let _entered = span.enter();
+
+        let (appointments, total) = appointment_query.find_page(page, self.conn)?;
+
+        Ok((self.with_segments(appointments)?, total))
+    }
+
+    fn find(&self, id: i32) -> QueryResult<FullAppointment> {
+        let span = debug_span!("Appointment::find");
+        let _entered = span.enter();
+
+        let current_appointment = appointment::table
+            .filter(appointment::appointment_id.eq(id))
+            .first::<Appointment>(self.conn)?;
+
+        self.with_segments(vec![current_appointment])?
+            .pop()
+            .ok_or(diesel::result::Error::NotFound)
+    }
+
+    fn plan(&self, start_time: NaiveDateTime, segments: &[BookSegment]) -> Result<Vec<PlannedSegment>, ApiError> {
+        let span = debug_span!("Appointment::plan");
+        let _entered = span.enter();
+
+        let services = PgServiceRepository::new(self.conn);
+        let mut errors = FieldErrors::default();
+        let mut planned: Vec<PlannedSegment> = vec![];
+        let mut next_start = start_time;
+
+        for (index, segment) in segments.iter().enumerate() {
+            let key = format!("segments[{}]", index);
+
+            let variant = service_variant::table
+                .filter(service_variant::service_variant_id.eq(segment.service_variant_id))
+                .first::<ServiceVariant>(self.conn)
+                .optional()?;
+
+            let variant = match variant {
+                Some(variant) => variant,
+                None => {
+                    errors.add(&format!("{}.service_variant_id", key), "does not exist");
+                    continue;
+                },
+            };
+
+            // find_add_ons only finds services that are not deleted
+            let available = match services.find_add_ons(variant.service_id).optional()? {
+                Some(available) => available,
+                None => {
+                    errors.add(&format!("{}.service_variant_id", key), "belongs to a deleted service");
+                    continue;
+                },
+            };
+
+            let current_service = service::table
+                .filter(service::service_id.eq(variant.service_id))
+                .first::<Service>(self.conn)?;
+
+            if variant.capacity_of(&current_service) > 1 {
+                errors.add(&format!("{}.service_variant_id", key), "is a group class; enroll in one of its class sessions instead");
+                continue;
+            }
+
+            let performs = staff_service::table
+                .inner_join(staff::table)
+                .filter(staff_service::staff_id.eq(segment.staff_id))
+                .filter(staff_service::service_variant_id.eq(variant.service_variant_id))
+                .filter(staff_service::is_active.eq(1))
+                .filter(staff::deleted_at.is_null())
+                .count()
+                .get_result::<i64>(self.conn)? > 0;
+
+            if !performs {
+                errors.add(&format!("{}.staff_id", key), "does not perform this service variant");
+            }
+
+            let add_ons = ServiceAddOn::select(&available, &segment.add_on_ids, &format!("{}.add_on_ids", key), &mut errors);
+            let blocked_time = block_extra_time::table
+                .filter(block_extra_time::service_id.eq(variant.service_id))
+                .first::<BlockExtraTime>(self.conn)?;
+
+            let mut planned_segment = PlannedSegment::new(segment.staff_id, &variant, &blocked_time, add_ons, next_start);
+
+            // the same staff member needs their padding between two segments
+            let free_at = planned.iter()
+                .filter(|earlier| earlier.staff_id == segment.staff_id)
+                .map(|earlier| earlier.blocked_until)
+                .max();
+
+            if let Some(free_at) = free_at {
+                if planned_segment.blocked_from < free_at {
+                    let start_time = next_start + (free_at - planned_segment.blocked_from);
+                    planned_segment = PlannedSegment::new(segment.staff_id, &variant, &blocked_time, planned_segment.quote.add_ons, start_time);
+                }
+            }
+
+            planned_segment.resource_types = service_resource::table
+                .filter(service_resource::service_id.eq(variant.service_id))
+                .order(service_resource::resource_type.asc())
+                .select(service_resource::resource_type)
+                .load::<String>(self.conn)?;
+
+            next_start = planned_segment.end_time;
+            planned.push(planned_segment);
+        }
+
+        errors.into_result()?;
+
+        Ok(planned)
+    }
+
+    fn book(&self, booking: AppointmentBook, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        let span = debug_span!("Appointment::book");
+        let _entered = span.enter();
+
+        let full_appointment = self.conn.transaction::<_, ApiError, _>(|| {
+            let full_appointment = self.insert_booking(&booking, None, actor)?;
+            webhook::emit(self.conn, webhook::APPOINTMENT_CREATED, json!(full_appointment));
+
+            Ok(full_appointment)
+        })?;
+
+        metrics::appointments_booked("appointment", 1);
+
+        Ok(full_appointment)
+    }
+
+    fn cancel(&self, id: i32, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        let span = debug_span!("Appointment::cancel");
+        let _entered = span.enter();
+
+        let (full_appointment, cancelled) = self.conn.transaction::<_, ApiError, _>(|| {
+            let appointment_before = appointment::table
+                .filter(appointment::appointment_id.eq(id))
+                .for_update()
+                .first::<Appointment>(self.conn)?;
+
+            if appointment_before.status == APPOINTMENT_CANCELLED {
+                return Ok((self.find(id)?, false));
+            }
+
+            if appointment_before.status == APPOINTMENT_COMPLETED {
+                return Err(ApiError::coded(409, "appointment.completed", "A completed appointment cannot be cancelled".to_string()));
+            }
+
+            self.set_cancelled(&appointment_before, actor)?;
+
+            let full_appointment = self.find(id)?;
+            webhook::emit(self.conn, webhook::APPOINTMENT_CANCELLED, json!(full_appointment));
+
+            Ok((full_appointment, true))
+        })?;
+
+        if cancelled {
+            metrics::appointments_cancelled("appointment", 1);
+        }
+
+        Ok(full_appointment)
+    }
+
+    fn complete(&self, id: i32, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        let span = debug_span!("Appointment::complete");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let appointment_before = appointment::table
+                .filter(appointment::appointment_id.eq(id))
+                .for_update()
+                .first::<Appointment>(self.conn)?;
+
+            if appointment_before.status == APPOINTMENT_COMPLETED {
+                return Ok(self.find(id)?);
+            }
+
+            if appointment_before.status == APPOINTMENT_CANCELLED {
+                return Err(ApiError::coded(409, "appointment.cancelled", "A cancelled appointment cannot be completed".to_string()));
+            }
+
+            let appointment_completed: Appointment = diesel::update(appointment::table)
+                .filter(appointment::appointment_id.eq(id))
+                .set(appointment::status.eq(APPOINTMENT_COMPLETED))
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "appointment", id, &appointment_before, &appointment_completed)?;
+
+            let full_appointment = self.find(id)?;
+            webhook::emit(self.conn, webhook::APPOINTMENT_COMPLETED, json!(full_appointment));
+
+            Ok(full_appointment)
+        })
+    }
+
+    fn availability(&self, request: AvailabilityRequest) -> Result<Vec<NaiveDateTime>, ApiError> {
+        let span = debug_span!("Appointment::availability");
+        let _entered = span.enter();
+
+        // planned once from midnight; every other start time is the same
+        // visit moved later
+        let day_start = request.date.and_hms(0, 0, 0);
+        let planned = self.plan(day_start, &request.segments)?;
+        let spans: Vec<BlockedSpan> = planned.iter().enumerate().map(|(index, segment)| segment.span(index)).collect();
+        let staff_ids = staff_ids(&spans);
+        let (hours, busy) = self.calendar(&staff_ids, day_start - Duration::days(1), day_start + Duration::days(2))?;
+        let pool = self.resource_pool(request.store_id, &planned, day_start - Duration::days(1), day_start + Duration::days(2))?;
+
+        let mut start_times = vec![];
+        let mut offset = Duration::zero();
+
+        while offset < Duration::days(1) {
+            let mut errors = FieldErrors::default();
+
+            for span in &spans {
+                span.shifted(offset).check(&hours, &busy, &mut errors);
+            }
+
+            hold_resources(&mut pool.clone(), &planned, offset, &mut errors);
+
+            if errors.is_empty() {
+                start_times.push(day_start + offset);
+            }
+
+            offset = offset + request.interval();
+        }
+
+        Ok(start_times)
+    }
+
+    fn find_series(&self, id: i32) -> QueryResult<FullAppointmentSeries> {
+        let span = debug_span!("Appointment::find_series");
+        let _entered = span.enter();
+
+        let series = appointment_series::table
+            .filter(appointment_series::appointment_series_id.eq(id))
+            .first::<AppointmentSeries>(self.conn)?;
+
+        let appointments = appointment::table
+            .filter(appointment::appointment_series_id.eq(id))
+            .order((appointment::start_time.asc(), appointment::appointment_id.asc()))
+            .load::<Appointment>(self.conn)?;
+
+        Ok(FullAppointmentSeries { series, appointments })
+    }
+
+    fn book_series(&self, request: SeriesBook, actor: &Actor) -> Result<FullAppointmentSeries, ApiError> {
+        let span = debug_span!("Appointment::book_series");
+        let _entered = span.enter();
+
+        let full_series = self.conn.transaction::<_, ApiError, _>(|| {
+            // unknown variants or add-ons are a 422 for the request, not a
+            // conflict on every occurrence
+            self.plan(request.start_time, &request.segments)?;
+
+            let series_created: AppointmentSeries = diesel::insert_into(appointment_series::table)
+                .values(AppointmentSeriesCreate {
+                    customer_id: request.customer_id,
+                    status: SERIES_ACTIVE.to_string(),
+                    start_time: request.start_time,
+                    rrule: parse_rule(&request.rrule)?.to_string(),
+                    segments: json!(request.segments),
+                    store_id: request.store_id
+                })
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "appointment_series", series_created.appointment_series_id, &series_created)?;
+            self.materialize_or_conflict(&series_created, None, actor)?;
+
+            Ok(self.find_series(series_created.appointment_series_id)?)
+        })?;
+
+        metrics::appointments_booked("series", full_series.appointments.len());
+
+        Ok(full_series)
+    }
+
+    fn change_series(&self, id: i32, occurrence_time: NaiveDateTime, scope: SeriesScope, change: SeriesChange, actor: &Actor) -> Result<FullAppointmentSeries, ApiError> {
+        let span = debug_span!("Appointment::change_series");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let series = self.active_series(id, occurrence_time)?;
+            self.plan(change.start_time, &change.segments)?;
+
+            if scope == SeriesScope::This {
+                if change.rrule.is_some() {
+                    let mut errors = FieldErrors::default();
+                    errors.add("rrule", "can only be changed for following or all occurrences");
+                    errors.into_result()?;
+                }
+
+                let previous = self.booked_occurrence(id, occurrence_time)?;
+
+                if let Some(previous) = &previous {
+                    self.set_cancelled(previous, actor)?;
+                }
+
+                let booking = AppointmentBook { customer_id: series.customer_id, store_id: series.store_id, start_time: change.start_time, segments: change.segments };
+                let full_appointment = self.insert_booking(&booking, Some((id, occurrence_time)), actor)?;
+
+                let event = match previous {
+                    Some(_) => webhook::APPOINTMENT_RESCHEDULED,
+                    None => webhook::APPOINTMENT_CREATED,
+                };
+                webhook::emit(self.conn, event, json!(full_appointment));
+
+                return Ok(self.find_series(id)?);
+            }
+
+            // a series moved to another weekday keeps its rule on the new day
+            let rule = match &change.rrule {
+                Some(rrule) => parse_rule(rrule)?,
+                None => series.recurrence()?.moved((change.start_time.date() - occurrence_time.date()).num_days()),
+            };
+
+            if scope == SeriesScope::Following && occurrence_time > series.start_time {
+                let (before, after) = series.recurrence()?.split(series.start_time, occurrence_time);
+
+                self.cancel_occurrences_from(id, occurrence_time, actor)?;
+                self.save_series(&series, AppointmentSeries { rrule: before.to_string(), ..series.clone() }, actor)?;
+
+                let series_created: AppointmentSeries = diesel::insert_into(appointment_series::table)
+                    .values(AppointmentSeriesCreate {
+                        customer_id: series.customer_id,
+                        status: SERIES_ACTIVE.to_string(),
+                        start_time: change.start_time,
+                        rrule: match change.rrule {
+                            Some(_) => rule.to_string(),
+                            None => Recurrence { by_day: rule.by_day, ..after }.to_string(),
+                        },
+                        segments: json!(change.segments),
+                        store_id: series.store_id
+                    })
+                    .get_result(self.conn)?;
+
+                audit::created(self.conn, actor, "appointment_series", series_created.appointment_series_id, &series_created)?;
+                self.materialize_or_conflict(&series_created, None, actor)?;
+
+                return Ok(self.find_series(series_created.appointment_series_id)?);
+            }
+
+            // `all` leaves occurrences that are already due as they were;
+            // `following` from the first occurrence is the whole series
+            let from = match scope {
+                SeriesScope::All => Some(Utc::now().naive_utc()),
+                _ => None,
+            };
+
+            self.cancel_occurrences_from(id, from.unwrap_or(series.start_time), actor)?;
+
+            let series_changed = self.save_series(&series, AppointmentSeries {
+                start_time: change.start_time,
+                rrule: rule.to_string(),
+                segments: json!(change.segments),
+                excluded_times: vec![],
+                materialized_until: None,
+                ..series.clone()
+            }, actor)?;
+
+            self.materialize_or_conflict(&series_changed, from, actor)?;
+
+            Ok(self.find_series(id)?)
+        })
+    }
+
+    fn cancel_series(&self, id: i32, occurrence_time: NaiveDateTime, scope: SeriesScope, actor: &Actor) -> Result<FullAppointmentSeries, ApiError> {
+        let span = debug_span!("Appointment::cancel_series");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let series = self.active_series(id, occurrence_time)?;
+
+            let series_changed = match scope {
+                SeriesScope::This => {
+                    if let Some(booked) = self.booked_occurrence(id, occurrence_time)? {
+                        self.set_cancelled(&booked, actor)?;
+                        webhook::emit(self.conn, webhook::APPOINTMENT_CANCELLED, json!(self.find(booked.appointment_id)?));
+                    }
+
+                    let mut excluded_times = series.excluded_times.clone();
+                    excluded_times.push(occurrence_time);
+
+                    AppointmentSeries { excluded_times, ..series.clone() }
+                },
+                SeriesScope::Following if occurrence_time > series.start_time => {
+                    self.cancel_occurrences_from(id, occurrence_time, actor)?;
+                    let (before, _) = series.recurrence()?.split(series.start_time, occurrence_time);
+
+                    AppointmentSeries { rrule: before.to_string(), ..series.clone() }
+                },
+                _ => {
+                    let from = match scope {
+                        SeriesScope::All => Utc::now().naive_utc(),
+                        _ => series.start_time,
+                    };
+                    self.cancel_occurrences_from(id, from, actor)?;
+
+                    AppointmentSeries { status: SERIES_CANCELLED.to_string(), ..series.clone() }
+                },
+            };
+
+            self.save_series(&series, series_changed, actor)?;
+
+            Ok(self.find_series(id)?)
+        })
+    }
+
+    fn materialize_series(&self, through: NaiveDateTime, actor: &Actor) -> Result<MaterializeReport, ApiError> {
+        let span = debug_span!("Appointment::materialize_series");
+        let _entered = span.enter();
+
+        let due = appointment_series::table
+            .filter(appointment_series::status.eq(SERIES_ACTIVE))
+            .filter(appointment_series::materialized_until.is_null().or(appointment_series::materialized_until.lt(through)))
+            .order(appointment_series::appointment_series_id.asc())
+            .load::<AppointmentSeries>(self.conn)?;
+
+        let mut report = MaterializeReport { series: due.len(), booked: 0, skipped: vec![] };
+
+        for series in &due {
+            // each occurrence has been committed in its own transaction
+            let (booked, skipped) = self.materialize(series, None, through, actor)?;
+            metrics::appointments_booked("series", booked);
+
+            report.booked += booked;
+            report.skipped.extend(skipped);
+        }
+
+        Ok(report)
+    }
+}
+
+impl<'a> PgAppointmentRepository<'a> {
+    fn with_segments(&self, appointments: Vec<Appointment>) -> QueryResult<Vec<FullAppointment>> {
+        let all_segments = AppointmentSegment::belonging_to(&appointments)
+            .order(appointment_segment::position.asc())
+            .load::<AppointmentSegment>(self.conn)?;
+        let all_add_ons = AppointmentSegmentAddOn::belonging_to(&all_segments)
+            .order(appointment_segment_add_on::appointment_segment_add_on_id.asc())
+            .load::<AppointmentSegmentAddOn>(self.conn)?
+            .grouped_by(&all_segments);
+        let all_resources = AppointmentSegmentResource::belonging_to(&all_segments)
+            .order(appointment_segment_resource::appointment_segment_resource_id.asc())
+            .load::<AppointmentSegmentResource>(self.conn)?
+            .grouped_by(&all_segments);
+
+        let mut segments_by_appointment: HashMap<i32, Vec<FullAppointmentSegment>> = HashMap::new();
+
+        for ((segment, add_ons), resources) in all_segments.into_iter().zip(all_add_ons).zip(all_resources) {
+            segments_by_appointment
+                .entry(segment.appointment_id)
+                .or_default()
+                .push(FullAppointmentSegment { segment, add_ons, resources });
+        }
+
+        let full_appointments = appointments
+            .into_iter()
+            .map(|current_appointment| FullAppointment {
+                segments: segments_by_appointment.remove(&current_appointment.appointment_id).unwrap_or_default(),
+                appointment: current_appointment
+            })
+            .collect();
+
+        Ok(full_appointments)
+    }
+
+    /// Plans, checks and stores one visit, optionally as an occurrence of a
+    /// series. Does not open a transaction or emit webhooks; callers hold the
+    /// transaction the staff locks are taken in.
+    fn insert_booking(&self, booking: &AppointmentBook, occurrence: Option<(i32, NaiveDateTime)>, actor: &Actor) -> Result<FullAppointment, ApiError> {
+        let planned = self.plan(booking.start_time, &booking.segments)?;
+        let spans: Vec<BlockedSpan> = planned.iter().enumerate().map(|(index, segment)| segment.span(index)).collect();
+        let staff_ids = staff_ids(&spans);
+
+        self.lock_staff(&staff_ids)?;
+
+        let from = spans.iter().map(|span| span.from).min().unwrap_or(booking.start_time);
+        let until = spans.iter().map(|span| span.until).max().unwrap_or(booking.start_time);
+        let (hours, busy) = self.calendar(&staff_ids, from, until)?;
+
+        let mut errors = FieldErrors::default();
+
+        for span in &spans {
+            span.check(&hours, &busy, &mut errors);
+        }
+
+        let mut pool = self.resource_pool(booking.store_id, &planned, from, until)?;
+        let held = hold_resources(&mut pool, &planned, Duration::zero(), &mut errors);
+
+        if !errors.is_empty() {
+            return Err(ApiError {
+                errors: Some(errors.into_map()),
+                ..ApiError::coded(409, "appointment.conflict", "The visit does not fit the staff calendars".to_string())
+            });
+        }
+
+        let appointment_created: Appointment = diesel::insert_into(appointment::table)
+            .values(AppointmentCreate {
+                customer_id: booking.customer_id,
+                status: APPOINTMENT_BOOKED.to_string(),
+                start_time: planned.iter().map(|segment| segment.start_time).min().unwrap_or(booking.start_time),
+                end_time: planned.iter().map(|segment| segment.end_time).max().unwrap_or(booking.start_time),
+                price: planned.iter().map(|segment| segment.quote.price).sum(),
+                appointment_series_id: occurrence.map(|(series_id, _)| series_id),
+                occurrence_time: occurrence.map(|(_, occurrence_time)| occurrence_time),
+                store_id: booking.store_id
+            })
+            .get_result(self.conn)?;
+
+        for ((position, segment), resource_ids) in (0..).zip(planned).zip(held) {
+            let segment_created: AppointmentSegment = diesel::insert_into(appointment_segment::table)
+                .values(AppointmentSegmentCreate {
+                    appointment_id: appointment_created.appointment_id,
+                    position,
+                    staff_id: segment.staff_id,
+                    service_variant_id: segment.quote.service_variant_id,
+                    price: segment.quote.price,
+                    start_time: segment.start_time,
+                    end_time: segment.end_time,
+                    blocked_from: segment.blocked_from,
+                    blocked_until: segment.blocked_until
+                })
+                .get_result(self.conn)?;
+
+            let add_ons: Vec<AppointmentSegmentAddOnCreate> = segment.quote.add_ons.iter()
+                .map(|add_on| AppointmentSegmentAddOnCreate {
+                    appointment_segment_id: segment_created.appointment_segment_id,
+                    service_add_on_id: add_on.service_add_on_id,
+                    price: add_on.price
+                })
+                .collect();
+
+            diesel::insert_into(appointment_segment_add_on::table)
+                .values(&add_ons)
+                .execute(self.conn)?;
+
+            let resources: Vec<AppointmentSegmentResourceCreate> = resource_ids.into_iter()
+                .map(|resource_id| AppointmentSegmentResourceCreate {
+                    appointment_segment_id: segment_created.appointment_segment_id,
+                    resource_id
+                })
+                .collect();
+
+            diesel::insert_into(appointment_segment_resource::table)
+                .values(&resources)
+                .execute(self.conn)?;
+        }
+
+        let full_appointment = self.find(appointment_created.appointment_id)?;
+
+        audit::created(self.conn, actor, "appointment", appointment_created.appointment_id, &full_appointment)?;
+
+        Ok(full_appointment)
+    }
+
+    fn set_cancelled(&self, appointment_before: &Appointment, actor: &Actor) -> QueryResult<Appointment> {
+        let appointment_cancelled: Appointment = diesel::update(appointment::table)
+            .filter(appointment::appointment_id.eq(appointment_before.appointment_id))
+            .set(appointment::status.eq(APPOINTMENT_CANCELLED))
+            .get_result(self.conn)?;
+
+        audit::updated(self.conn, actor, "appointment", appointment_before.appointment_id, appointment_before, &appointment_cancelled)?;
+
+        Ok(appointment_cancelled)
+    }
+
+    /// The series, if it is still active and has an occurrence at
+    /// `occurrence_time`.
+    fn active_series(&self, id: i32, occurrence_time: NaiveDateTime) -> Result<AppointmentSeries, ApiError> {
+        let series = appointment_series::table
+            .filter(appointment_series::appointment_series_id.eq(id))
+            .first::<AppointmentSeries>(self.conn)?;
+
+        if series.status != SERIES_ACTIVE {
+            return Err(ApiError::coded(409, "appointment_series.cancelled", "The series has been cancelled".to_string()));
+        }
+
+        if !series.has_occurrence(occurrence_time)? {
+            return Err(ApiError::coded(404, "appointment_series.occurrence_not_found", format!("The series has no occurrence at {}", occurrence_time)));
+        }
+
+        Ok(series)
+    }
+
+    fn booked_occurrence(&self, series_id: i32, occurrence_time: NaiveDateTime) -> QueryResult<Option<Appointment>> {
+        appointment::table
+            .filter(appointment::appointment_series_id.eq(series_id))
+            .filter(appointment::occurrence_time.eq(occurrence_time))
+            .filter(appointment::status.eq(APPOINTMENT_BOOKED))
+            .first::<Appointment>(self.conn)
+            .optional()
+    }
+
+    /// Cancels the booked occurrences of the series at or after `from`.
+    fn cancel_occurrences_from(&self, series_id: i32, from: NaiveDateTime, actor: &Actor) -> Result<(), ApiError> {
+        let booked = appointment::table
+            .filter(appointment::appointment_series_id.eq(series_id))
+            .filter(appointment::occurrence_time.ge(from))
+            .filter(appointment::status.eq(APPOINTMENT_BOOKED))
+            .load::<Appointment>(self.conn)?;
+
+        for appointment_before in &booked {
+            self.set_cancelled(appointment_before, actor)?;
+            webhook::emit(self.conn, webhook::APPOINTMENT_CANCELLED, json!(self.find(appointment_before.appointment_id)?));
+        }
+
+        Ok(())
+    }
+
+    /// Stores the status, rule, segments and materialization state of
+    /// `series_after`.
+    fn save_series(&self, series_before: &AppointmentSeries, series_after: AppointmentSeries, actor: &Actor) -> QueryResult<AppointmentSeries> {
+        let series_saved: AppointmentSeries = diesel::update(appointment_series::table)
+            .filter(appointment_series::appointment_series_id.eq(series_before.appointment_series_id))
+            .set((
+                appointment_series::status.eq(series_after.status),
+                appointment_series::start_time.eq(series_after.start_time),
+                appointment_series::rrule.eq(series_after.rrule),
+                appointment_series::segments.eq(series_after.segments),
+                appointment_series::excluded_times.eq(series_after.excluded_times),
+                appointment_series::materialized_until.eq(series_after.materialized_until)
+            ))
+            .get_result(self.conn)?;
+
+        audit::updated(self.conn, actor, "appointment_series", series_saved.appointment_series_id, series_before, &series_saved)?;
+
+        Ok(series_saved)
+    }
+
+    /// Books the occurrences of `series` after its `materialized_until` up to
+    /// `through`, each in its own savepoint. Occurrences before `from`, ones
+    /// cancelled on their own and ones that already have an appointment are
+    /// left alone; ones that do not fit are returned instead of booked.
+    fn materialize(&self, series: &AppointmentSeries, from: Option<NaiveDateTime>, through: NaiveDateTime, actor: &Actor) -> Result<(usize, Vec<SkippedOccurrence>), ApiError> {
+        let taken: Vec<Option<NaiveDateTime>> = appointment::table
+            .filter(appointment::appointment_series_id.eq(series.appointment_series_id))
+            .select(appointment::occurrence_time)
+            .load(self.conn)?;
+
+        let due: Vec<NaiveDateTime> = series.recurrence()?
+            .occurrences(series.start_time, through)
+            .into_iter()
+            .filter(|occurrence_time| series.materialized_until.is_none_or(|until| *occurrence_time > until))
+            .filter(|occurrence_time| from.is_none_or(|from| *occurrence_time >= from))
+            .filter(|occurrence_time| !series.excluded_times.contains(occurrence_time) && !taken.contains(&Some(*occurrence_time)))
+            .collect();
+
+        let mut booked = 0;
+        let mut skipped = vec![];
+
+        for occurrence_time in due {
+            let booking = series.booking(occurrence_time)?;
+            let result = self.conn.transaction::<_, ApiError, _>(|| {
+                self.insert_booking(&booking, Some((series.appointment_series_id, occurrence_time)), actor)
+            });
+
+            match result {
+                Ok(full_appointment) => {
+                    webhook::emit(self.conn, webhook::APPOINTMENT_CREATED, json!(full_appointment));
+                    booked += 1;
+                },
+                Err(err) if err.status_code == 409 || err.status_code == 422 => {
+                    let mut errors = err.errors.unwrap_or_default();
+
+                    if errors.is_empty() {
+                        errors.insert("occurrence_time".to_string(), vec![err.message]);
+                    }
+
+                    skipped.push(SkippedOccurrence { appointment_series_id: series.appointment_series_id, occurrence_time, errors });
+                },
+                Err(err) => return Err(err),
+            }
+        }
+
+        if series.materialized_until.is_none_or(|until| until < through) {
+            diesel::update(appointment_series::table)
+                .filter(appointment_series::appointment_series_id.eq(series.appointment_series_id))
+                .set(appointment_series::materialized_until.eq(through))
+                .execute(self.conn)?;
+        }
+
+        Ok((booked, skipped))
+    }
+
+    /// Materializes through the horizon, failing with a 409 keyed by
+    /// occurrence if any of them does not fit.
+    fn materialize_or_conflict(&self, series: &AppointmentSeries, from: Option<NaiveDateTime>, actor: &Actor) -> Result<(), ApiError> {
+        let (_, skipped) = self.materialize(series, from, series_horizon(), actor)?;
+
+        if skipped.is_empty() {
+            return Ok(());
+        }
+
+        let mut errors = BTreeMap::new();
+
+        for occurrence in skipped {
+            for (field, messages) in occurrence.errors {
+                errors.insert(format!("occurrences[{}].{}", occurrence.occurrence_time.format("%Y-%m-%dT%H:%M:%S"), field), messages);
+            }
+        }
+
+        Err(ApiError {
+            errors: Some(errors),
+            ..ApiError::coded(409, "appointment_series.conflict", "Some occurrences do not fit the staff calendars".to_string())
+        })
+    }
+
+    /// Locks the staff rows until the transaction ends, so a second booking
+    /// for the same staff waits and then sees this one.
+    pub fn lock_staff(&self, staff_ids: &[i32]) -> QueryResult<()> {
+        staff::table
+            .filter(staff::staff_id.eq_any(staff_ids))
+            .order(staff::staff_id.asc())
+            .select(staff::staff_id)
+            .for_update()
+            .load::<i32>(self.conn)?;
+
+        Ok(())
+    }
+
+    /// Weekly hours of `staff_ids` and the booked segments and class
+    /// sessions they are busy with between `from` and `until`.
+    pub fn calendar(&self, staff_ids: &[i32], from: NaiveDateTime, until: NaiveDateTime) -> QueryResult<(Vec<StaffHours>, Vec<BusySpan>)> {
+        let hours = staff_hours::table
+            .filter(staff_hours::staff_id.eq_any(staff_ids))
+            .load::<StaffHours>(self.conn)?;
+
+        let busy = appointment_segment::table
+            .inner_join(appointment::table)
+            .filter(appointment::status.ne(APPOINTMENT_CANCELLED))
+            .filter(appointment_segment::staff_id.eq_any(staff_ids))
+            .filter(appointment_segment::blocked_from.lt(until))
+            .filter(appointment_segment::blocked_until.gt(from))
+            .select((appointment_segment::staff_id, appointment_segment::blocked_from, appointment_segment::blocked_until))
+            .load::<(i32, NaiveDateTime, NaiveDateTime)>(self.conn)?;
+
+        let classes = class_session::table
+            .filter(class_session::status.eq(CLASS_SCHEDULED))
+            .filter(class_session::staff_id.eq_any(staff_ids))
+            .filter(class_session::blocked_from.lt(until))
+            .filter(class_session::blocked_until.gt(from))
+            .select((class_session::staff_id, class_session::blocked_from, class_session::blocked_until))
+            .load::<(i32, NaiveDateTime, NaiveDateTime)>(self.conn)?;
+
+        let busy = busy.into_iter()
+            .chain(classes)
+            .map(|(staff_id, from, until)| BusySpan { staff_id, from, until })
+            .collect();
+
+        Ok((hours, busy))
+    }
+
+    /// Active resources at `store_id` of the types `planned` needs, and the
+    /// booked segments holding them between `from` and `until`. The resource
+    /// rows stay locked until the transaction ends, like the staff rows.
+    pub fn resource_pool(&self, store_id: Option<i32>, planned: &[PlannedSegment], from: NaiveDateTime, until: NaiveDateTime) -> Result<ResourcePool, ApiError> {
+        let mut resource_types: Vec<&str> = planned.iter()
+            .flat_map(|segment| segment.resource_types.iter().map(String::as_str))
+            .collect();
+        resource_types.sort_unstable();
+        resource_types.dedup();
+
+        if resource_types.is_empty() {
+            return Ok(ResourcePool::default());
+        }
+
+        let store_id = match store_id {
+            Some(store_id) => store_id,
+            None => {
+                let mut errors = FieldErrors::default();
+                errors.add("store_id", "is required when a service needs a resource");
+                return Err(ApiError::validation(errors.into_map()));
+            },
+        };
+
+        let resources = resource::table
+            .filter(resource::store_id.eq(store_id))
+            .filter(resource::resource_type.eq_any(&resource_types))
+            .filter(resource::is_active.eq(1))
+            .filter(resource::deleted_at.is_null())
+            .order(resource::resource_id.asc())
+            .for_update()
+            .load::<Resource>(self.conn)?;
+        let resource_ids: Vec<i32> = resources.iter().map(|current| current.resource_id).collect();
+
+        let held = appointment_segment_resource::table
+            .inner_join(appointment_segment::table.inner_join(appointment::table))
+            .filter(appointment::status.ne(APPOINTMENT_CANCELLED))
+            .filter(appointment_segment_resource::resource_id.eq_any(&resource_ids))
+            .filter(appointment_segment::blocked_from.lt(until))
+            .filter(appointment_segment::blocked_until.gt(from))
+            .select((appointment_segment_resource::resource_id, appointment_segment::blocked_from, appointment_segment::blocked_until))
+            .load::<(i32, NaiveDateTime, NaiveDateTime)>(self.conn)?
+            .into_iter()
+            .map(|(resource_id, from, until)| ResourceHold { resource_id, from, until })
+            .collect();
+
+        Ok(ResourcePool { resources, held })
+    }
+}
+
+/// Holds a resource of every type each segment needs, moved `offset` later,
+/// and reports under `segments[index]` the types with none free. Returns the
+/// resource ids held for each segment.
+fn hold_resources(pool: &mut ResourcePool, planned: &[PlannedSegment], offset: Duration, errors: &mut FieldErrors) -> Vec<Vec<i32>> {
+    planned.iter()
+        .enumerate()
+        .map(|(index, segment)| {
+            segment.resource_types.iter()
+                .filter_map(|resource_type| {
+                    let resource_id = pool.take(resource_type, segment.blocked_from + offset, segment.blocked_until + offset);
+
+                    if resource_id.is_none() {
+                        errors.add(&format!("segments[{}]", index), &format!("needs a free {} at the store", resource_type));
+                    }
+
+                    resource_id
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn staff_ids(spans: &[BlockedSpan]) -> Vec<i32> {
+    let mut staff_ids: Vec<i32> = spans.iter().map(|span| span.staff_id).collect();
+    staff_ids.sort_unstable();
+    staff_ids.dedup();
+
+    staff_ids
+}
+
+fn parse_rule(rrule: &str) -> Result<Recurrence, ApiError> {
+    rrule.parse().map_err(|message: String| {
+        let mut errors = FieldErrors::default();
+        errors.add("rrule", &message);
+        ApiError::validation(errors.into_map())
+    })
+}
\ No newline at end of file
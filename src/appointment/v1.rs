@@ -0,0 +1,235 @@
+use crate::api_error::ApiError;
+use crate::appointment::{
+    Appointment, AppointmentBook, AppointmentId, AppointmentQuery, AppointmentSeries, AppointmentSeriesId, AvailabilityRequest,
+    SeriesBook, SeriesChange, SeriesOccurrencePath, SeriesScopeQuery
+};
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::validation::Valid;
+use actix_web::{get, post, put, web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments",
+    tag = "appointment",
+    operation_id = "appointment_find_all",
+    params(
+        AppointmentQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Appointments with their segments", body = [FullAppointment], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/appointments")]
+async fn find_all(query: web::Query<AppointmentQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (appointments, total) = Appointment::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, appointments))
+}
+
+/// Books one or more services back to back as a single visit. Either every
+/// segment is booked or none is.
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointments",
+    tag = "appointment",
+    operation_id = "appointment_book",
+    request_body = AppointmentBook,
+    responses(
+        (status = 201, description = "Booked appointment", body = FullAppointment),
+        (status = 409, description = "A segment is outside its staff member's hours or overlaps another booking, or no resource it needs is free", body = ErrorBody),
+        (status = 422, description = "Unknown variants or add-ons, staff who do not perform them, or no `store_id` for services that need resources", body = ErrorBody)
+    )
+)]
+#[post("/appointments")]
+async fn book(booking: Valid<AppointmentBook>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let appointment = Appointment::book(booking.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(appointment))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointments/availability",
+    tag = "appointment",
+    operation_id = "appointment_availability",
+    request_body = AvailabilityRequest,
+    responses(
+        (status = 200, description = "Start times on the date at which the whole visit can be booked", body = [String])
+    )
+)]
+#[post("/appointments/availability")]
+async fn availability(request: Valid<AvailabilityRequest>) -> Result<HttpResponse, ApiError> {
+    let start_times = Appointment::availability(request.into_inner())?;
+    Ok(HttpResponse::Ok().json(start_times))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/{appointment_id}",
+    tag = "appointment",
+    operation_id = "appointment_find",
+    params(("appointment_id" = i32, Path, description = "Appointment id")),
+    responses(
+        (status = 200, description = "Appointment with its segments", body = FullAppointment)
+    )
+)]
+#[get("/appointments/{appointment_id}")]
+async fn find(id: web::Path<AppointmentId>) -> Result<HttpResponse, ApiError> {
+    let appointment = Appointment::find(id.appointment_id)?;
+    Ok(HttpResponse::Ok().json(appointment))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointments/{appointment_id}/cancel",
+    tag = "appointment",
+    operation_id = "appointment_cancel",
+    params(("appointment_id" = i32, Path, description = "Appointment id")),
+    responses(
+        (status = 200, description = "Cancelled appointment; its time is free again", body = FullAppointment)
+    )
+)]
+#[post("/appointments/{appointment_id}/cancel")]
+async fn cancel(id: web::Path<AppointmentId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let appointment = Appointment::cancel(id.appointment_id, &actor)?;
+    Ok(HttpResponse::Ok().json(appointment))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointments/{appointment_id}/complete",
+    tag = "appointment",
+    operation_id = "appointment_complete",
+    params(("appointment_id" = i32, Path, description = "Appointment id")),
+    responses(
+        (status = 200, description = "Completed appointment", body = FullAppointment),
+        (status = 409, description = "The appointment is cancelled", body = ErrorBody)
+    )
+)]
+#[post("/appointments/{appointment_id}/complete")]
+async fn complete(id: web::Path<AppointmentId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let appointment = Appointment::complete(id.appointment_id, &actor)?;
+    Ok(HttpResponse::Ok().json(appointment))
+}
+
+/// Creates a recurring series and books every occurrence within the horizon.
+/// Either all of them fit the staff calendars or nothing is created.
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointment_series",
+    tag = "appointment",
+    operation_id = "appointment_series_book",
+    request_body = SeriesBook,
+    responses(
+        (status = 201, description = "Series with its booked occurrences", body = FullAppointmentSeries),
+        (status = 409, description = "Occurrences that do not fit, keyed like `occurrences[2020-11-03T10:00:00].segments[0]`", body = ErrorBody),
+        (status = 422, description = "Invalid rule, unknown variants or add-ons, or staff who do not perform them", body = ErrorBody)
+    )
+)]
+#[post("/appointment_series")]
+async fn book_series(request: Valid<SeriesBook>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let series = AppointmentSeries::book(request.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(series))
+}
+
+/// Books the occurrences of every active series that have come within the
+/// horizon. Occurrences that no longer fit are skipped and reported.
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointment_series/materialize",
+    tag = "appointment",
+    operation_id = "appointment_series_materialize",
+    responses(
+        (status = 200, description = "Occurrences booked and skipped", body = MaterializeReport)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/appointment_series/materialize")]
+async fn materialize_series(actor: Actor) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let report = AppointmentSeries::materialize(&actor)?;
+    Ok(HttpResponse::Ok().json(report))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointment_series/{appointment_series_id}",
+    tag = "appointment",
+    operation_id = "appointment_series_find",
+    params(("appointment_series_id" = i32, Path, description = "Appointment series id")),
+    responses(
+        (status = 200, description = "Series with its appointments", body = FullAppointmentSeries)
+    )
+)]
+#[get("/appointment_series/{appointment_series_id}")]
+async fn find_series(id: web::Path<AppointmentSeriesId>) -> Result<HttpResponse, ApiError> {
+    let series = AppointmentSeries::find(id.appointment_series_id)?;
+    Ok(HttpResponse::Ok().json(series))
+}
+
+/// Moves one occurrence (`this`), it and every later one (`following`,
+/// which splits off a new series) or every occurrence not yet due (`all`).
+#[utoipa::path(
+    put,
+    path = "/api/v1/appointment_series/{appointment_series_id}/occurrences/{occurrence_time}",
+    tag = "appointment",
+    operation_id = "appointment_series_change",
+    params(
+        ("appointment_series_id" = i32, Path, description = "Appointment series id"),
+        ("occurrence_time" = String, Path, description = "Occurrence as scheduled by the rule, e.g. 2020-11-03T10:00:00"),
+        SeriesScopeQuery
+    ),
+    request_body = SeriesChange,
+    responses(
+        (status = 200, description = "The changed series; for `following` the new one", body = FullAppointmentSeries),
+        (status = 404, description = "No such series or occurrence", body = ErrorBody),
+        (status = 409, description = "The series is cancelled or a changed occurrence does not fit", body = ErrorBody)
+    )
+)]
+#[put("/appointment_series/{appointment_series_id}/occurrences/{occurrence_time}")]
+async fn change_series(path: web::Path<SeriesOccurrencePath>, query: web::Query<SeriesScopeQuery>, change: Valid<SeriesChange>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let series = AppointmentSeries::change(path.appointment_series_id, path.occurrence_time, query.scope()?, change.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(series))
+}
+
+/// Cancels one occurrence (`this`), it and every later one (`following`) or
+/// the whole series from now on (`all`).
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointment_series/{appointment_series_id}/occurrences/{occurrence_time}/cancel",
+    tag = "appointment",
+    operation_id = "appointment_series_cancel",
+    params(
+        ("appointment_series_id" = i32, Path, description = "Appointment series id"),
+        ("occurrence_time" = String, Path, description = "Occurrence as scheduled by the rule, e.g. 2020-11-03T10:00:00"),
+        SeriesScopeQuery
+    ),
+    responses(
+        (status = 200, description = "The series with its remaining occurrences", body = FullAppointmentSeries),
+        (status = 404, description = "No such series or occurrence", body = ErrorBody),
+        (status = 409, description = "The series is already cancelled", body = ErrorBody)
+    )
+)]
+#[post("/appointment_series/{appointment_series_id}/occurrences/{occurrence_time}/cancel")]
+async fn cancel_series(path: web::Path<SeriesOccurrencePath>, query: web::Query<SeriesScopeQuery>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let series = AppointmentSeries::cancel(path.appointment_series_id, path.occurrence_time, query.scope()?, &actor)?;
+    Ok(HttpResponse::Ok().json(series))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(book);
+    cfg.service(availability);
+    cfg.service(find);
+    cfg.service(cancel);
+    cfg.service(complete);
+    cfg.service(book_series);
+    cfg.service(materialize_series);
+    cfg.service(find_series);
+    cfg.service(change_series);
+    cfg.service(cancel_series);
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+use crate::api_error::ApiError;
+use crate::staff::Staff;
+use actix_web::{dev, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+
+const MANAGER_ACCESS: [&str; 2] = ["owner", "manager"];
+
+pub const STAFF_ID_HEADER: &str = "x-staff-id";
+
+/// Who is making a change, taken from the `X-Staff-Id` request header.
+///
+/// The header is trusted as sent; there is no authentication in front of it.
+/// `System` is never read from a request; it marks changes made by the admin
+/// CLI.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Actor {
+    Anonymous,
+    Staff(i32),
+    System
+}
+
+impl Actor {
+    pub fn label(&self) -> String {
+        match self {
+            Actor::Anonymous => "anonymous".to_string(),
+            Actor::Staff(id) => format!("staff:{}", id),
+            Actor::System => "system".to_string(),
+        }
+    }
+
+    pub fn require_manager(&self) -> Result<(), ApiError> {
+        let id = match self {
+            Actor::Staff(id) => *id,
+            Actor::System => return Ok(()),
+            Actor::Anonymous => return Err(ApiError::coded(401, "auth.staff_id_required", "X-Staff-Id header required".to_string())),
+        };
+
+        let member = Staff::find(id)
+            .map_err(|_| ApiError::coded(403, "auth.manager_required", "Manager access required".to_string()))?;
+
+        match member.access {
+            Some(level) if MANAGER_ACCESS.contains(&level.as_str()) => Ok(()),
+            _ => Err(ApiError::coded(403, "auth.manager_required", "Manager access required".to_string())),
+        }
+    }
+}
+
+impl FromRequest for Actor {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        let header = match req.headers().get(STAFF_ID_HEADER) {
+            Some(header) => header,
+            None => return ready(Ok(Actor::Anonymous)),
+        };
+
+        let actor = header.to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<i32>().ok())
+            .map(Actor::Staff)
+            .ok_or_else(|| ApiError::coded(400, "auth.invalid_staff_id", "Invalid X-Staff-Id header".to_string()));
+
+        ready(actor)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,8 @@
+pub mod actor;
+pub mod model;
+pub mod routes;
+pub mod v1;
+
+pub use actor::{Actor, STAFF_ID_HEADER};
+pub use model::*;
+pub use routes::init_routes;
\ No newline at end of file
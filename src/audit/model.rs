@@ -0,0 +1,101 @@
+use crate::db;
+use crate::audit::Actor;
+use crate::schema::audit_log::{self, dsl::*};
+use chrono::NaiveDateTime;
+use crate::diesel::prelude::*;
+use diesel::pg::PgConnection;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use serde_json::{json, Value};
+use tracing::debug_span;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AuditQuery {
+    pub entity: Option<String>,
+    pub entity_id: Option<i32>
+}
+
+#[derive(Insertable)]
+#[table_name = "audit_log"]
+pub struct AuditLogCreate {
+    pub actor: String,
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub action: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>
+}
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, ToSchema)]
+#[primary_key(audit_log_id)]
+#[table_name = "audit_log"]
+pub struct AuditLog {
+    pub audit_log_id: i32,
+    pub actor: String,
+    pub entity_type: String,
+    pub entity_id: i32,
+    pub action: String,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub created_at: NaiveDateTime
+}
+
+fn record(conn: &PgConnection, by: &Actor, entity: &str, id: i32, change: &str, before_value: Option<Value>, after_value: Option<Value>) -> QueryResult<()> {
+    let entry = AuditLogCreate {
+        actor: by.label(),
+        entity_type: entity.to_string(),
+        entity_id: id,
+        action: change.to_string(),
+        before: before_value,
+        after: after_value
+    };
+
+    diesel::insert_into(audit_log::table)
+        .values(entry)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn created<T: Serialize>(conn: &PgConnection, by: &Actor, entity: &str, id: i32, after_value: &T) -> QueryResult<()> {
+    record(conn, by, entity, id, "create", None, Some(json!(after_value)))
+}
+
+pub fn updated<B: Serialize, A: Serialize>(conn: &PgConnection, by: &Actor, entity: &str, id: i32, before_value: &B, after_value: &A) -> QueryResult<()> {
+    record(conn, by, entity, id, "update", Some(json!(before_value)), Some(json!(after_value)))
+}
+
+pub fn deleted<T: Serialize>(conn: &PgConnection, by: &Actor, entity: &str, id: i32, before_value: &T) -> QueryResult<()> {
+    record(conn, by, entity, id, "delete", Some(json!(before_value)), None)
+}
+
+pub fn restored<T: Serialize>(conn: &PgConnection, by: &Actor, entity: &str, id: i32, after_value: &T) -> QueryResult<()> {
+    record(conn, by, entity, id, "restore", None, Some(json!(after_value)))
+}
+
+/// Records that a password changed without logging either password.
+pub fn password_reset(conn: &PgConnection, by: &Actor, entity: &str, id: i32) -> QueryResult<()> {
+    record(conn, by, entity, id, "password_reset", None, None)
+}
+
+impl AuditLog {
+    pub fn find(audit_query: AuditQuery) -> QueryResult<Vec<Self>> {
+        let span = debug_span!("AuditLog::find");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let mut query = audit_log.into_boxed();
+
+        if let Some(entity) = audit_query.entity {
+            query = query.filter(audit_log::entity_type.eq(entity));
+        }
+
+        if let Some(id) = audit_query.entity_id {
+            query = query.filter(audit_log::entity_id.eq(id));
+        }
+
+        query.order(audit_log::audit_log_id.desc()).load::<Self>(&conn)
+    }
+}
\ No newline at end of file
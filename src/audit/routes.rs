@@ -0,0 +1,26 @@
+use crate::api_error::ApiError;
+use crate::audit::{Actor, AuditLog, AuditQuery};
+use actix_web::{get, web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    tag = "audit",
+    operation_id = "legacy_audit_find",
+    params(AuditQuery),
+    responses(
+        (status = 200, description = "Audit log entries, newest first", body = [AuditLog])
+    ),
+    security(("staff_id" = []))
+)]
+#[get("/audit")]
+async fn find(actor: Actor, query: web::Query<AuditQuery>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let entries = AuditLog::find(query.into_inner())?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find);
+}
\ No newline at end of file
@@ -0,0 +1,288 @@
+use chrono::NaiveTime;
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
+use diesel::pg::PgConnection;
+use diesel::prelude::*;
+use dotenv::dotenv;
+use rust_scheduler::api_error::ApiError;
+use rust_scheduler::appointment::{self, AppointmentRepository, PgAppointmentRepository};
+use rust_scheduler::audit::Actor;
+use rust_scheduler::config::CONFIG;
+use rust_scheduler::schema::{service, staff, store};
+use rust_scheduler::service::{GenerateService, GenerateServiceVariant, PgServiceRepository, ServiceCategoryCreate, ServiceRepository};
+use rust_scheduler::staff::{PgStaffRepository, StaffCreate, StaffHoursCreate, StaffRepository};
+use rust_scheduler::store::{PgStoreRepository, StoreAddressCreate, StoreCreate, StoreHoursCreate, StoreRepository};
+use rust_scheduler::validation::{FieldErrors, Validate};
+use std::io::{self, BufRead, Write};
+use std::process;
+
+fn main() {
+    dotenv().ok();
+
+    let matches = App::new("scheduler-admin")
+        .about("Seeding, user management and maintenance for the scheduler database")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(SubCommand::with_name("create-owner")
+            .about("Creates a staff member with owner access")
+            .arg(Arg::with_name("email").long("email").takes_value(true).required(true))
+            .arg(Arg::with_name("first-name").long("first-name").takes_value(true).required(true))
+            .arg(Arg::with_name("last-name").long("last-name").takes_value(true).required(true))
+            .arg(Arg::with_name("phone").long("phone").takes_value(true).required(true))
+            .arg(Arg::with_name("password").long("password").takes_value(true)
+                .help("Read from stdin when omitted")))
+        .subcommand(SubCommand::with_name("seed")
+            .about("Creates demo stores, services and staff")
+            .arg(Arg::with_name("force").long("force")
+                .help("Seed even if the database already has stores, services or staff")))
+        .subcommand(SubCommand::with_name("reset-password")
+            .about("Sets a new password for a staff member")
+            .arg(Arg::with_name("email").long("email").takes_value(true).required(true))
+            .arg(Arg::with_name("password").long("password").takes_value(true)
+                .help("Read from stdin when omitted")))
+        .subcommand(SubCommand::with_name("repair-hours")
+            .about("Adds missing weekday rows to staff_hours and store_hours"))
+        .subcommand(SubCommand::with_name("hash-passwords")
+            .about("Hashes staff passwords still stored as plain text"))
+        .subcommand(SubCommand::with_name("materialize-series")
+            .about("Books occurrences of recurring appointment series that have come within the horizon"))
+        .get_matches();
+
+    let conn = PgConnection::establish(&CONFIG.database.url).unwrap_or_else(|err| {
+        eprintln!("Could not connect to the database: {}", err);
+        process::exit(1);
+    });
+
+    let result = conn.transaction::<_, ApiError, _>(|| match matches.subcommand() {
+        ("create-owner", Some(args)) => create_owner(&conn, args),
+        ("seed", Some(args)) => seed(&conn, args.is_present("force")),
+        ("reset-password", Some(args)) => reset_password(&conn, args),
+        ("repair-hours", _) => repair_hours(&conn),
+        ("hash-passwords", _) => hash_passwords(&conn),
+        ("materialize-series", _) => materialize_series(&conn),
+        _ => unreachable!("clap requires a subcommand"),
+    });
+
+    if let Err(err) = result {
+        eprintln!("error: {}", err.message);
+
+        for (field, messages) in err.errors.iter().flatten() {
+            for message in messages {
+                eprintln!("  {}: {}", field, message);
+            }
+        }
+
+        process::exit(1);
+    }
+}
+
+/// Takes `--password` if given, otherwise reads one line from stdin so the
+/// password stays out of shell history.
+fn password(args: &ArgMatches) -> Result<String, ApiError> {
+    if let Some(value) = args.value_of("password") {
+        return Ok(value.to_string());
+    }
+
+    eprint!("Password: ");
+    io::stderr().flush().ok();
+
+    let mut line = String::new();
+    io::stdin().lock().read_line(&mut line)
+        .map_err(|err| ApiError::new(500, format!("Could not read password: {}", err)))?;
+
+    Ok(line.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+fn create_owner(conn: &PgConnection, args: &ArgMatches) -> Result<(), ApiError> {
+    let owner = StaffCreate {
+        first_name: args.value_of("first-name").unwrap_or_default().to_string(),
+        last_name: args.value_of("last-name").unwrap_or_default().to_string(),
+        password: password(args)?,
+        email: args.value_of("email").unwrap_or_default().to_string(),
+        phone: args.value_of("phone").unwrap_or_default().to_string(),
+        access: "owner".to_string(),
+        calendar_color: "#1f77b4".to_string()
+    };
+
+    let mut errors = FieldErrors::default();
+    owner.validate(&mut errors);
+    errors.into_result()?;
+
+    let staff = PgStaffRepository::new(conn);
+
+    if staff.find_by_email(&owner.email).optional()?.is_some() {
+        return Err(ApiError::new(409, format!("Staff with email {} already exists", owner.email)));
+    }
+
+    let created = staff.create(owner, &Actor::System)?;
+    println!("Created owner {} <{}> with staff_id {}", created.first_name, created.email, created.staff_id);
+
+    Ok(())
+}
+
+fn reset_password(conn: &PgConnection, args: &ArgMatches) -> Result<(), ApiError> {
+    let email = args.value_of("email").unwrap_or_default();
+    let staff = PgStaffRepository::new(conn);
+
+    let member = staff.find_by_email(email).optional()?
+        .ok_or_else(|| ApiError::new(404, format!("No staff with email {}", email)))?;
+
+    let new_password = password(args)?;
+
+    if new_password.trim().is_empty() {
+        return Err(ApiError::new(422, "Password must not be empty".to_string()));
+    }
+
+    staff.reset_password(member.staff_id, &new_password, &Actor::System)?;
+    println!("Reset password for {}", member.email);
+
+    Ok(())
+}
+
+fn repair_hours(conn: &PgConnection) -> Result<(), ApiError> {
+    let staff_rows = PgStaffRepository::new(conn).repair_hours()?;
+    let store_rows = PgStoreRepository::new(conn).repair_hours()?;
+    println!("Added {} staff_hours and {} store_hours rows", staff_rows, store_rows);
+
+    Ok(())
+}
+
+fn hash_passwords(conn: &PgConnection) -> Result<(), ApiError> {
+    let hashed = PgStaffRepository::new(conn).hash_plaintext_passwords()?;
+    println!("Hashed {} plain text passwords", hashed);
+
+    Ok(())
+}
+
+fn materialize_series(conn: &PgConnection) -> Result<(), ApiError> {
+    let report = PgAppointmentRepository::new(conn).materialize_series(appointment::series_horizon(), &Actor::System)?;
+    println!("Booked {} occurrences across {} series", report.booked, report.series);
+
+    for skipped in &report.skipped {
+        let reasons: Vec<String> = skipped.errors.iter()
+            .map(|(field, messages)| format!("{}: {}", field, messages.join(", ")))
+            .collect();
+        println!("  skipped series {} at {}: {}", skipped.appointment_series_id, skipped.occurrence_time, reasons.join("; "));
+    }
+
+    Ok(())
+}
+
+fn time(hour: u32, minute: u32) -> Option<NaiveTime> {
+    Some(NaiveTime::from_hms(hour, minute, 0))
+}
+
+/// Monday to Saturday 9 to 5, closed Sunday (day 0).
+fn open_hours(day: i32) -> (Option<NaiveTime>, Option<NaiveTime>) {
+    if day == 0 {
+        (None, None)
+    } else {
+        (time(9, 0), time(17, 0))
+    }
+}
+
+fn seed(conn: &PgConnection, force: bool) -> Result<(), ApiError> {
+    let existing = store::table.count().get_result::<i64>(conn)?
+        + service::table.count().get_result::<i64>(conn)?
+        + staff::table.count().get_result::<i64>(conn)?;
+
+    if existing > 0 && !force {
+        return Err(ApiError::new(409, "Database already has stores, services or staff; pass --force to seed anyway".to_string()));
+    }
+
+    let actor = Actor::System;
+    let stores = PgStoreRepository::new(conn);
+    let services = PgServiceRepository::new(conn);
+    let staff = PgStaffRepository::new(conn);
+
+    let demo_stores = [("Downtown", "100 Main St", "62701"), ("Westside", "2500 Wabash Ave", "62704")];
+
+    for (name, street_address, zip) in demo_stores.iter() {
+        let created = stores.create(StoreCreate { name: name.to_string() }, &actor)?;
+
+        stores.create_address(StoreAddressCreate {
+            store_id: created.store_id,
+            street_address: street_address.to_string(),
+            city: "Springfield".to_string(),
+            state: "IL".to_string(),
+            zip: zip.to_string(),
+            phone: "2175550100".to_string(),
+            email: format!("{}@example.com", name.to_lowercase())
+        }, &actor)?;
+
+        stores.update_hours((0..7).map(|day| {
+            let (start_time, end_time) = open_hours(day);
+            StoreHoursCreate { store_id: created.store_id, day_of_week: day, start_time, end_time }
+        }).collect(), &actor)?;
+    }
+
+    let mut category_ids = vec![];
+
+    for (display_order, name) in (1..).zip(["Hair", "Nails"].iter()) {
+        let created = services.create_category(ServiceCategoryCreate {
+            name: name.to_string(),
+            description: None,
+            display_order,
+            is_active: 1
+        }, &actor)?;
+
+        category_ids.push(created.service_category_id);
+    }
+
+    let demo_services = [
+        ("Haircut", category_ids[0], vec![(25.0, time(0, 30)), (40.0, time(0, 45))]),
+        ("Color", category_ids[0], vec![(80.0, time(1, 30))]),
+        ("Manicure", category_ids[1], vec![(30.0, time(0, 45))])
+    ];
+
+    let mut variant_ids = vec![];
+
+    for (name, category_id, variants) in demo_services.iter() {
+        let created = services.create(GenerateService {
+            name: name.to_string(),
+            description: None,
+            is_active: 1,
+            service_category_id: Some(*category_id),
+            capacity: 1,
+            before_time: None,
+            after_time: time(0, 10),
+            variants: variants.iter().map(|(price, duration)| GenerateServiceVariant { price: *price, duration: *duration, capacity: None }).collect()
+        }, &actor)?;
+
+        for variant in services.find_variants(created.service_id)? {
+            variant_ids.push(variant.service_variant_id);
+        }
+    }
+
+    let demo_staff = [
+        ("Jamie", "Rivera", "manager", "#2ca02c", "2175550101"),
+        ("Alex", "Chen", "staff", "#d62728", "2175550102"),
+        ("Sam", "Okafor", "staff", "#9467bd", "2175550103")
+    ];
+
+    for (first_name, last_name, access, calendar_color, phone) in demo_staff.iter() {
+        let created = staff.create(StaffCreate {
+            first_name: first_name.to_string(),
+            last_name: last_name.to_string(),
+            password: "changeme".to_string(),
+            email: format!("{}@example.com", first_name.to_lowercase()),
+            phone: phone.to_string(),
+            access: access.to_string(),
+            calendar_color: calendar_color.to_string()
+        }, &actor)?;
+
+        staff.update_hours((0..7).map(|day| {
+            let (start_time, end_time) = open_hours(day);
+            StaffHoursCreate { staff_id: created.staff_id, day_of_week: day, start_time, end_time }
+        }).collect(), &actor)?;
+
+        for variant_id in &variant_ids {
+            staff.add_service(created.staff_id, *variant_id, &actor)?;
+        }
+    }
+
+    println!(
+        "Seeded {} stores, {} services and {} staff (password \"changeme\")",
+        demo_stores.len(), demo_services.len(), demo_staff.len()
+    );
+
+    Ok(())
+}
\ No newline at end of file
@@ -0,0 +1,223 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::customer::Customer;
+use crate::{schema::class_enrollment, schema::class_session};
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::validation::{FieldErrors, Validate};
+use chrono::NaiveDateTime;
+use diesel::pg::{Pg, PgConnection};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::repository::{ClassRepository, PgClassRepository};
+
+pub const CLASS_SCHEDULED: &str = "scheduled";
+pub const CLASS_CANCELLED: &str = "cancelled";
+
+pub const ENROLLMENT_ENROLLED: &str = "enrolled";
+pub const ENROLLMENT_CANCELLED: &str = "cancelled";
+
+const CLASS_SESSION_SORT_FIELDS: [&str; 2] = ["start_time", "class_session_id"];
+
+#[derive(Deserialize)]
+pub struct ClassSessionId {
+    pub class_session_id: i32
+}
+
+#[derive(Deserialize)]
+pub struct ClassEnrollmentPath {
+    pub class_session_id: i32,
+    pub class_enrollment_id: i32
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ClassSessionQuery {
+    pub staff_id: Option<i32>,
+    pub service_variant_id: Option<i32>,
+    /// `scheduled` or `cancelled`
+    pub status: Option<String>,
+    /// Sessions starting at or after this time
+    pub from: Option<NaiveDateTime>,
+    /// Sessions starting before this time
+    pub to: Option<NaiveDateTime>
+}
+
+impl ClassSessionQuery {
+    fn filtered(&self) -> class_session::BoxedQuery<'static, Pg> {
+        let mut query = class_session::table.into_boxed();
+
+        if let Some(staff) = self.staff_id {
+            query = query.filter(class_session::staff_id.eq(staff));
+        }
+
+        if let Some(variant) = self.service_variant_id {
+            query = query.filter(class_session::service_variant_id.eq(variant));
+        }
+
+        if let Some(current_status) = &self.status {
+            query = query.filter(class_session::status.eq(current_status.clone()));
+        }
+
+        if let Some(from) = self.from {
+            query = query.filter(class_session::start_time.ge(from));
+        }
+
+        if let Some(to) = self.to {
+            query = query.filter(class_session::start_time.lt(to));
+        }
+
+        query
+    }
+
+    pub(super) fn find_page(&self, page: &Pagination, conn: &PgConnection) -> Result<(Vec<ClassSession>, i64), ApiError> {
+        let sort = page.sort(&CLASS_SESSION_SORT_FIELDS)?;
+        let total = self.filtered().count().get_result::<i64>(conn)?;
+
+        let query = match (sort.field.as_str(), sort.descending) {
+            ("start_time", false) => self.filtered().order(class_session::start_time.asc()),
+            ("start_time", true) => self.filtered().order(class_session::start_time.desc()),
+            (_, true) => self.filtered().order(class_session::class_session_id.desc()),
+            (_, false) => self.filtered().order(class_session::class_session_id.asc()),
+        };
+
+        let sessions = query
+            .then_order_by(class_session::class_session_id.asc())
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<ClassSession>(conn)?;
+
+        Ok((sessions, total))
+    }
+}
+
+/// One slot of a group class variant, taught by one staff member. The staff
+/// member is blocked from `blocked_from` to `blocked_until` however many
+/// customers enroll, so one-to-one appointments cannot overlap it.
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[primary_key(class_session_id)]
+#[table_name = "class_session"]
+pub struct ClassSession {
+    pub class_session_id: i32,
+    pub service_variant_id: i32,
+    pub staff_id: i32,
+    pub status: String,
+    /// Customers who can enroll
+    pub capacity: i32,
+    /// Price per enrolled customer
+    pub price: f64,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub blocked_from: NaiveDateTime,
+    pub blocked_until: NaiveDateTime,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Insertable)]
+#[table_name = "class_session"]
+pub struct ClassSessionCreate {
+    pub service_variant_id: i32,
+    pub staff_id: i32,
+    pub status: String,
+    pub capacity: i32,
+    pub price: f64,
+    pub start_time: NaiveDateTime,
+    pub end_time: NaiveDateTime,
+    pub blocked_from: NaiveDateTime,
+    pub blocked_until: NaiveDateTime
+}
+
+/// A customer's place in a class session, at the price the session had
+/// when they enrolled.
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[belongs_to(ClassSession)]
+#[primary_key(class_enrollment_id)]
+#[table_name = "class_enrollment"]
+pub struct ClassEnrollment {
+    pub class_enrollment_id: i32,
+    pub class_session_id: i32,
+    pub customer_id: i32,
+    pub status: String,
+    pub price: f64,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Insertable)]
+#[table_name = "class_enrollment"]
+pub struct ClassEnrollmentCreate {
+    pub class_session_id: i32,
+    pub customer_id: i32,
+    pub status: String,
+    pub price: f64
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct FullClassSession {
+    pub session: ClassSession,
+    /// Customers currently enrolled
+    pub enrolled: i64,
+    pub spots_left: i64
+}
+
+/// Schedules a session of a group class variant. Times come from the
+/// variant's duration and the service's padding, as for appointments.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ClassSessionSchedule {
+    pub service_variant_id: i32,
+    pub staff_id: i32,
+    pub start_time: NaiveDateTime,
+    /// Overrides the variant's capacity for this session
+    pub capacity: Option<i32>
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct EnrollmentCreate {
+    pub customer_id: i32
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RosterEntry {
+    pub enrollment: ClassEnrollment,
+    pub customer: Customer
+}
+
+impl Validate for ClassSessionSchedule {
+    fn validate(&self, errors: &mut FieldErrors) {
+        if let Some(capacity) = self.capacity {
+            errors.capacity("capacity", capacity);
+        }
+    }
+}
+
+/// Shortcuts that run `ClassRepository` on a connection from the server pool.
+impl ClassSession {
+    pub fn find_all(query: ClassSessionQuery, page: &Pagination) -> Result<(Vec<FullClassSession>, i64), ApiError> {
+        PgClassRepository::new(&db::establish_connection()).find_all(query, page)
+    }
+
+    pub fn find(id: i32) -> QueryResult<FullClassSession> {
+        PgClassRepository::new(&db::establish_connection()).find(id)
+    }
+
+    pub fn schedule(request: ClassSessionSchedule, actor: &Actor) -> Result<FullClassSession, ApiError> {
+        PgClassRepository::new(&db::establish_connection()).schedule(request, actor)
+    }
+
+    pub fn cancel(id: i32, actor: &Actor) -> Result<FullClassSession, ApiError> {
+        PgClassRepository::new(&db::establish_connection()).cancel(id, actor)
+    }
+
+    pub fn enroll(id: i32, request: EnrollmentCreate, actor: &Actor) -> Result<ClassEnrollment, ApiError> {
+        PgClassRepository::new(&db::establish_connection()).enroll(id, request, actor)
+    }
+
+    pub fn cancel_enrollment(id: i32, class_enrollment_id: i32, actor: &Actor) -> Result<ClassEnrollment, ApiError> {
+        PgClassRepository::new(&db::establish_connection()).cancel_enrollment(id, class_enrollment_id, actor)
+    }
+
+    pub fn roster(id: i32) -> QueryResult<Vec<RosterEntry>> {
+        PgClassRepository::new(&db::establish_connection()).roster(id)
+    }
+}
\ No newline at end of file
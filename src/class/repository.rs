@@ -0,0 +1,356 @@
+use crate::api_error::ApiError;
+use crate::appointment::{PgAppointmentRepository, PlannedSegment};
+use crate::audit::{self, Actor};
+use crate::customer::Customer;
+use crate::{
+    schema::block_extra_time,
+    schema::class_enrollment,
+    schema::class_session,
+    schema::customer,
+    schema::service,
+    schema::service_variant,
+    schema::staff,
+    schema::staff_service
+};
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::service::{BlockExtraTime, Service, ServiceVariant};
+use crate::validation::FieldErrors;
+use diesel::pg::PgConnection;
+use std::collections::HashMap;
+use tracing::debug_span;
+
+use super::model::*;
+
+/// Group class sessions and the customers enrolled in them.
+///
+/// Sessions block their staff member's calendar like appointment segments
+/// do, so the two cannot overlap.
+pub trait ClassRepository {
+    fn find_all(&self, query: ClassSessionQuery, page: &Pagination) -> Result<(Vec<FullClassSession>, i64), ApiError>;
+
+    fn find(&self, id: i32) -> QueryResult<FullClassSession>;
+
+    /// Schedules a session of a group class variant, or a 409 if it falls
+    /// outside the staff member's hours or overlaps their other bookings.
+    fn schedule(&self, request: ClassSessionSchedule, actor: &Actor) -> Result<FullClassSession, ApiError>;
+
+    /// Cancels the session and every enrollment in it.
+    fn cancel(&self, id: i32, actor: &Actor) -> Result<FullClassSession, ApiError>;
+
+    /// Enrolls a customer, or a 409 if the session is cancelled, full or
+    /// already has them.
+    fn enroll(&self, id: i32, request: EnrollmentCreate, actor: &Actor) -> Result<ClassEnrollment, ApiError>;
+
+    fn cancel_enrollment(&self, id: i32, class_enrollment_id: i32, actor: &Actor) -> Result<ClassEnrollment, ApiError>;
+
+    /// Customers enrolled in the session, in the order they enrolled.
+    fn roster(&self, id: i32) -> QueryResult<Vec<RosterEntry>>;
+}
+
+pub struct PgClassRepository<'a> {
+    conn: &'a PgConnection
+}
+
+impl<'a> PgClassRepository<'a> {
+    pub fn new(conn: &'a PgConnection) -> PgClassRepository<'a> {
+        PgClassRepository { conn }
+    }
+}
+
+impl<'a> ClassRepository for PgClassRepository<'a> {
+    fn find_all(&self, query: ClassSessionQuery, page: &Pagination) -> Result<(Vec<FullClassSession>, i64), ApiError> {
+        let span = debug_span!("Class::find_all");
+        let _entered = span.enter();
+
+        let (sessions, total) = query.find_page(page, self.conn)?;
+
+        Ok((self.with_enrolled(sessions)?, total))
+    }
+
+    fn find(&self, id: i32) -> QueryResult<FullClassSession> {
+        let span = debug_span!("Class::find");
+        let _entered = span.enter();
+
+        let session = class_session::table
+            .filter(class_session::class_session_id.eq(id))
+            .first::<ClassSession>(self.conn)?;
+
+        self.with_enrolled(vec![session])?
+            .pop()
+            .ok_or(diesel::result::Error::NotFound)
+    }
+
+    fn schedule(&self, request: ClassSessionSchedule, actor: &Actor) -> Result<FullClassSession, ApiError> {
+        let span = debug_span!("Class::schedule");
+        let _entered = span.enter();
+
+        let mut errors = FieldErrors::default();
+
+        let variant = service_variant::table
+            .filter(service_variant::service_variant_id.eq(request.service_variant_id))
+            .first::<ServiceVariant>(self.conn)
+            .optional()?;
+
+        let variant = match variant {
+            Some(variant) => variant,
+            None => {
+                errors.add("service_variant_id", "does not exist");
+                return Err(ApiError::validation(errors.into_map()));
+            },
+        };
+
+        let current_service = service::table
+            .filter(service::service_id.eq(variant.service_id))
+            .filter(service::deleted_at.is_null())
+            .first::<Service>(self.conn)
+            .optional()?;
+
+        let capacity = match current_service {
+            Some(current_service) => variant.capacity_of(&current_service),
+            None => {
+                errors.add("service_variant_id", "belongs to a deleted service");
+                0
+            },
+        };
+
+        if capacity == 1 {
+            errors.add("service_variant_id", "is not a group class; book an appointment instead");
+        }
+
+        let performs = staff_service::table
+            .inner_join(staff::table)
+            .filter(staff_service::staff_id.eq(request.staff_id))
+            .filter(staff_service::service_variant_id.eq(variant.service_variant_id))
+            .filter(staff_service::is_active.eq(1))
+            .filter(staff::deleted_at.is_null())
+            .count()
+            .get_result::<i64>(self.conn)? > 0;
+
+        if !performs {
+            errors.add("staff_id", "does not perform this service variant");
+        }
+
+        errors.into_result()?;
+
+        let blocked_time = block_extra_time::table
+            .filter(block_extra_time::service_id.eq(variant.service_id))
+            .first::<BlockExtraTime>(self.conn)?;
+        let planned = PlannedSegment::new(request.staff_id, &variant, &blocked_time, vec![], request.start_time);
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let appointments = PgAppointmentRepository::new(self.conn);
+            appointments.lock_staff(&[request.staff_id])?;
+
+            let (hours, busy) = appointments.calendar(&[request.staff_id], planned.blocked_from, planned.blocked_until)?;
+            let mut errors = FieldErrors::default();
+
+            for message in planned.span(0).conflicts(&hours, &busy) {
+                errors.add("start_time", message);
+            }
+
+            if !errors.is_empty() {
+                return Err(ApiError {
+                    errors: Some(errors.into_map()),
+                    ..ApiError::coded(409, "class_session.conflict", "The session does not fit the staff calendar".to_string())
+                });
+            }
+
+            let session_created: ClassSession = diesel::insert_into(class_session::table)
+                .values(ClassSessionCreate {
+                    service_variant_id: variant.service_variant_id,
+                    staff_id: request.staff_id,
+                    status: CLASS_SCHEDULED.to_string(),
+                    capacity: request.capacity.unwrap_or(capacity),
+                    price: planned.quote.price,
+                    start_time: planned.start_time,
+                    end_time: planned.end_time,
+                    blocked_from: planned.blocked_from,
+                    blocked_until: planned.blocked_until
+                })
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "class_session", session_created.class_session_id, &session_created)?;
+
+            Ok(self.find(session_created.class_session_id)?)
+        })
+    }
+
+    fn cancel(&self, id: i32, actor: &Actor) -> Result<FullClassSession, ApiError> {
+        let span = debug_span!("Class::cancel");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let session_before = class_session::table
+                .filter(class_session::class_session_id.eq(id))
+                .for_update()
+                .first::<ClassSession>(self.conn)?;
+
+            if session_before.status == CLASS_CANCELLED {
+                return Ok(self.find(id)?);
+            }
+
+            let session_cancelled: ClassSession = diesel::update(class_session::table)
+                .filter(class_session::class_session_id.eq(id))
+                .set(class_session::status.eq(CLASS_CANCELLED))
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "class_session", id, &session_before, &session_cancelled)?;
+
+            let enrolled = class_enrollment::table
+                .filter(class_enrollment::class_session_id.eq(id))
+                .filter(class_enrollment::status.eq(ENROLLMENT_ENROLLED))
+                .load::<ClassEnrollment>(self.conn)?;
+
+            for enrollment in &enrolled {
+                self.set_cancelled(enrollment, actor)?;
+            }
+
+            Ok(self.find(id)?)
+        })
+    }
+
+    fn enroll(&self, id: i32, request: EnrollmentCreate, actor: &Actor) -> Result<ClassEnrollment, ApiError> {
+        let span = debug_span!("Class::enroll");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            // held until commit, so two enrollments for the last spot cannot
+            // both see it free
+            let session = class_session::table
+                .filter(class_session::class_session_id.eq(id))
+                .for_update()
+                .first::<ClassSession>(self.conn)?;
+
+            let known = customer::table
+                .filter(customer::customer_id.eq(request.customer_id))
+                .count()
+                .get_result::<i64>(self.conn)? > 0;
+
+            if !known {
+                let mut errors = FieldErrors::default();
+                errors.add("customer_id", "does not exist");
+                errors.into_result()?;
+            }
+
+            if session.status == CLASS_CANCELLED {
+                return Err(ApiError::coded(409, "class_session.cancelled", "The class session is cancelled".to_string()));
+            }
+
+            let enrolled = class_enrollment::table
+                .filter(class_enrollment::class_session_id.eq(id))
+                .filter(class_enrollment::status.eq(ENROLLMENT_ENROLLED))
+                .select(class_enrollment::customer_id)
+                .load::<i32>(self.conn)?;
+
+            if enrolled.contains(&request.customer_id) {
+                return Err(ApiError::coded(409, "class_session.already_enrolled", "The customer is already enrolled in this class session".to_string()));
+            }
+
+            if enrolled.len() as i64 >= i64::from(session.capacity) {
+                return Err(ApiError::coded(409, "class_session.full", "The class session is full".to_string()));
+            }
+
+            let enrollment_created: ClassEnrollment = diesel::insert_into(class_enrollment::table)
+                .values(ClassEnrollmentCreate {
+                    class_session_id: id,
+                    customer_id: request.customer_id,
+                    status: ENROLLMENT_ENROLLED.to_string(),
+                    price: session.price
+                })
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "class_enrollment", enrollment_created.class_enrollment_id, &enrollment_created)?;
+
+            Ok(enrollment_created)
+        })
+    }
+
+    fn cancel_enrollment(&self, id: i32, class_enrollment_id: i32, actor: &Actor) -> Result<ClassEnrollment, ApiError> {
+        let span = debug_span!("Class::cancel_enrollment");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            // held until commit, so the status change and its audit row are
+            // written once even when two cancellations race
+            let enrollment_before = class_enrollment::table
+                .filter(class_enrollment::class_enrollment_id.eq(class_enrollment_id))
+                .filter(class_enrollment::class_session_id.eq(id))
+                .for_update()
+                .first::<ClassEnrollment>(self.conn)?;
+
+            if enrollment_before.status == ENROLLMENT_CANCELLED {
+                return Ok(enrollment_before);
+            }
+
+            Ok(self.set_cancelled(&enrollment_before, actor)?)
+        })
+    }
+
+    fn roster(&self, id: i32) -> QueryResult<Vec<RosterEntry>> {
+        let span = debug_span!("Class::roster");
+        let _entered = span.enter();
+
+        class_session::table
+            .filter(class_session::class_session_id.eq(id))
+            .select(class_session::class_session_id)
+            .first::<i32>(self.conn)?;
+
+        let entries = class_enrollment::table
+            .inner_join(customer::table)
+            .filter(class_enrollment::class_session_id.eq(id))
+            .filter(class_enrollment::status.eq(ENROLLMENT_ENROLLED))
+            .order(class_enrollment::class_enrollment_id.asc())
+            .load::<(ClassEnrollment, Customer)>(self.conn)?
+            .into_iter()
+            .map(|(enrollment, customer)| RosterEntry { enrollment, customer })
+            .collect();
+
+        Ok(entries)
+    }
+}
+
+impl<'a> PgClassRepository<'a> {
+    fn with_enrolled(&self, sessions: Vec<ClassSession>) -> QueryResult<Vec<FullClassSession>> {
+        let session_ids: Vec<i32> = sessions.iter().map(|session| session.class_session_id).collect();
+
+        let enrolled_in = class_enrollment::table
+            .filter(class_enrollment::class_session_id.eq_any(&session_ids))
+            .filter(class_enrollment::status.eq(ENROLLMENT_ENROLLED))
+            .select(class_enrollment::class_session_id)
+            .load::<i32>(self.conn)?;
+
+        let mut counts: HashMap<i32, i64> = HashMap::new();
+
+        for session_id in enrolled_in {
+            *counts.entry(session_id).or_default() += 1;
+        }
+
+        let full_sessions = sessions
+            .into_iter()
+            .map(|session| {
+                let enrolled = counts.get(&session.class_session_id).copied().unwrap_or_default();
+
+                FullClassSession {
+                    spots_left: (i64::from(session.capacity) - enrolled).max(0),
+                    enrolled,
+                    session
+                }
+            })
+            .collect();
+
+        Ok(full_sessions)
+    }
+
+    /// Cancels one enrollment and audits it, inside the caller's transaction.
+    fn set_cancelled(&self, enrollment_before: &ClassEnrollment, actor: &Actor) -> QueryResult<ClassEnrollment> {
+        let enrollment_cancelled: ClassEnrollment = diesel::update(class_enrollment::table)
+            .filter(class_enrollment::class_enrollment_id.eq(enrollment_before.class_enrollment_id))
+            .set(class_enrollment::status.eq(ENROLLMENT_CANCELLED))
+            .get_result(self.conn)?;
+
+        audit::updated(self.conn, actor, "class_enrollment", enrollment_before.class_enrollment_id, enrollment_before, &enrollment_cancelled)?;
+
+        Ok(enrollment_cancelled)
+    }
+}
\ No newline at end of file
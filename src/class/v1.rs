@@ -0,0 +1,144 @@
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::class::{ClassEnrollmentPath, ClassSession, ClassSessionId, ClassSessionQuery, ClassSessionSchedule, EnrollmentCreate};
+use crate::pagination::{PageParams, Pagination};
+use crate::validation::Valid;
+use actix_web::{get, post, web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/class_sessions",
+    tag = "class",
+    operation_id = "class_session_find_all",
+    params(
+        ClassSessionQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Class sessions with their enrollment counts", body = [FullClassSession], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/class_sessions")]
+async fn find_all(query: web::Query<ClassSessionQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (sessions, total) = ClassSession::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, sessions))
+}
+
+/// Schedules a session of a group class. The staff member is blocked for
+/// the whole session, so it cannot overlap their appointments.
+#[utoipa::path(
+    post,
+    path = "/api/v1/class_sessions",
+    tag = "class",
+    operation_id = "class_session_schedule",
+    request_body = ClassSessionSchedule,
+    responses(
+        (status = 201, description = "Scheduled class session", body = FullClassSession),
+        (status = 409, description = "The session is outside the staff member's hours or overlaps another booking", body = ErrorBody),
+        (status = 422, description = "Unknown or one-to-one variant, or staff who do not perform it", body = ErrorBody)
+    )
+)]
+#[post("/class_sessions")]
+async fn schedule(request: Valid<ClassSessionSchedule>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let session = ClassSession::schedule(request.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(session))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/class_sessions/{class_session_id}",
+    tag = "class",
+    operation_id = "class_session_find",
+    params(("class_session_id" = i32, Path, description = "Class session id")),
+    responses(
+        (status = 200, description = "Class session with its enrollment count", body = FullClassSession)
+    )
+)]
+#[get("/class_sessions/{class_session_id}")]
+async fn find(id: web::Path<ClassSessionId>) -> Result<HttpResponse, ApiError> {
+    let session = ClassSession::find(id.class_session_id)?;
+    Ok(HttpResponse::Ok().json(session))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/class_sessions/{class_session_id}/cancel",
+    tag = "class",
+    operation_id = "class_session_cancel",
+    params(("class_session_id" = i32, Path, description = "Class session id")),
+    responses(
+        (status = 200, description = "Cancelled class session; its enrollments are cancelled too", body = FullClassSession)
+    )
+)]
+#[post("/class_sessions/{class_session_id}/cancel")]
+async fn cancel(id: web::Path<ClassSessionId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let session = ClassSession::cancel(id.class_session_id, &actor)?;
+    Ok(HttpResponse::Ok().json(session))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/class_sessions/{class_session_id}/enrollments",
+    tag = "class",
+    operation_id = "class_session_enroll",
+    params(("class_session_id" = i32, Path, description = "Class session id")),
+    request_body = EnrollmentCreate,
+    responses(
+        (status = 201, description = "Enrollment", body = ClassEnrollment),
+        (status = 409, description = "The session is cancelled, full, or already has the customer", body = ErrorBody),
+        (status = 422, description = "Unknown customer", body = ErrorBody)
+    )
+)]
+#[post("/class_sessions/{class_session_id}/enrollments")]
+async fn enroll(id: web::Path<ClassSessionId>, request: web::Json<EnrollmentCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let enrollment = ClassSession::enroll(id.class_session_id, request.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(enrollment))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/class_sessions/{class_session_id}/enrollments/{class_enrollment_id}/cancel",
+    tag = "class",
+    operation_id = "class_session_cancel_enrollment",
+    params(
+        ("class_session_id" = i32, Path, description = "Class session id"),
+        ("class_enrollment_id" = i32, Path, description = "Enrollment id")
+    ),
+    responses(
+        (status = 200, description = "Cancelled enrollment; its spot is free again", body = ClassEnrollment)
+    )
+)]
+#[post("/class_sessions/{class_session_id}/enrollments/{class_enrollment_id}/cancel")]
+async fn cancel_enrollment(path: web::Path<ClassEnrollmentPath>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let enrollment = ClassSession::cancel_enrollment(path.class_session_id, path.class_enrollment_id, &actor)?;
+    Ok(HttpResponse::Ok().json(enrollment))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/class_sessions/{class_session_id}/roster",
+    tag = "class",
+    operation_id = "class_session_roster",
+    params(("class_session_id" = i32, Path, description = "Class session id")),
+    responses(
+        (status = 200, description = "Enrolled customers in the order they enrolled", body = [RosterEntry])
+    )
+)]
+#[get("/class_sessions/{class_session_id}/roster")]
+async fn roster(id: web::Path<ClassSessionId>) -> Result<HttpResponse, ApiError> {
+    let entries = ClassSession::roster(id.class_session_id)?;
+    Ok(HttpResponse::Ok().json(entries))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(schedule);
+    cfg.service(find);
+    cfg.service(cancel);
+    cfg.service(enroll);
+    cfg.service(cancel_enrollment);
+    cfg.service(roster);
+}
\ No newline at end of file
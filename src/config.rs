@@ -0,0 +1,366 @@
+use actix_web::http::Method;
+use serde::Deserialize;
+use std::env;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use std::process;
+use std::str::FromStr;
+
+/// Read when `CONFIG_FILE` is not set. A missing default file is not an
+/// error, everything can come from the environment instead.
+const DEFAULT_CONFIG_FILE: &str = "config.json";
+
+lazy_static! {
+    pub static ref CONFIG: Config = Config::load().unwrap_or_else(|errors| {
+        eprintln!("Invalid configuration:");
+        errors.iter().for_each(|error| eprintln!("  - {}", error));
+        process::exit(1);
+    });
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub cors: CorsConfig,
+    pub database: DatabaseConfig,
+    pub log: LogConfig,
+    pub appointment: AppointmentConfig,
+    pub sms: SmsConfig,
+    pub webhook: WebhookConfig
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerConfig {
+    pub host: String,
+    pub port: u16,
+    /// Defaults to one worker per logical CPU
+    pub workers: Option<usize>,
+    /// Seconds an idle connection is kept open, 0 disables keep-alive
+    pub keep_alive: usize,
+    /// Seconds to let in-flight requests and background jobs finish on SIGTERM
+    pub shutdown_timeout: u64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CorsConfig {
+    /// Full origins such as `https://app.example.com`, or `*` for any
+    pub allowed_origins: Vec<String>,
+    pub allowed_methods: Vec<String>,
+    pub max_age: usize
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: String,
+    pub pool_max_size: u32,
+    pub pool_min_idle: Option<u32>,
+    /// Seconds to wait for a free connection before giving up
+    pub pool_timeout: u64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LogConfig {
+    /// `env_logger` filter, e.g. `info` or `info,actix_web=debug`
+    pub level: String
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppointmentConfig {
+    /// Days ahead that occurrences of a recurring series are booked
+    pub series_horizon_days: i64
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SmsConfig {
+    /// `log` to write messages to the log, `http` to post them to a gateway
+    pub provider: String,
+    /// File the `log` provider also appends messages to
+    pub log_file: Option<String>,
+    /// Gateway the `http` provider posts to, required for that provider
+    pub http_url: Option<String>,
+    pub http_token: Option<String>,
+    pub from: Option<String>,
+    /// Secret the gateway sends in `X-Sms-Secret` on status callbacks;
+    /// callbacks are refused while it is unset
+    pub status_secret: Option<String>
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    /// Seconds between checks for due deliveries
+    pub poll_seconds: u64,
+    /// Attempts before a delivery is marked failed
+    pub max_attempts: i32,
+    /// Seconds before the first retry, doubled for each one after it
+    pub retry_base_seconds: i64
+}
+
+impl Default for ServerConfig {
+    fn default() -> ServerConfig {
+        ServerConfig { host: "127.0.0.1".to_string(), port: 8000, workers: None, keep_alive: 5, shutdown_timeout: 30 }
+    }
+}
+
+impl Default for CorsConfig {
+    fn default() -> CorsConfig {
+        CorsConfig {
+            allowed_origins: vec!["http://localhost:3000".to_string()],
+            allowed_methods: ["GET", "POST", "PUT", "PATCH", "DELETE"].iter().map(|method| method.to_string()).collect(),
+            max_age: 3600
+        }
+    }
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> DatabaseConfig {
+        DatabaseConfig { url: String::new(), pool_max_size: 10, pool_min_idle: None, pool_timeout: 30 }
+    }
+}
+
+impl Default for LogConfig {
+    fn default() -> LogConfig {
+        LogConfig { level: "info".to_string() }
+    }
+}
+
+impl Default for AppointmentConfig {
+    fn default() -> AppointmentConfig {
+        AppointmentConfig { series_horizon_days: 90 }
+    }
+}
+
+impl Default for SmsConfig {
+    fn default() -> SmsConfig {
+        SmsConfig { provider: "log".to_string(), log_file: None, http_url: None, http_token: None, from: None, status_secret: None }
+    }
+}
+
+impl Default for WebhookConfig {
+    fn default() -> WebhookConfig {
+        WebhookConfig { poll_seconds: 5, max_attempts: 8, retry_base_seconds: 30 }
+    }
+}
+
+impl Config {
+    /// Reads the config file, applies environment overrides and validates
+    /// the result, returning every problem found rather than the first.
+    pub fn load() -> Result<Config, Vec<String>> {
+        let mut config = Config::from_file()?;
+        let mut errors = Vec::new();
+
+        config.apply_env(&mut errors);
+        config.validate(&mut errors);
+
+        match errors.is_empty() {
+            true => Ok(config),
+            false => Err(errors),
+        }
+    }
+
+    fn from_file() -> Result<Config, Vec<String>> {
+        let (path, required) = match env::var("CONFIG_FILE") {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+
+        if !required && !Path::new(&path).exists() {
+            return Ok(Config::default());
+        }
+
+        let contents = fs::read_to_string(&path)
+            .map_err(|err| vec![format!("could not read config file {}: {}", path, err)])?;
+
+        serde_json::from_str(&contents)
+            .map_err(|err| vec![format!("config file {} is invalid: {}", path, err)])
+    }
+
+    fn apply_env(&mut self, errors: &mut Vec<String>) {
+        override_with(&mut self.server.host, "HOST", errors);
+        override_with(&mut self.server.port, "PORT", errors);
+        override_with_some(&mut self.server.workers, "WORKERS", errors);
+        override_with(&mut self.server.keep_alive, "KEEP_ALIVE", errors);
+        override_with(&mut self.server.shutdown_timeout, "SHUTDOWN_TIMEOUT", errors);
+
+        override_list(&mut self.cors.allowed_origins, "CORS_ALLOWED_ORIGINS");
+        override_list(&mut self.cors.allowed_methods, "CORS_ALLOWED_METHODS");
+        override_with(&mut self.cors.max_age, "CORS_MAX_AGE", errors);
+
+        override_with(&mut self.database.url, "DATABASE_URL", errors);
+        override_with(&mut self.database.pool_max_size, "DATABASE_POOL_MAX_SIZE", errors);
+        override_with_some(&mut self.database.pool_min_idle, "DATABASE_POOL_MIN_IDLE", errors);
+        override_with(&mut self.database.pool_timeout, "DATABASE_POOL_TIMEOUT", errors);
+
+        override_with(&mut self.log.level, "LOG_LEVEL", errors);
+
+        override_with(&mut self.appointment.series_horizon_days, "SERIES_HORIZON_DAYS", errors);
+
+        override_with(&mut self.sms.provider, "SMS_PROVIDER", errors);
+        override_with_some(&mut self.sms.log_file, "SMS_LOG_FILE", errors);
+        override_with_some(&mut self.sms.http_url, "SMS_HTTP_URL", errors);
+        override_with_some(&mut self.sms.http_token, "SMS_HTTP_TOKEN", errors);
+        override_with_some(&mut self.sms.from, "SMS_FROM", errors);
+        override_with_some(&mut self.sms.status_secret, "SMS_STATUS_SECRET", errors);
+
+        override_with(&mut self.webhook.poll_seconds, "WEBHOOK_POLL_SECONDS", errors);
+        override_with(&mut self.webhook.max_attempts, "WEBHOOK_MAX_ATTEMPTS", errors);
+        override_with(&mut self.webhook.retry_base_seconds, "WEBHOOK_RETRY_BASE_SECONDS", errors);
+    }
+
+    fn validate(&self, errors: &mut Vec<String>) {
+        if self.server.host.trim().is_empty() {
+            errors.push("server.host (HOST) must not be empty".to_string());
+        }
+
+        if self.server.port == 0 {
+            errors.push("server.port (PORT) must be between 1 and 65535".to_string());
+        }
+
+        if self.server.workers == Some(0) {
+            errors.push("server.workers (WORKERS) must be at least 1".to_string());
+        }
+
+        if self.cors.allowed_origins.is_empty() {
+            errors.push("cors.allowed_origins (CORS_ALLOWED_ORIGINS) must list at least one origin or \"*\"".to_string());
+        }
+
+        for origin in &self.cors.allowed_origins {
+            if origin != "*" && !is_origin(origin) {
+                errors.push(format!("cors.allowed_origins: {:?} is not an origin like https://app.example.com", origin));
+            }
+        }
+
+        for method in &self.cors.allowed_methods {
+            if Method::from_bytes(method.as_bytes()).is_err() || method.to_uppercase() != *method {
+                errors.push(format!("cors.allowed_methods: {:?} is not an upper case HTTP method", method));
+            }
+        }
+
+        if self.database.url.is_empty() {
+            errors.push("database.url (DATABASE_URL) must be set".to_string());
+        } else if !self.database.url.starts_with("postgres://") && !self.database.url.starts_with("postgresql://") {
+            errors.push("database.url (DATABASE_URL) must be a postgres:// URL".to_string());
+        }
+
+        if self.database.pool_max_size == 0 {
+            errors.push("database.pool_max_size (DATABASE_POOL_MAX_SIZE) must be at least 1".to_string());
+        }
+
+        if let Some(min_idle) = self.database.pool_min_idle {
+            if min_idle > self.database.pool_max_size {
+                errors.push(format!("database.pool_min_idle ({}) must not exceed database.pool_max_size ({})", min_idle, self.database.pool_max_size));
+            }
+        }
+
+        if let Err(error) = validate_log_filter(&self.log.level) {
+            errors.push(format!("log.level (LOG_LEVEL): {}", error));
+        }
+
+        if !(1..=730).contains(&self.appointment.series_horizon_days) {
+            errors.push("appointment.series_horizon_days (SERIES_HORIZON_DAYS) must be between 1 and 730".to_string());
+        }
+
+        match (self.sms.provider.as_str(), &self.sms.http_url) {
+            ("log", _) => {},
+            ("http", None) => errors.push("sms.http_url (SMS_HTTP_URL) must be set when sms.provider is http".to_string()),
+            ("http", Some(url)) if !url.starts_with("http://") && !url.starts_with("https://") => {
+                errors.push("sms.http_url (SMS_HTTP_URL) must be an http:// or https:// URL".to_string());
+            },
+            ("http", Some(_)) => {},
+            (provider, _) => errors.push(format!("sms.provider (SMS_PROVIDER): {:?} is not log or http", provider)),
+        }
+
+        match (self.sms.provider.as_str(), &self.sms.status_secret) {
+            (_, Some(secret)) if secret.len() < 16 => {
+                errors.push("sms.status_secret (SMS_STATUS_SECRET) must be at least 16 characters".to_string());
+            },
+            ("http", None) => errors.push("sms.status_secret (SMS_STATUS_SECRET) must be set when sms.provider is http".to_string()),
+            _ => {},
+        }
+
+        if self.webhook.poll_seconds == 0 {
+            errors.push("webhook.poll_seconds (WEBHOOK_POLL_SECONDS) must be at least 1".to_string());
+        }
+
+        if self.webhook.max_attempts < 1 {
+            errors.push("webhook.max_attempts (WEBHOOK_MAX_ATTEMPTS) must be at least 1".to_string());
+        }
+
+        if self.webhook.retry_base_seconds < 1 {
+            errors.push("webhook.retry_base_seconds (WEBHOOK_RETRY_BASE_SECONDS) must be at least 1".to_string());
+        }
+    }
+
+    pub fn bind_address(&self) -> String {
+        format!("{}:{}", self.server.host, self.server.port)
+    }
+}
+
+fn override_with<T>(field: &mut T, key: &str, errors: &mut Vec<String>)
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(key) {
+        match value.parse() {
+            Ok(value) => *field = value,
+            Err(err) => errors.push(format!("{}={:?} is invalid: {}", key, value, err)),
+        }
+    }
+}
+
+fn override_with_some<T>(field: &mut Option<T>, key: &str, errors: &mut Vec<String>)
+where
+    T: FromStr,
+    T::Err: Display,
+{
+    if let Ok(value) = env::var(key) {
+        match value.parse() {
+            Ok(value) => *field = Some(value),
+            Err(err) => errors.push(format!("{}={:?} is invalid: {}", key, value, err)),
+        }
+    }
+}
+
+/// Comma separated, e.g. `CORS_ALLOWED_METHODS=GET,POST`.
+fn override_list(field: &mut Vec<String>, key: &str) {
+    if let Ok(value) = env::var(key) {
+        *field = value.split(',').map(str::trim).filter(|item| !item.is_empty()).map(String::from).collect();
+    }
+}
+
+/// Scheme and host with an optional port, and nothing after it.
+fn is_origin(origin: &str) -> bool {
+    let host = match origin.strip_prefix("https://").or_else(|| origin.strip_prefix("http://")) {
+        Some(host) => host,
+        None => return false,
+    };
+
+    !host.is_empty() && !host.contains('/') && !host.contains('?') && !host.contains('#')
+}
+
+/// Accepts the `env_logger` filter syntax: a level, or comma separated
+/// `module=level` directives.
+fn validate_log_filter(filter: &str) -> Result<(), String> {
+    if filter.trim().is_empty() {
+        return Err("must not be empty".to_string());
+    }
+
+    for directive in filter.split(',') {
+        let level = directive.rsplit('=').next().unwrap_or_default().trim();
+
+        if log::LevelFilter::from_str(level).is_err() {
+            return Err(format!("{:?} does not end in a level such as info or debug", directive));
+        }
+    }
+
+    Ok(())
+}
\ No newline at end of file
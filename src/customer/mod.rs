@@ -0,0 +1,6 @@
+pub mod model;
+pub mod routes;
+pub mod v1;
+
+pub use model::*;
+pub use routes::init_routes;
\ No newline at end of file
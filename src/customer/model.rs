@@ -0,0 +1,137 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::{
+    schema::customer::{self, dsl::*},
+    schema::notification_preference,
+    schema::sms_message
+};
+use crate::diesel::prelude::*;
+use crate::validation::{FieldErrors, Validate};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::notification::model::NotificationPreferenceCreate;
+use tracing::debug_span;
+
+#[derive(Deserialize)]
+pub struct CustomerId {
+    pub customer_id: i32
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
+#[table_name = "customer"]
+pub struct CustomerCreate {
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>
+}
+
+#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, Debug, ToSchema)]
+#[primary_key(customer_id)]
+#[table_name = "customer"]
+pub struct Customer {
+    pub customer_id: i32,
+    pub first_name: String,
+    pub last_name: String,
+    pub email: Option<String>,
+    pub phone: Option<String>
+}
+
+impl Validate for CustomerCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("first_name", &self.first_name);
+        errors.required("last_name", &self.last_name);
+
+        if let Some(customer_email) = &self.email {
+            errors.email("email", customer_email);
+        }
+
+        if let Some(customer_phone) = &self.phone {
+            errors.phone("phone", customer_phone);
+        }
+    }
+}
+
+impl Customer {
+    pub fn find_all() -> QueryResult<Vec<Self>> {
+        let span = debug_span!("Customer::find_all");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+        customer.order(customer::customer_id.asc()).load::<Self>(&conn)
+    }
+
+    pub fn find(id: i32) -> QueryResult<Self> {
+        let span = debug_span!("Customer::find");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        customer.filter(customer::customer_id.eq(id)).first::<Self>(&conn)
+    }
+
+    pub fn create(customer_create: CustomerCreate) -> QueryResult<Self> {
+        let span = debug_span!("Customer::create");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        conn.transaction(|| {
+            let customer_created: Self = diesel::insert_into(customer::table)
+                .values(customer_create)
+                .get_result(&conn)?;
+
+            // generate default notification preferences
+            let preference = NotificationPreferenceCreate {
+                customer_id: customer_created.customer_id,
+                email_enabled: 1,
+                sms_enabled: 0
+            };
+
+            diesel::insert_into(notification_preference::table)
+                .values(preference)
+                .execute(&conn)?;
+
+            Ok(customer_created)
+        })
+    }
+
+    pub fn update(id: i32, customer_update: CustomerCreate) -> Result<Self, ApiError> {
+        let span = debug_span!("Customer::update");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let customer_updated = diesel::update(customer::table)
+            .filter(customer::customer_id.eq(id))
+            .set(customer_update)
+            .get_result(&conn)?;
+
+        Ok(customer_updated)
+    }
+
+    pub fn delete(id: i32) -> Result<usize, ApiError> {
+        let span = debug_span!("Customer::delete");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        // also make sure to delete other customer data
+        diesel::delete(sms_message::table)
+            .filter(sms_message::customer_id.eq(id))
+            .execute(&conn)?;
+
+        diesel::delete(notification_preference::table)
+            .filter(notification_preference::customer_id.eq(id))
+            .execute(&conn)?;
+
+        let res = diesel::delete(
+                customer::table
+                    .filter(customer::customer_id.eq(id))
+            )
+            .execute(&conn)?;
+
+        Ok(res)
+    }
+}
\ No newline at end of file
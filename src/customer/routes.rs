@@ -0,0 +1,93 @@
+use crate::api_error::ApiError;
+use crate::customer::{Customer, CustomerCreate, CustomerId};
+use crate::validation::Valid;
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/customer",
+    tag = "customer",
+    operation_id = "legacy_customer_find_all",
+    responses(
+        (status = 200, description = "Customers", body = [Customer])
+    )
+)]
+#[get("/customer")]
+async fn find_all() -> Result<HttpResponse, ApiError> {
+    let customers = Customer::find_all()?;
+    Ok(HttpResponse::Ok().json(customers))
+}
+
+#[utoipa::path(
+    get,
+    path = "/customer/{customer_id}",
+    tag = "customer",
+    operation_id = "legacy_customer_find",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Customer", body = Customer)
+    )
+)]
+#[get("/customer/{customer_id}")]
+async fn find(id: web::Path<CustomerId>) -> Result<HttpResponse, ApiError> {
+    let customer = Customer::find(id.customer_id)?;
+    Ok(HttpResponse::Ok().json(customer))
+}
+
+#[utoipa::path(
+    post,
+    path = "/customer",
+    tag = "customer",
+    operation_id = "legacy_customer_create",
+    request_body = CustomerCreate,
+    responses(
+        (status = 200, description = "Created customer", body = Customer)
+    )
+)]
+#[post("/customer")]
+async fn create(customer: Valid<CustomerCreate>) -> Result<HttpResponse, ApiError> {
+    let customer = Customer::create(customer.into_inner())?;
+    Ok(HttpResponse::Ok().json(customer))
+}
+
+#[utoipa::path(
+    put,
+    path = "/customer/{customer_id}",
+    tag = "customer",
+    operation_id = "legacy_customer_update",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    request_body = CustomerCreate,
+    responses(
+        (status = 200, description = "Updated customer", body = Customer)
+    )
+)]
+#[put("/customer/{customer_id}")]
+async fn update(id: web::Path<CustomerId>, customer: Valid<CustomerCreate>) -> Result<HttpResponse, ApiError> {
+    let customer = Customer::update(id.customer_id, customer.into_inner())?;
+    Ok(HttpResponse::Ok().json(customer))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/customer/{customer_id}",
+    tag = "customer",
+    operation_id = "legacy_customer_delete",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Number of customers deleted", body = Deleted)
+    )
+)]
+#[delete("/customer/{customer_id}")]
+async fn delete(id: web::Path<CustomerId>) -> Result<HttpResponse, ApiError> {
+    let customer_deleted = Customer::delete(id.customer_id)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": customer_deleted })))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(find);
+    cfg.service(create);
+    cfg.service(update);
+    cfg.service(delete);
+}
\ No newline at end of file
@@ -1,13 +1,37 @@
-use diesel::prelude::*;
+use crate::config::CONFIG;
 use diesel::pg::PgConnection;
-use dotenv::dotenv;
-use std::env;
+use diesel::r2d2::{ConnectionManager, Pool, PooledConnection, State};
+use std::time::Duration;
 
-pub fn establish_connection() -> PgConnection {
-    dotenv().ok();
+pub type DbConnection = PooledConnection<ConnectionManager<PgConnection>>;
 
-    let database_url = env::var("DATABASE_URL")
-        .expect("DATABASE_URL must be set");
-    PgConnection::establish(&database_url)
-        .expect(&format!("Error connecting to {}", database_url))
+lazy_static! {
+    static ref POOL: Pool<ConnectionManager<PgConnection>> = {
+        let database = &CONFIG.database;
+
+        // connections are opened on demand so the server can start before the database
+        Pool::builder()
+            .max_size(database.pool_max_size)
+            .min_idle(database.pool_min_idle)
+            .connection_timeout(Duration::from_secs(database.pool_timeout))
+            .build_unchecked(ConnectionManager::new(database.url.as_str()))
+    };
+}
+
+pub fn establish_connection() -> DbConnection {
+    POOL.get()
+        .unwrap_or_else(|err| panic!("Error getting a database connection: {}", err))
+}
+
+/// Like `establish_connection`, but gives up after `timeout` instead of panicking.
+pub fn connection_timeout(timeout: Duration) -> Result<DbConnection, String> {
+    POOL.get_timeout(timeout).map_err(|err| err.to_string())
+}
+
+pub fn pool_state() -> State {
+    POOL.state()
+}
+
+pub fn pool_max_size() -> u32 {
+    POOL.max_size()
 }
\ No newline at end of file
@@ -0,0 +1,64 @@
+use crate::db;
+use actix_web::{get, web, HttpResponse};
+use diesel_migrations::MigrationConnection;
+use serde_json::json;
+use std::time::Duration;
+
+// MIGRATION_VERSIONS, generated by build.rs from the migrations directory
+include!(concat!(env!("OUT_DIR"), "/migrations.rs"));
+
+/// Kept short so a probe fails fast instead of waiting out the pool timeout.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(2);
+
+enum Readiness {
+    DatabaseDown(String),
+    MigrationsUnknown(String),
+    Pending(Vec<&'static str>),
+}
+
+/// Whether the database is reachable and which expected migrations it has not run.
+fn check() -> Readiness {
+    let conn = match db::connection_timeout(PROBE_TIMEOUT) {
+        Ok(conn) => conn,
+        Err(err) => return Readiness::DatabaseDown(err),
+    };
+
+    let applied = match conn.previously_run_migration_versions() {
+        Ok(applied) => applied,
+        Err(err) => return Readiness::MigrationsUnknown(err.to_string()),
+    };
+
+    Readiness::Pending(MIGRATION_VERSIONS.iter().filter(|version| !applied.contains(**version)).copied().collect())
+}
+
+/// The process is up and serving requests.
+#[get("/healthz")]
+async fn healthz() -> HttpResponse {
+    HttpResponse::Ok().json(json!({ "status": "ok" }))
+}
+
+/// The database is reachable and has every migration this build expects.
+#[get("/readyz")]
+async fn readyz() -> HttpResponse {
+    let (ready, database, migrations) = match check() {
+        Readiness::Pending(pending) if pending.is_empty() => (true, json!("ok"), json!("ok")),
+        Readiness::Pending(pending) => (false, json!("ok"), json!({ "pending": pending })),
+        Readiness::MigrationsUnknown(err) => (false, json!("ok"), json!({ "error": err })),
+        Readiness::DatabaseDown(err) => (false, json!({ "error": err }), json!("unknown")),
+    };
+
+    let body = json!({
+        "status": if ready { "ready" } else { "unavailable" },
+        "checks": { "database": database, "migrations": migrations }
+    });
+
+    match ready {
+        true => HttpResponse::Ok().json(body),
+        false => HttpResponse::ServiceUnavailable().json(body),
+    }
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(healthz);
+    cfg.service(readyz);
+}
\ No newline at end of file
@@ -0,0 +1,75 @@
+#[macro_use]
+extern crate log;
+#[macro_use]
+extern crate diesel;
+#[macro_use]
+extern crate lazy_static;
+extern crate diesel_migrations;
+
+use actix_web::dev::Service;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{web, http};
+
+pub mod api_error;
+pub mod appointment;
+pub mod audit;
+pub mod class;
+pub mod config;
+pub mod customer;
+pub mod db;
+pub mod health;
+pub mod logging;
+pub mod metrics;
+pub mod notification;
+pub mod openapi;
+pub mod pagination;
+pub mod request_id;
+pub mod resource;
+pub mod schema;
+pub mod search;
+pub mod staff;
+pub mod store;
+pub mod service;
+pub mod validation;
+pub mod webhook;
+
+/// Every route the server answers, shared with the integration tests.
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/api/v1")
+        .configure(staff::v1::init_routes)
+        .configure(store::v1::init_routes)
+        .configure(service::v1::init_routes)
+        .configure(customer::v1::init_routes)
+        .configure(notification::v1::init_routes)
+        .configure(webhook::v1::init_routes)
+        .configure(audit::v1::init_routes)
+        .configure(search::v1::init_routes)
+        .configure(appointment::v1::init_routes)
+        .configure(class::v1::init_routes)
+        .configure(resource::v1::init_routes));
+
+    openapi::init_routes(cfg);
+    health::init_routes(cfg);
+    metrics::init_routes(cfg);
+
+    // unversioned paths stay available until clients move to /api/v1
+    cfg.service(web::scope("")
+        .wrap_fn(|req, srv| {
+            let res = srv.call(req);
+            async {
+                let mut res = res.await?;
+                let headers = res.headers_mut();
+                headers.insert(HeaderName::from_static("deprecation"), HeaderValue::from_static("true"));
+                // appended so pagination links on the same response are kept
+                headers.append(http::header::LINK, HeaderValue::from_static("</docs>; rel=\"deprecation\""));
+                Ok(res)
+            }
+        })
+        .configure(staff::init_routes)
+        .configure(store::init_routes)
+        .configure(service::init_routes)
+        .configure(customer::init_routes)
+        .configure(notification::init_routes)
+        .configure(webhook::init_routes)
+        .configure(audit::init_routes));
+}
\ No newline at end of file
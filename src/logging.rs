@@ -0,0 +1,249 @@
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Map, Value};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::future::Future;
+use std::io::Write;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Level, Metadata, Span, Subscriber};
+
+/// Writes every log line to stderr as a single JSON object.
+///
+/// `log` records (`info!`, `error!`, ...) go through `env_logger` with a JSON
+/// format, `tracing` events and span timings go through `JsonSubscriber`.
+/// Both attach the `request_id` of the request being handled on this thread,
+/// and both are filtered by the same `log.level` directives.
+pub fn init(filter: &str) {
+    env_logger::Builder::new()
+        .parse_filters(filter)
+        .format(|buf, record| {
+            let mut line = Map::new();
+            line.insert("message".to_string(), json!(record.args().to_string()));
+            writeln!(buf, "{}", to_line(&record.level().to_string(), record.target(), line))
+        })
+        .init();
+
+    tracing::subscriber::set_global_default(JsonSubscriber::default())
+        .expect("tracing subscriber is only set once");
+}
+
+/// The request id of the request span entered on this thread, if any.
+pub fn current_request_id() -> Option<String> {
+    let span = STACK.with(|stack| stack.borrow().last().copied())?;
+    let spans = SPANS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    spans.get(&span).and_then(|data| data.request_id.clone())
+}
+
+fn to_line(level: &str, target: &str, fields: Map<String, Value>) -> Value {
+    let mut line = Map::new();
+    line.insert("timestamp".to_string(), json!(Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true)));
+    line.insert("level".to_string(), json!(level));
+    line.insert("target".to_string(), json!(target));
+
+    if let Some(request_id) = current_request_id() {
+        line.insert("request_id".to_string(), json!(request_id));
+    }
+
+    line.extend(fields);
+    Value::Object(line)
+}
+
+fn log_level(level: &Level) -> log::Level {
+    match *level {
+        Level::ERROR => log::Level::Error,
+        Level::WARN => log::Level::Warn,
+        Level::INFO => log::Level::Info,
+        Level::DEBUG => log::Level::Debug,
+        Level::TRACE => log::Level::Trace,
+    }
+}
+
+/// Whether the `log.level` directives let `level` through for `target`.
+fn log_enabled(level: &Level, target: &str) -> bool {
+    log::logger().enabled(&log::Metadata::builder().level(log_level(level)).target(target).build())
+}
+
+lazy_static! {
+    static ref SPANS: Mutex<HashMap<u64, SpanData>> = Mutex::new(HashMap::new());
+}
+
+thread_local! {
+    /// Ids of the spans entered on this thread, innermost last.
+    static STACK: RefCell<Vec<u64>> = const { RefCell::new(Vec::new()) };
+}
+
+struct SpanData {
+    metadata: &'static Metadata<'static>,
+    fields: Map<String, Value>,
+    request_id: Option<String>,
+    started: Instant,
+    refs: usize
+}
+
+/// Collects event and span fields into a JSON object.
+struct JsonVisitor<'a>(&'a mut Map<String, Value>);
+
+impl<'a> Visit for JsonVisitor<'a> {
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.insert(field.name().to_string(), json!(value));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.insert(field.name().to_string(), json!(format!("{:?}", value)));
+    }
+}
+
+/// Spans and events are filtered by the `log.level` directives, except the
+/// request span from `request_id`, which is always tracked so its request id
+/// reaches every line logged while the request is handled. A span is written
+/// out, with its duration, when it closes and its level is enabled.
+#[derive(Default)]
+pub struct JsonSubscriber {
+    next_id: AtomicU64
+}
+
+impl Subscriber for JsonSubscriber {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        (metadata.is_span() && metadata.target() == "request") || log_enabled(metadata.level(), metadata.target())
+    }
+
+    fn new_span(&self, attributes: &Attributes<'_>) -> Id {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let mut fields = Map::new();
+        attributes.record(&mut JsonVisitor(&mut fields));
+
+        let parent = match attributes.parent() {
+            Some(parent) => Some(parent.into_u64()),
+            None if attributes.is_contextual() => STACK.with(|stack| stack.borrow().last().copied()),
+            None => None,
+        };
+
+        let mut spans = SPANS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        let request_id = match fields.remove("request_id") {
+            Some(Value::String(request_id)) => Some(request_id),
+            _ => parent.and_then(|parent| spans.get(&parent)).and_then(|data| data.request_id.clone()),
+        };
+
+        spans.insert(id, SpanData { metadata: attributes.metadata(), fields, request_id, started: Instant::now(), refs: 1 });
+        Id::from_u64(id)
+    }
+
+    fn record(&self, span: &Id, values: &Record<'_>) {
+        let mut spans = SPANS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(data) = spans.get_mut(&span.into_u64()) {
+            values.record(&mut JsonVisitor(&mut data.fields));
+        }
+    }
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let metadata = event.metadata();
+        let mut fields = Map::new();
+        event.record(&mut JsonVisitor(&mut fields));
+
+        eprintln!("{}", to_line(&metadata.level().to_string(), metadata.target(), fields));
+    }
+
+    fn enter(&self, span: &Id) {
+        STACK.with(|stack| stack.borrow_mut().push(span.into_u64()));
+    }
+
+    fn exit(&self, span: &Id) {
+        STACK.with(|stack| {
+            let mut stack = stack.borrow_mut();
+
+            if let Some(position) = stack.iter().rposition(|entered| *entered == span.into_u64()) {
+                stack.remove(position);
+            }
+        });
+    }
+
+    fn clone_span(&self, span: &Id) -> Id {
+        let mut spans = SPANS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        if let Some(data) = spans.get_mut(&span.into_u64()) {
+            data.refs += 1;
+        }
+
+        span.clone()
+    }
+
+    fn try_close(&self, span: Id) -> bool {
+        let closed = {
+            let mut spans = SPANS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+            match spans.get_mut(&span.into_u64()) {
+                Some(data) if data.refs > 1 => {
+                    data.refs -= 1;
+                    return false;
+                },
+                Some(_) => spans.remove(&span.into_u64()),
+                None => return false,
+            }
+        };
+
+        if let Some(data) = closed {
+            if log_enabled(data.metadata.level(), data.metadata.target()) {
+                let mut fields = data.fields;
+                fields.insert("span".to_string(), json!(data.metadata.name()));
+                fields.insert("duration_us".to_string(), json!(data.started.elapsed().as_micros() as u64));
+
+                if let Some(request_id) = data.request_id {
+                    fields.insert("request_id".to_string(), json!(request_id));
+                }
+
+                eprintln!("{}", to_line(&data.metadata.level().to_string(), data.metadata.target(), fields));
+            }
+        }
+
+        true
+    }
+}
+
+/// Enters `span` every time the wrapped future is polled, so work done by
+/// an async handler is attributed to the request it belongs to.
+pub struct InSpan<F> {
+    inner: F,
+    span: Span
+}
+
+impl<F> InSpan<F> {
+    pub fn new(inner: F, span: Span) -> InSpan<F> {
+        InSpan { inner, span }
+    }
+}
+
+impl<F: Future + Unpin> Future for InSpan<F> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        let this = &mut *self;
+        let _entered = this.span.enter();
+
+        Pin::new(&mut this.inner).poll(cx)
+    }
+}
\ No newline at end of file
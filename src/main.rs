@@ -1,52 +1,119 @@
 #[macro_use]
 extern crate log;
-#[macro_use]
-extern crate diesel;
-extern crate diesel_migrations;
 
-use actix_web::{App, HttpServer, http};
-use actix_cors::Cors;
+use actix_rt::signal::unix::{signal, SignalKind};
+use actix_web::dev::Server;
+use actix_web::http::header::HeaderName;
+use actix_web::{web, App, HttpServer, http};
+use actix_cors::{Cors, CorsFactory};
 use dotenv::dotenv;
+use futures::future::select_all;
 use listenfd::ListenFd;
-use std::env;
-
-mod api_error;
-mod db;
-mod schema;
-mod staff;
-mod store;
-mod service;
+use rust_scheduler::config::CONFIG;
+use rust_scheduler::{api_error, audit, configure, logging, metrics, request_id, webhook};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 #[actix_rt::main]
 async fn main() -> std::io::Result<()> {
     dotenv().ok();
-    env_logger::init();
+    lazy_static::initialize(&CONFIG);
+    logging::init(&CONFIG.log.level);
 
     let mut listenfd = ListenFd::from_env();
     let mut server = HttpServer::new(|| {
         App::new()
-        .wrap(
-            Cors::new()
-                .allowed_origin("http://localhost:3000")
-                .allowed_methods(vec!["GET", "POST"])
-                .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
-                .allowed_header(http::header::CONTENT_TYPE)
-                .max_age(3600)
-                .finish())
-            .configure(staff::init_routes)
-            .configure(store::init_routes)
-            .configure(service::init_routes)
+        .wrap(metrics::RequestMetrics)
+        .wrap(request_id::RequestIdentifier)
+        .wrap(cors())
+            .app_data(api_error::json_config())
+            .app_data(api_error::path_config())
+            .app_data(api_error::query_config())
+            .configure(configure)
+            .default_service(web::route().to(api_error::route_not_found))
     });
 
+    server = server
+        .keep_alive(CONFIG.server.keep_alive)
+        .shutdown_timeout(CONFIG.server.shutdown_timeout)
+        .disable_signals();
+
+    if let Some(workers) = CONFIG.server.workers {
+        server = server.workers(workers);
+    }
+
     server = match listenfd.take_tcp_listener(0)? {
         Some(listener) => server.listen(listener)?,
-        None => {
-            let host = env::var("HOST").expect("Host not set");
-            let port = env::var("PORT").expect("Port not set");
-            server.bind(format!("{}:{}", host, port))?
-        }
+        None => server.bind(CONFIG.bind_address())?,
     };
 
-    info!("Starting server");
-    server.run().await
+    let dispatcher = webhook::dispatcher::start();
+
+    // SIGTERM stops accepting connections and waits up to shutdown_timeout
+    // for in-flight requests, so audit and notification writes made inside
+    // them are committed before the dispatcher is stopped
+    info!("Starting server on {}", CONFIG.bind_address());
+    let server = server.run();
+    let shutdown_started = Arc::new(Mutex::new(None));
+    actix_rt::spawn(stop_on_signal(server.clone(), shutdown_started.clone()));
+    server.await?;
+
+    // the dispatcher only gets what the requests left of shutdown_timeout
+    let shutdown_started = shutdown_started.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).unwrap_or_else(Instant::now);
+    let deadline = shutdown_started + Duration::from_secs(CONFIG.server.shutdown_timeout);
+    info!("Server stopped, waiting for webhook deliveries in flight");
+
+    if !dispatcher.stop(deadline.saturating_duration_since(Instant::now())) {
+        warn!("Webhook dispatcher still busy after {}s, exiting anyway", CONFIG.server.shutdown_timeout);
+    }
+
+    Ok(())
+}
+
+/// Stops `server` the way actix would on its own, gracefully on SIGTERM and
+/// at once on SIGINT or SIGQUIT, and records when shutdown started.
+async fn stop_on_signal(server: Server, shutdown_started: Arc<Mutex<Option<Instant>>>) {
+    let mut signals = Vec::new();
+
+    for &(kind, graceful) in &[(SignalKind::terminate(), true), (SignalKind::interrupt(), false), (SignalKind::quit(), false)] {
+        match signal(kind) {
+            Ok(stream) => signals.push((stream, graceful)),
+            Err(err) => error!("Could not listen for {:?}: {}", kind, err),
+        }
+    }
+
+    if signals.is_empty() {
+        return;
+    }
+
+    let (_, index, _) = select_all(signals.iter_mut().map(|(stream, _)| Box::pin(stream.recv()))).await;
+    *shutdown_started.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(Instant::now());
+
+    server.stop(signals[index].1).await;
+}
+
+fn cors() -> CorsFactory {
+    let cors = &CONFIG.cors;
+    let mut builder = Cors::new();
+
+    if !cors.allowed_origins.iter().any(|origin| origin == "*") {
+        for origin in &cors.allowed_origins {
+            builder = builder.allowed_origin(origin);
+        }
+    }
+
+    builder
+        .allowed_methods(cors.allowed_methods.iter().map(String::as_str))
+        .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
+        .allowed_header(http::header::CONTENT_TYPE)
+        .allowed_header(HeaderName::from_static(audit::STAFF_ID_HEADER))
+        .allowed_header(HeaderName::from_static(request_id::REQUEST_ID_HEADER))
+        .expose_headers(vec![
+            http::header::LINK,
+            HeaderName::from_static("deprecation"),
+            HeaderName::from_static("x-total-count"),
+            HeaderName::from_static(request_id::REQUEST_ID_HEADER)
+        ])
+        .max_age(cors.max_age)
+        .finish()
 }
\ No newline at end of file
@@ -0,0 +1,222 @@
+use crate::api_error::ApiError;
+use crate::db;
+use actix_web::dev::{Body, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::{get, web, Error, HttpResponse};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::task::{Context, Poll};
+use std::time::Instant;
+
+/// Upper bounds, in seconds, of the request latency histogram buckets.
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+/// Route label for requests that matched no route, so probing random
+/// paths cannot create unbounded label values.
+const UNMATCHED_ROUTE: &str = "unmatched";
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry::default());
+}
+
+#[derive(Default)]
+struct Registry {
+    /// (method, route, status) to request count
+    requests: BTreeMap<(String, String, u16), u64>,
+    /// (method, route) to latency histogram
+    latency: BTreeMap<(String, String), Histogram>,
+    /// source to appointments booked
+    booked: BTreeMap<&'static str, u64>,
+    /// source to appointments cancelled
+    cancelled: BTreeMap<&'static str, u64>
+}
+
+#[derive(Default)]
+struct Histogram {
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (bucket, bound) in self.buckets.iter_mut().zip(LATENCY_BUCKETS.iter()) {
+            if seconds <= *bound {
+                *bucket += 1;
+            }
+        }
+
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+fn record(method: String, route: String, status: u16, seconds: f64) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    *registry.requests.entry((method.clone(), route.clone(), status)).or_default() += 1;
+    registry.latency.entry((method, route)).or_default().observe(seconds);
+}
+
+/// Counts appointments booked from `source`: `appointment` for a one-off
+/// booking, `series` for occurrences of a recurring series. Call after the
+/// transaction commits.
+pub fn appointments_booked(source: &'static str, count: usize) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *registry.booked.entry(source).or_default() += count as u64;
+}
+
+/// Counts appointments cancelled from `source`, like `appointments_booked`.
+pub fn appointments_cancelled(source: &'static str, count: usize) {
+    let mut registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    *registry.cancelled.entry(source).or_default() += count as u64;
+}
+
+/// The route pattern a request matched, e.g. `/api/v1/stores/{store_id}`,
+/// rebuilt by putting the parameter names back in place of their values.
+pub fn route_pattern(res: &ServiceResponse<Body>) -> String {
+    let not_found = res.response()
+        .error()
+        .and_then(|err| err.as_error::<ApiError>())
+        .is_some_and(|err| err.code == "route.not_found");
+
+    if not_found {
+        return UNMATCHED_ROUTE.to_string();
+    }
+
+    let mut params = res.request().match_info().iter().peekable();
+    let mut pattern = String::new();
+
+    for segment in res.request().path().split('/').skip(1) {
+        pattern.push('/');
+
+        match params.peek() {
+            Some((name, value)) if *value == segment => {
+                let _ = write!(pattern, "{{{}}}", name);
+                params.next();
+            },
+            _ => pattern.push_str(segment),
+        }
+    }
+
+    pattern
+}
+
+/// Middleware that counts requests and records their latency per route.
+pub struct RequestMetrics;
+
+impl<S> Transform<S> for RequestMetrics
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<Body>, Error = Error> + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestMetricsMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestMetricsMiddleware { service })
+    }
+}
+
+pub struct RequestMetricsMiddleware<S> {
+    service: S,
+}
+
+impl<S> Service for RequestMetricsMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<Body>, Error = Error> + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let started = Instant::now();
+        let method = req.method().to_string();
+
+        self.service
+            .call(req)
+            .map(move |res| {
+                let res = res?;
+                record(method, route_pattern(&res), res.status().as_u16(), started.elapsed().as_secs_f64());
+                Ok(res)
+            })
+            .boxed_local()
+    }
+}
+
+/// Label values are escaped as the Prometheus text format requires.
+fn label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn render() -> String {
+    let registry = REGISTRY.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# HELP http_requests_total Requests handled, by method, route and status code.");
+    let _ = writeln!(out, "# TYPE http_requests_total counter");
+
+    for ((method, route, status), count) in &registry.requests {
+        let _ = writeln!(out, "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}", method, label(route), status, count);
+    }
+
+    let _ = writeln!(out, "# HELP http_request_duration_seconds Request latency, by method and route.");
+    let _ = writeln!(out, "# TYPE http_request_duration_seconds histogram");
+
+    for ((method, route), histogram) in &registry.latency {
+        let labels = format!("method=\"{}\",route=\"{}\"", method, label(route));
+
+        for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets.iter()) {
+            let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}", labels, bound, count);
+        }
+
+        let _ = writeln!(out, "http_request_duration_seconds_bucket{{{},le=\"+Inf\"}} {}", labels, histogram.count);
+        let _ = writeln!(out, "http_request_duration_seconds_sum{{{}}} {}", labels, histogram.sum);
+        let _ = writeln!(out, "http_request_duration_seconds_count{{{}}} {}", labels, histogram.count);
+    }
+
+    let _ = writeln!(out, "# HELP appointments_booked_total Appointments booked, by source.");
+    let _ = writeln!(out, "# TYPE appointments_booked_total counter");
+
+    for (source, count) in &registry.booked {
+        let _ = writeln!(out, "appointments_booked_total{{source=\"{}\"}} {}", source, count);
+    }
+
+    let _ = writeln!(out, "# HELP appointments_cancelled_total Appointments cancelled, by source.");
+    let _ = writeln!(out, "# TYPE appointments_cancelled_total counter");
+
+    for (source, count) in &registry.cancelled {
+        let _ = writeln!(out, "appointments_cancelled_total{{source=\"{}\"}} {}", source, count);
+    }
+
+    let pool = db::pool_state();
+
+    let _ = writeln!(out, "# HELP db_pool_connections Open database connections, by state.");
+    let _ = writeln!(out, "# TYPE db_pool_connections gauge");
+    let _ = writeln!(out, "db_pool_connections{{state=\"idle\"}} {}", pool.idle_connections);
+    let _ = writeln!(out, "db_pool_connections{{state=\"in_use\"}} {}", pool.connections - pool.idle_connections);
+    let _ = writeln!(out, "# HELP db_pool_max_connections Configured database pool size.");
+    let _ = writeln!(out, "# TYPE db_pool_max_connections gauge");
+    let _ = writeln!(out, "db_pool_max_connections {}", db::pool_max_size());
+
+    out
+}
+
+#[get("/metrics")]
+async fn metrics() -> HttpResponse {
+    HttpResponse::Ok().content_type("text/plain; version=0.0.4").body(render())
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(metrics);
+}
\ No newline at end of file
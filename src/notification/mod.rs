@@ -0,0 +1,7 @@
+pub mod model;
+pub mod routes;
+pub mod v1;
+pub mod sms;
+
+pub use model::*;
+pub use routes::init_routes;
\ No newline at end of file
@@ -0,0 +1,231 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::config::CONFIG;
+use crate::{
+    schema::notification_preference::{self, dsl::*},
+    schema::sms_message::{self, dsl::*}
+};
+use crate::customer::Customer;
+use crate::notification::sms::{self, SmsProvider};
+use chrono::{NaiveDateTime, Utc};
+use crate::diesel::prelude::*;
+use crate::validation::{FieldErrors, Validate};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use tracing::debug_span;
+
+pub const SMS_PENDING: &str = "pending";
+pub const SMS_SENT: &str = "sent";
+pub const SMS_DELIVERED: &str = "delivered";
+pub const SMS_UNDELIVERED: &str = "undelivered";
+pub const SMS_FAILED: &str = "failed";
+
+lazy_static! {
+    static ref SMS_PROVIDER: Box<dyn SmsProvider> = sms::provider_from_config(&CONFIG.sms);
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[table_name = "notification_preference"]
+pub struct NotificationPreferenceCreate {
+    pub customer_id: i32,
+    pub email_enabled: i32,
+    pub sms_enabled: i32
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, ToSchema)]
+#[table_name = "notification_preference"]
+pub struct NotificationPreferenceUpdate {
+    pub email_enabled: i32,
+    pub sms_enabled: i32
+}
+
+#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, Debug, ToSchema)]
+#[primary_key(notification_preference_id)]
+#[table_name = "notification_preference"]
+pub struct NotificationPreference {
+    pub notification_preference_id: i32,
+    pub customer_id: i32,
+    pub email_enabled: i32,
+    pub sms_enabled: i32
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SendSms {
+    pub customer_id: i32,
+    pub body: String
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SmsBody {
+    pub body: String
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct SmsStatusUpdate {
+    pub provider_message_id: String,
+    pub status: String,
+    pub error: Option<String>
+}
+
+#[derive(Insertable)]
+#[table_name = "sms_message"]
+pub struct SmsMessageCreate {
+    pub customer_id: i32,
+    pub phone: String,
+    pub body: String,
+    pub status: String
+}
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, ToSchema)]
+#[primary_key(sms_message_id)]
+#[table_name = "sms_message"]
+pub struct SmsMessage {
+    pub sms_message_id: i32,
+    pub customer_id: i32,
+    pub phone: String,
+    pub body: String,
+    pub status: String,
+    pub provider_message_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+impl Validate for NotificationPreferenceUpdate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.flag("email_enabled", self.email_enabled);
+        errors.flag("sms_enabled", self.sms_enabled);
+    }
+}
+
+impl Validate for SendSms {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("body", &self.body);
+    }
+}
+
+impl Validate for SmsBody {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("body", &self.body);
+    }
+}
+
+impl NotificationPreference {
+    pub fn find(id: i32) -> QueryResult<Self> {
+        let span = debug_span!("NotificationPreference::find");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        notification_preference
+            .filter(notification_preference::customer_id.eq(id))
+            .first::<Self>(&conn)
+    }
+
+    pub fn update(id: i32, preference_update: NotificationPreferenceUpdate) -> Result<Self, ApiError> {
+        let span = debug_span!("NotificationPreference::update");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let preference_updated = diesel::update(notification_preference::table)
+            .filter(notification_preference::customer_id.eq(id))
+            .set(preference_update)
+            .get_result(&conn)?;
+
+        Ok(preference_updated)
+    }
+}
+
+impl SmsMessage {
+    pub fn find_by_customer(id: i32) -> QueryResult<Vec<Self>> {
+        let span = debug_span!("SmsMessage::find_by_customer");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        sms_message
+            .filter(sms_message::customer_id.eq(id))
+            .order(sms_message::created_at.desc())
+            .load::<Self>(&conn)
+    }
+
+    pub fn send(sms_send: SendSms) -> Result<Self, ApiError> {
+        let span = debug_span!("SmsMessage::send");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let recipient = Customer::find(sms_send.customer_id)?;
+        let preference = NotificationPreference::find(sms_send.customer_id)?;
+
+        if preference.sms_enabled != 1 {
+            return Err(ApiError::coded(409, "customer.sms_opted_out", "Customer has not opted in to SMS".to_string()));
+        }
+
+        let recipient_phone = match recipient.phone {
+            Some(number) => number,
+            None => return Err(ApiError::coded(400, "customer.phone_missing", "Customer has no phone number".to_string())),
+        };
+
+        let message_create = SmsMessageCreate {
+            customer_id: recipient.customer_id,
+            phone: recipient_phone,
+            body: sms_send.body,
+            status: SMS_PENDING.to_string()
+        };
+
+        let message_created: Self = diesel::insert_into(sms_message::table)
+            .values(message_create)
+            .get_result(&conn)?;
+
+        // record the provider outcome against the stored message
+        let message_updated = match SMS_PROVIDER.send(&message_created.phone, &message_created.body) {
+            Ok(receipt) => diesel::update(sms_message::table)
+                .filter(sms_message::sms_message_id.eq(message_created.sms_message_id))
+                .set((
+                    sms_message::status.eq(SMS_SENT),
+                    sms_message::provider_message_id.eq(receipt.provider_message_id),
+                    sms_message::updated_at.eq(Utc::now().naive_utc())
+                ))
+                .get_result(&conn)?,
+            Err(err) => {
+                warn!("SMS {} failed: {}", message_created.sms_message_id, err);
+
+                diesel::update(sms_message::table)
+                    .filter(sms_message::sms_message_id.eq(message_created.sms_message_id))
+                    .set((
+                        sms_message::status.eq(SMS_FAILED),
+                        sms_message::error.eq(err.message),
+                        sms_message::updated_at.eq(Utc::now().naive_utc())
+                    ))
+                    .get_result(&conn)?
+            }
+        };
+
+        Ok(message_updated)
+    }
+
+    pub fn update_status(status_update: SmsStatusUpdate) -> Result<Self, ApiError> {
+        let span = debug_span!("SmsMessage::update_status");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let known_statuses = [SMS_SENT, SMS_DELIVERED, SMS_UNDELIVERED, SMS_FAILED];
+        if !known_statuses.contains(&status_update.status.as_str()) {
+            return Err(ApiError::coded(400, "sms.unknown_status", format!("Unknown SMS status: {}", status_update.status)));
+        }
+
+        let message_updated = diesel::update(sms_message::table)
+            .filter(sms_message::provider_message_id.eq(status_update.provider_message_id))
+            .set((
+                sms_message::status.eq(status_update.status),
+                sms_message::error.eq(status_update.error),
+                sms_message::updated_at.eq(Utc::now().naive_utc())
+            ))
+            .get_result(&conn)?;
+
+        Ok(message_updated)
+    }
+}
\ No newline at end of file
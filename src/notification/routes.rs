@@ -0,0 +1,98 @@
+use crate::api_error::ApiError;
+use crate::customer::CustomerId;
+use crate::notification::{NotificationPreference, NotificationPreferenceUpdate, SmsMessage, SendSms, SmsStatusUpdate};
+use crate::notification::sms::SmsCallback;
+use crate::request_id;
+use crate::validation::Valid;
+use actix_web::{get, post, put, web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/notification_preference/{customer_id}",
+    tag = "notification",
+    operation_id = "legacy_notification_find_preference",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Notification preferences", body = NotificationPreference)
+    )
+)]
+#[get("/notification_preference/{customer_id}")]
+async fn find_preference(id: web::Path<CustomerId>) -> Result<HttpResponse, ApiError> {
+    let preference = NotificationPreference::find(id.customer_id)?;
+    Ok(HttpResponse::Ok().json(preference))
+}
+
+#[utoipa::path(
+    put,
+    path = "/notification_preference/{customer_id}",
+    tag = "notification",
+    operation_id = "legacy_notification_update_preference",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    request_body = NotificationPreferenceUpdate,
+    responses(
+        (status = 200, description = "Updated notification preferences", body = NotificationPreference)
+    )
+)]
+#[put("/notification_preference/{customer_id}")]
+async fn update_preference(id: web::Path<CustomerId>, preference: Valid<NotificationPreferenceUpdate>) -> Result<HttpResponse, ApiError> {
+    let preference = NotificationPreference::update(id.customer_id, preference.into_inner())?;
+    Ok(HttpResponse::Ok().json(preference))
+}
+
+#[utoipa::path(
+    get,
+    path = "/sms/{customer_id}",
+    tag = "notification",
+    operation_id = "legacy_notification_find_sms",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "SMS messages sent to the customer", body = [SmsMessage])
+    )
+)]
+#[get("/sms/{customer_id}")]
+async fn find_sms(id: web::Path<CustomerId>) -> Result<HttpResponse, ApiError> {
+    let messages = SmsMessage::find_by_customer(id.customer_id)?;
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+#[utoipa::path(
+    post,
+    path = "/sms",
+    tag = "notification",
+    operation_id = "legacy_notification_send_sms",
+    request_body = SendSms,
+    responses(
+        (status = 200, description = "Sent (or failed) SMS message", body = SmsMessage)
+    )
+)]
+#[post("/sms")]
+async fn send_sms(message: Valid<SendSms>) -> Result<HttpResponse, ApiError> {
+    let message = message.into_inner();
+    let message = request_id::block(move || SmsMessage::send(message)).await?;
+    Ok(HttpResponse::Ok().json(message))
+}
+
+#[utoipa::path(
+    put,
+    path = "/sms_status",
+    tag = "notification",
+    operation_id = "legacy_notification_update_sms_status",
+    request_body = SmsStatusUpdate,
+    responses(
+        (status = 200, description = "Updated SMS message", body = SmsMessage)
+    ),
+    security(("sms_secret" = []))
+)]
+#[put("/sms_status")]
+async fn update_sms_status(_callback: SmsCallback, status: web::Json<SmsStatusUpdate>) -> Result<HttpResponse, ApiError> {
+    let message = SmsMessage::update_status(status.into_inner())?;
+    Ok(HttpResponse::Ok().json(message))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_preference);
+    cfg.service(update_preference);
+    cfg.service(find_sms);
+    cfg.service(send_sms);
+    cfg.service(update_sms_status);
+}
\ No newline at end of file
@@ -0,0 +1,139 @@
+use crate::api_error::ApiError;
+use crate::config::{SmsConfig, CONFIG};
+use crate::logging;
+use actix_web::{dev, FromRequest, HttpRequest};
+use chrono::Utc;
+use futures::future::{ready, Ready};
+use serde_json::json;
+use std::fs::OpenOptions;
+use std::io::Write;
+use uuid::Uuid;
+
+pub const SMS_SECRET_HEADER: &str = "x-sms-secret";
+
+pub struct SmsReceipt {
+    pub provider_message_id: Option<String>
+}
+
+/// Something that can hand a text message off for delivery.
+///
+/// Providers only report whether the message was accepted; later delivery
+/// updates arrive through `PUT /sms_status`, authorized by `SmsCallback`.
+pub trait SmsProvider: Send + Sync {
+    fn send(&self, to: &str, body: &str) -> Result<SmsReceipt, ApiError>;
+}
+
+/// Development provider that logs messages and optionally appends them to a file.
+pub struct LogSmsProvider {
+    pub file: Option<String>
+}
+
+impl SmsProvider for LogSmsProvider {
+    fn send(&self, to: &str, body: &str) -> Result<SmsReceipt, ApiError> {
+        let message_id = Uuid::new_v4().to_string();
+        info!("SMS {} to {}: {}", message_id, to, body);
+
+        if let Some(path) = &self.file {
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|err| ApiError::new(500, format!("SMS log error: {}", err)))?;
+
+            writeln!(file, "{}\t{}\t{}\t{}", Utc::now().to_rfc3339(), message_id, to, body)
+                .map_err(|err| ApiError::new(500, format!("SMS log error: {}", err)))?;
+        }
+
+        Ok(SmsReceipt { provider_message_id: Some(message_id) })
+    }
+}
+
+/// Posts `{ "to", "from", "body" }` as JSON to a gateway and reads the
+/// message id from the `id` field of the response.
+pub struct HttpSmsProvider {
+    pub url: String,
+    pub token: Option<String>,
+    pub from: Option<String>
+}
+
+impl SmsProvider for HttpSmsProvider {
+    fn send(&self, to: &str, body: &str) -> Result<SmsReceipt, ApiError> {
+        let mut request = ureq::post(&self.url);
+        request.timeout_connect(5_000).timeout_read(10_000);
+
+        if let Some(token) = &self.token {
+            request.set("Authorization", &format!("Bearer {}", token));
+        }
+
+        if let Some(request_id) = logging::current_request_id() {
+            request.set("X-Request-Id", &request_id);
+        }
+
+        let response = request.send_json(json!({ "to": to, "from": self.from, "body": body }));
+
+        if let Some(err) = response.synthetic_error() {
+            return Err(ApiError::coded(502, "sms.provider_failed", format!("SMS provider error: {}", err)));
+        }
+
+        if !response.ok() {
+            return Err(ApiError::coded(502, "sms.provider_failed", format!("SMS provider returned {}", response.status())));
+        }
+
+        let provider_message_id = response.into_json()
+            .ok()
+            .and_then(|res| res["id"].as_str().map(String::from));
+
+        Ok(SmsReceipt { provider_message_id })
+    }
+}
+
+/// Builds the provider named by `sms.provider`. The settings were checked
+/// when the config was loaded, so an `http` provider always has a URL.
+pub fn provider_from_config(sms: &SmsConfig) -> Box<dyn SmsProvider> {
+    match (sms.provider.as_str(), &sms.http_url) {
+        ("http", Some(url)) => Box::new(HttpSmsProvider {
+            url: url.clone(),
+            token: sms.http_token.clone(),
+            from: sms.from.clone()
+        }),
+        _ => Box::new(LogSmsProvider {
+            file: sms.log_file.clone()
+        })
+    }
+}
+
+/// Proof that a status callback came from the gateway: the `X-Sms-Secret`
+/// header matches `sms.status_secret`. With no secret configured every
+/// callback is refused.
+pub struct SmsCallback;
+
+impl FromRequest for SmsCallback {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        let sent = req.headers().get(SMS_SECRET_HEADER).map(|header| header.as_bytes());
+
+        let authorized = match (&CONFIG.sms.status_secret, sent) {
+            (Some(secret), Some(sent)) => constant_time_eq(secret.as_bytes(), sent),
+            _ => false,
+        };
+
+        if !authorized {
+            return ready(Err(ApiError::coded(401, "sms.callback_unauthorized", "Missing or invalid X-Sms-Secret header".to_string())));
+        }
+
+        ready(Ok(SmsCallback))
+    }
+}
+
+/// Compares without stopping at the first differing byte, so the time taken
+/// does not reveal how much of the secret a caller guessed.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+
+    a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
\ No newline at end of file
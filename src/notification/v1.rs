@@ -0,0 +1,103 @@
+use crate::api_error::ApiError;
+use crate::customer::CustomerId;
+use crate::notification::{NotificationPreference, NotificationPreferenceUpdate, SmsMessage, SendSms, SmsBody, SmsStatusUpdate};
+use crate::notification::sms::SmsCallback;
+use crate::request_id;
+use crate::validation::Valid;
+use actix_web::{get, post, put, web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers/{customer_id}/notification_preferences",
+    tag = "notification",
+    operation_id = "notification_find_preference",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "Notification preferences", body = NotificationPreference)
+    )
+)]
+#[get("/customers/{customer_id}/notification_preferences")]
+async fn find_preference(id: web::Path<CustomerId>) -> Result<HttpResponse, ApiError> {
+    let preference = NotificationPreference::find(id.customer_id)?;
+    Ok(HttpResponse::Ok().json(preference))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/customers/{customer_id}/notification_preferences",
+    tag = "notification",
+    operation_id = "notification_update_preference",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    request_body = NotificationPreferenceUpdate,
+    responses(
+        (status = 200, description = "Updated notification preferences", body = NotificationPreference)
+    )
+)]
+#[put("/customers/{customer_id}/notification_preferences")]
+async fn update_preference(id: web::Path<CustomerId>, preference: Valid<NotificationPreferenceUpdate>) -> Result<HttpResponse, ApiError> {
+    let preference = NotificationPreference::update(id.customer_id, preference.into_inner())?;
+    Ok(HttpResponse::Ok().json(preference))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/customers/{customer_id}/sms_messages",
+    tag = "notification",
+    operation_id = "notification_find_sms",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    responses(
+        (status = 200, description = "SMS messages sent to the customer", body = [SmsMessage])
+    )
+)]
+#[get("/customers/{customer_id}/sms_messages")]
+async fn find_sms(id: web::Path<CustomerId>) -> Result<HttpResponse, ApiError> {
+    let messages = SmsMessage::find_by_customer(id.customer_id)?;
+    Ok(HttpResponse::Ok().json(messages))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/customers/{customer_id}/sms_messages",
+    tag = "notification",
+    operation_id = "notification_send_sms",
+    params(("customer_id" = i32, Path, description = "Customer id")),
+    request_body = SmsBody,
+    responses(
+        (status = 201, description = "Sent (or failed) SMS message", body = SmsMessage)
+    )
+)]
+#[post("/customers/{customer_id}/sms_messages")]
+async fn send_sms(id: web::Path<CustomerId>, message: Valid<SmsBody>) -> Result<HttpResponse, ApiError> {
+    let sms_send = SendSms {
+        customer_id: id.customer_id,
+        body: message.into_inner().body
+    };
+    let message = request_id::block(move || SmsMessage::send(sms_send)).await?;
+
+    Ok(HttpResponse::Created().json(message))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/sms_messages/status",
+    tag = "notification",
+    operation_id = "notification_update_sms_status",
+    request_body = SmsStatusUpdate,
+    responses(
+        (status = 200, description = "Updated SMS message", body = SmsMessage)
+    ),
+    security(("sms_secret" = []))
+)]
+#[put("/sms_messages/status")]
+async fn update_sms_status(_callback: SmsCallback, status: web::Json<SmsStatusUpdate>) -> Result<HttpResponse, ApiError> {
+    let message = SmsMessage::update_status(status.into_inner())?;
+    Ok(HttpResponse::Ok().json(message))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_preference);
+    cfg.service(update_preference);
+    cfg.service(find_sms);
+    cfg.service(send_sms);
+    cfg.service(update_sms_status);
+}
\ No newline at end of file
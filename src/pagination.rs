@@ -0,0 +1,159 @@
+use crate::api_error::ApiError;
+use actix_web::http::header;
+use actix_web::{dev, web, FromRequest, HttpRequest, HttpResponse};
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+use utoipa::IntoParams;
+
+const DEFAULT_LIMIT: i64 = 50;
+const MAX_LIMIT: i64 = 200;
+
+/// Routes under this prefix are paged by default; the legacy routes outside
+/// it return every row until the client asks for a page.
+const PAGED_BY_DEFAULT: &str = "/api/";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PageParams {
+    /// Page size, 1 to 200 (default 50, or every row on a legacy route
+    /// that sends neither limit nor offset)
+    limit: Option<i64>,
+    /// Rows to skip (default 0)
+    offset: Option<i64>,
+    /// `field`, `field:asc` or `field:desc`
+    sort: Option<String>
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sort {
+    pub field: String,
+    pub descending: bool
+}
+
+/// `limit`, `offset` and `sort=field:asc|desc` from the query string of a list endpoint.
+///
+/// Other query parameters are left for the endpoint's own filter struct and
+/// are carried over into the `Link` header.
+///
+/// A legacy route requested without `limit` or `offset` gets every row, as
+/// it did before pagination, and no `Link` header.
+pub struct Pagination {
+    pub limit: i64,
+    pub offset: i64,
+    paged: bool,
+    sort: Option<String>,
+    path: String,
+    passthrough: Vec<String>
+}
+
+impl Pagination {
+    /// Parses `sort`, falling back to `allowed[0]` ascending.
+    pub fn sort(&self, allowed: &[&str]) -> Result<Sort, ApiError> {
+        let value = match &self.sort {
+            Some(value) => value,
+            None => return Ok(Sort { field: allowed[0].to_string(), descending: false }),
+        };
+
+        let mut parts = value.splitn(2, ':');
+        let field = parts.next().unwrap_or_default();
+        let descending = match parts.next() {
+            None | Some("asc") => false,
+            Some("desc") => true,
+            Some(direction) => return Err(ApiError::coded(400, "pagination.invalid_sort", format!("Invalid sort direction: {}", direction))),
+        };
+
+        if !allowed.contains(&field) {
+            return Err(ApiError::coded(400, "pagination.invalid_sort", format!("Cannot sort by {}, expected one of: {}", field, allowed.join(", "))));
+        }
+
+        Ok(Sort { field: field.to_string(), descending })
+    }
+
+    fn link(&self, offset: i64, rel: &str) -> String {
+        let mut params = self.passthrough.clone();
+        params.push(format!("limit={}", self.limit));
+        params.push(format!("offset={}", offset));
+
+        format!("<{}?{}>; rel=\"{}\"", self.path, params.join("&"), rel)
+    }
+
+    /// Builds the list response with `X-Total-Count` and `Link` headers.
+    pub fn respond<T: Serialize>(&self, total: i64, items: T) -> HttpResponse {
+        if !self.paged {
+            return HttpResponse::Ok()
+                .header("X-Total-Count", total.to_string())
+                .json(items);
+        }
+
+        let mut links = vec![self.link(0, "first")];
+
+        if self.offset > 0 {
+            links.push(self.link((self.offset - self.limit).max(0), "prev"));
+        }
+
+        if self.offset + self.limit < total {
+            links.push(self.link(self.offset + self.limit, "next"));
+        }
+
+        let last_offset = match total > 0 {
+            true => (total - 1) / self.limit * self.limit,
+            false => 0,
+        };
+        links.push(self.link(last_offset, "last"));
+
+        HttpResponse::Ok()
+            .header("X-Total-Count", total.to_string())
+            .header(header::LINK, links.join(", "))
+            .json(items)
+    }
+}
+
+impl FromRequest for Pagination {
+    type Error = ApiError;
+    type Future = Ready<Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut dev::Payload) -> Self::Future {
+        let params = match web::Query::<PageParams>::from_query(req.query_string()) {
+            Ok(params) => params.into_inner(),
+            Err(err) => return ready(Err(ApiError::coded(400, "pagination.invalid", format!("Invalid pagination: {}", err)))),
+        };
+
+        if !req.path().starts_with(PAGED_BY_DEFAULT) && params.limit.is_none() && params.offset.is_none() {
+            return ready(Ok(Pagination {
+                limit: i64::MAX,
+                offset: 0,
+                paged: false,
+                sort: params.sort,
+                path: req.path().to_string(),
+                passthrough: Vec::new()
+            }));
+        }
+
+        let limit = params.limit.unwrap_or(DEFAULT_LIMIT);
+        if !(1..=MAX_LIMIT).contains(&limit) {
+            return ready(Err(ApiError::coded(400, "pagination.invalid_limit", format!("limit must be between 1 and {}", MAX_LIMIT))));
+        }
+
+        let offset = params.offset.unwrap_or(0);
+        if offset < 0 {
+            return ready(Err(ApiError::coded(400, "pagination.invalid_offset", "offset cannot be negative".to_string())));
+        }
+
+        let passthrough = req.query_string()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .filter(|pair| !pair.starts_with("limit=") && !pair.starts_with("offset="))
+            .map(String::from)
+            .collect();
+
+        ready(Ok(Pagination {
+            limit,
+            offset,
+            paged: true,
+            sort: params.sort,
+            path: req.path().to_string(),
+            passthrough
+        }))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,177 @@
+use crate::api_error::ApiError;
+use crate::logging::{self, InSpan};
+use crate::metrics;
+use actix_web::dev::{Body, Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::BlockingError;
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::{web, Error};
+use futures::future::{ok, FutureExt, LocalBoxFuture, Ready};
+use std::task::{Context, Poll};
+use std::time::Instant;
+use tracing::Span;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+fn incoming_id(req: &ServiceRequest) -> Option<String> {
+    req.headers()
+        .get(REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(str::trim)
+        .filter(|value| !value.is_empty() && value.len() <= 128)
+        .map(String::from)
+}
+
+/// Runs blocking `work`, such as a call out to a provider, on the thread pool
+/// so it does not stall a worker. The work runs in a span carrying the
+/// calling request's id, so its log lines and outgoing calls keep it.
+pub async fn block<T, F>(work: F) -> Result<T, ApiError>
+where
+    F: FnOnce() -> Result<T, ApiError> + Send + 'static,
+    T: Send + 'static,
+{
+    let request_id = logging::current_request_id();
+
+    web::block(move || {
+        let span = match &request_id {
+            Some(request_id) => tracing::trace_span!(target: "request", "blocking", request_id = request_id.as_str()),
+            None => Span::none(),
+        };
+        let _entered = span.enter();
+
+        work()
+    })
+    .await
+    .map_err(|err| match err {
+        BlockingError::Error(err) => err,
+        BlockingError::Canceled => ApiError::new(500, "Blocking task was cancelled".to_string()),
+    })
+}
+
+/// Middleware that reuses an incoming `X-Request-Id` (or generates one),
+/// echoes it in the response header and stamps it onto `ApiError` bodies.
+///
+/// The request is handled inside a `request` span carrying the id, so log
+/// lines and model spans are tagged with it, and one access log line is
+/// written when the response is ready.
+pub struct RequestIdentifier;
+
+impl<S> Transform<S> for RequestIdentifier
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<Body>, Error = Error> + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type InitError = ();
+    type Transform = RequestIdentifierMiddleware<S>;
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ok(RequestIdentifierMiddleware { service })
+    }
+}
+
+pub struct RequestIdentifierMiddleware<S> {
+    service: S,
+}
+
+impl<S> Service for RequestIdentifierMiddleware<S>
+where
+    S: Service<Request = ServiceRequest, Response = ServiceResponse<Body>, Error = Error> + 'static,
+{
+    type Request = ServiceRequest;
+    type Response = ServiceResponse<Body>;
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    fn call(&mut self, req: ServiceRequest) -> Self::Future {
+        let request_id = incoming_id(&req).unwrap_or_else(|| Uuid::new_v4().to_string());
+        let span = tracing::trace_span!(target: "request", "request", request_id = request_id.as_str());
+
+        let started = Instant::now();
+        let method = req.method().to_string();
+        let path = req.path().to_string();
+        let remote_addr = req.connection_info().remote().map(String::from);
+        let user_agent = req.headers()
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from);
+
+        let response = {
+            let _entered = span.enter();
+            self.service.call(req)
+        };
+
+        let response = response.map(move |res| {
+            let mut res = stamp_error(res?, &request_id);
+
+            tracing::info!(
+                target: "access",
+                request_id = request_id.as_str(),
+                method = method.as_str(),
+                path = path.as_str(),
+                route = metrics::route_pattern(&res).as_str(),
+                status = res.status().as_u16(),
+                duration_us = started.elapsed().as_micros() as u64,
+                remote_addr = remote_addr.as_deref().unwrap_or_default(),
+                user_agent = user_agent.as_deref().unwrap_or_default()
+            );
+
+            if let Ok(value) = HeaderValue::from_str(&request_id) {
+                res.headers_mut().insert(HeaderName::from_static(REQUEST_ID_HEADER), value);
+            }
+
+            Ok(res)
+        });
+
+        InSpan::new(response.boxed_local(), span).boxed_local()
+    }
+}
+
+fn stamp_error(res: ServiceResponse<Body>, request_id: &str) -> ServiceResponse<Body> {
+    let api_error = match res.response().error().and_then(|err| err.as_error::<ApiError>()) {
+        Some(api_error) => api_error,
+        None => return res,
+    };
+
+    if api_error.status().is_server_error() {
+        error!("{} {} failed: {}", res.request().method(), res.request().path(), api_error.message);
+    }
+
+    let scope = resource_scope(res.request().path());
+    let response = api_error.to_response(scope.as_deref(), Some(request_id));
+    res.into_response(response)
+}
+
+/// The resource a path addresses, used to scope error codes. Versioned
+/// paths use plural collections, so `/api/v1/stores/3` scopes to `store`
+/// just like the legacy `/store/3`.
+fn resource_scope(path: &str) -> Option<String> {
+    let (path, versioned) = match path.strip_prefix("/api/v1") {
+        Some(path) => (path, true),
+        None => (path, false),
+    };
+    let segment = path.trim_start_matches('/').split('/').next()?;
+
+    if !versioned {
+        return Some(segment.to_string());
+    }
+
+    // `series` is the same in the singular
+    if segment.ends_with("series") {
+        return Some(segment.to_string());
+    }
+
+    let singular = match (segment.strip_suffix("ies"), segment.strip_suffix('s')) {
+        (Some(stem), _) => format!("{}y", stem),
+        (None, Some(stem)) => stem.to_string(),
+        (None, None) => segment.to_string(),
+    };
+
+    Some(singular)
+}
\ No newline at end of file
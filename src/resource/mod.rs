@@ -0,0 +1,6 @@
+pub mod model;
+pub mod repository;
+pub mod v1;
+
+pub use model::*;
+pub use repository::*;
\ No newline at end of file
@@ -0,0 +1,113 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::schema::resource;
+use crate::diesel::prelude::*;
+use crate::validation::{FieldErrors, Validate};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::repository::{PgResourceRepository, ResourceRepository};
+
+#[derive(Deserialize)]
+pub struct StoreResourcePath {
+    pub store_id: i32,
+    pub resource_id: i32
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ResourceQuery {
+    /// Only resources of this type, e.g. `treatment_room`
+    pub resource_type: Option<String>
+}
+
+/// A room, chair or piece of equipment at one store. A booking of a service
+/// that needs its type holds one free resource for each segment.
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[primary_key(resource_id)]
+#[table_name = "resource"]
+pub struct Resource {
+    pub resource_id: i32,
+    pub store_id: i32,
+    pub name: String,
+    /// Matched against the types services need, e.g. `treatment_room`
+    pub resource_type: String,
+    pub is_active: i32,
+    pub deleted_at: Option<NaiveDateTime>
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GenerateResource {
+    pub name: String,
+    pub resource_type: String,
+    pub is_active: i32
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[table_name = "resource"]
+pub struct ResourceCreate {
+    pub store_id: i32,
+    pub name: String,
+    pub resource_type: String,
+    pub is_active: i32
+}
+
+/// A resource held from `from` to `until`.
+#[derive(Clone)]
+pub struct ResourceHold {
+    pub resource_id: i32,
+    pub from: NaiveDateTime,
+    pub until: NaiveDateTime
+}
+
+/// The active resources of one store and the time they are already held.
+#[derive(Clone, Default)]
+pub struct ResourcePool {
+    pub resources: Vec<Resource>,
+    pub held: Vec<ResourceHold>
+}
+
+impl Validate for GenerateResource {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.resource_type("resource_type", &self.resource_type);
+        errors.flag("is_active", self.is_active);
+    }
+}
+
+impl ResourcePool {
+    /// Holds the free resource of `resource_type` with the lowest id from
+    /// `from` to `until`, or `None` when every one is taken.
+    pub fn take(&mut self, resource_type: &str, from: NaiveDateTime, until: NaiveDateTime) -> Option<i32> {
+        let held = &self.held;
+        let free = self.resources.iter()
+            .filter(|current| current.resource_type == resource_type)
+            .find(|current| !held.iter().any(|hold| hold.resource_id == current.resource_id && hold.from < until && hold.until > from))?
+            .resource_id;
+
+        self.held.push(ResourceHold { resource_id: free, from, until });
+
+        Some(free)
+    }
+}
+
+/// Shortcuts that run `ResourceRepository` on a connection from the server pool.
+impl Resource {
+    pub fn find_all(store_id: i32, query: ResourceQuery) -> QueryResult<Vec<Resource>> {
+        PgResourceRepository::new(&db::establish_connection()).find_all(store_id, query)
+    }
+
+    pub fn create(store_id: i32, resource: GenerateResource, actor: &Actor) -> Result<Resource, ApiError> {
+        PgResourceRepository::new(&db::establish_connection()).create(store_id, resource, actor)
+    }
+
+    pub fn update(store_id: i32, id: i32, resource: GenerateResource, actor: &Actor) -> Result<Resource, ApiError> {
+        PgResourceRepository::new(&db::establish_connection()).update(store_id, id, resource, actor)
+    }
+
+    pub fn delete(store_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgResourceRepository::new(&db::establish_connection()).delete(store_id, id, actor)
+    }
+}
\ No newline at end of file
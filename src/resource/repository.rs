@@ -0,0 +1,139 @@
+use crate::api_error::ApiError;
+use crate::audit::{self, Actor};
+use crate::{schema::resource, schema::store};
+use crate::diesel::prelude::*;
+use chrono::Utc;
+use diesel::pg::PgConnection;
+use tracing::debug_span;
+
+use super::model::*;
+
+/// Rooms, chairs and equipment at each store.
+///
+/// Every call runs on the connection the repository was built with, so
+/// callers choose the database and can wrap several calls in one
+/// transaction.
+pub trait ResourceRepository {
+    /// Resources of the store that are not deleted, inactive ones included.
+    fn find_all(&self, store_id: i32, query: ResourceQuery) -> QueryResult<Vec<Resource>>;
+
+    fn create(&self, store_id: i32, resource_data: GenerateResource, actor: &Actor) -> Result<Resource, ApiError>;
+
+    fn update(&self, store_id: i32, id: i32, resource_data: GenerateResource, actor: &Actor) -> Result<Resource, ApiError>;
+
+    fn delete(&self, store_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+}
+
+pub struct PgResourceRepository<'a> {
+    conn: &'a PgConnection
+}
+
+impl<'a> PgResourceRepository<'a> {
+    pub fn new(conn: &'a PgConnection) -> PgResourceRepository<'a> {
+        PgResourceRepository { conn }
+    }
+}
+
+impl<'a> ResourceRepository for PgResourceRepository<'a> {
+    fn find_all(&self, store_id: i32, query: ResourceQuery) -> QueryResult<Vec<Resource>> {
+        let span = debug_span!("Resource::find_all");
+        let _entered = span.enter();
+
+        store::table
+            .filter(store::store_id.eq(store_id))
+            .select(store::store_id)
+            .first::<i32>(self.conn)?;
+
+        let mut resources = resource::table
+            .filter(resource::store_id.eq(store_id))
+            .filter(resource::deleted_at.is_null())
+            .into_boxed();
+
+        if let Some(resource_type) = query.resource_type {
+            resources = resources.filter(resource::resource_type.eq(resource_type));
+        }
+
+        resources
+            .order(resource::resource_id.asc())
+            .load::<Resource>(self.conn)
+    }
+
+    fn create(&self, store_id: i32, resource_data: GenerateResource, actor: &Actor) -> Result<Resource, ApiError> {
+        let span = debug_span!("Resource::create");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            store::table
+                .filter(store::store_id.eq(store_id))
+                .select(store::store_id)
+                .first::<i32>(self.conn)?;
+
+            let resource_created: Resource = diesel::insert_into(resource::table)
+                .values(ResourceCreate {
+                    store_id,
+                    name: resource_data.name,
+                    resource_type: resource_data.resource_type,
+                    is_active: resource_data.is_active
+                })
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "resource", resource_created.resource_id, &resource_created)?;
+
+            Ok(resource_created)
+        })
+    }
+
+    fn update(&self, store_id: i32, id: i32, resource_data: GenerateResource, actor: &Actor) -> Result<Resource, ApiError> {
+        let span = debug_span!("Resource::update");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let resource_before = resource::table
+                .filter(resource::resource_id.eq(id))
+                .filter(resource::store_id.eq(store_id))
+                .filter(resource::deleted_at.is_null())
+                .first::<Resource>(self.conn)?;
+            let resource_updated: Resource = diesel::update(resource::table)
+                .filter(resource::resource_id.eq(id))
+                .set(ResourceCreate {
+                    store_id,
+                    name: resource_data.name,
+                    resource_type: resource_data.resource_type,
+                    is_active: resource_data.is_active
+                })
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "resource", id, &resource_before, &resource_updated)?;
+
+            Ok(resource_updated)
+        })
+    }
+
+    fn delete(&self, store_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Resource::delete");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let resource_before = resource::table
+                .filter(resource::resource_id.eq(id))
+                .filter(resource::store_id.eq(store_id))
+                .filter(resource::deleted_at.is_null())
+                .first::<Resource>(self.conn)
+                .optional()?;
+
+            // soft delete so past appointments still name the room they held
+            let res = diesel::update(resource::table)
+                .filter(resource::resource_id.eq(id))
+                .filter(resource::store_id.eq(store_id))
+                .filter(resource::deleted_at.is_null())
+                .set(resource::deleted_at.eq(Utc::now().naive_utc()))
+                .execute(self.conn)?;
+
+            if let Some(resource_before) = resource_before {
+                audit::deleted(self.conn, actor, "resource", id, &resource_before)?;
+            }
+
+            Ok(res)
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,92 @@
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::resource::{GenerateResource, Resource, ResourceQuery, StoreResourcePath};
+use crate::store::StoreId;
+use crate::validation::Valid;
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores/{store_id}/resources",
+    tag = "resource",
+    operation_id = "resource_find_all",
+    params(
+        ("store_id" = i32, Path, description = "Store id"),
+        ResourceQuery
+    ),
+    responses(
+        (status = 200, description = "Rooms, chairs and equipment at the store", body = [Resource])
+    )
+)]
+#[get("/stores/{store_id}/resources")]
+async fn find_all(id: web::Path<StoreId>, query: web::Query<ResourceQuery>) -> Result<HttpResponse, ApiError> {
+    let resources = Resource::find_all(id.store_id, query.into_inner())?;
+    Ok(HttpResponse::Ok().json(resources))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/stores/{store_id}/resources",
+    tag = "resource",
+    operation_id = "resource_create",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = GenerateResource,
+    responses(
+        (status = 201, description = "Created resource", body = Resource)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/stores/{store_id}/resources")]
+async fn create(id: web::Path<StoreId>, resource: Valid<GenerateResource>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let resource = Resource::create(id.store_id, resource.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(resource))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/stores/{store_id}/resources/{resource_id}",
+    tag = "resource",
+    operation_id = "resource_update",
+    params(
+        ("store_id" = i32, Path, description = "Store id"),
+        ("resource_id" = i32, Path, description = "Resource id")
+    ),
+    request_body = GenerateResource,
+    responses(
+        (status = 200, description = "Updated resource; bookings already holding it keep it", body = Resource)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/stores/{store_id}/resources/{resource_id}")]
+async fn update(path: web::Path<StoreResourcePath>, resource: Valid<GenerateResource>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let resource = Resource::update(path.store_id, path.resource_id, resource.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(resource))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/stores/{store_id}/resources/{resource_id}",
+    tag = "resource",
+    operation_id = "resource_delete",
+    params(
+        ("store_id" = i32, Path, description = "Store id"),
+        ("resource_id" = i32, Path, description = "Resource id")
+    ),
+    responses(
+        (status = 200, description = "Number of resources deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/stores/{store_id}/resources/{resource_id}")]
+async fn delete(path: web::Path<StoreResourcePath>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let resources_deleted = Resource::delete(path.store_id, path.resource_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": resources_deleted })))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(create);
+    cfg.service(update);
+    cfg.service(delete);
+}
\ No newline at end of file
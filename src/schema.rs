@@ -1,3 +1,78 @@
+table! {
+    appointment (appointment_id) {
+        appointment_id -> Int4,
+        customer_id -> Int4,
+        status -> Text,
+        start_time -> Timestamp,
+        end_time -> Timestamp,
+        price -> Float8,
+        created_at -> Timestamp,
+        appointment_series_id -> Nullable<Int4>,
+        occurrence_time -> Nullable<Timestamp>,
+        store_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    appointment_segment (appointment_segment_id) {
+        appointment_segment_id -> Int4,
+        appointment_id -> Int4,
+        position -> Int4,
+        staff_id -> Int4,
+        service_variant_id -> Int4,
+        price -> Float8,
+        start_time -> Timestamp,
+        end_time -> Timestamp,
+        blocked_from -> Timestamp,
+        blocked_until -> Timestamp,
+    }
+}
+
+table! {
+    appointment_segment_add_on (appointment_segment_add_on_id) {
+        appointment_segment_add_on_id -> Int4,
+        appointment_segment_id -> Int4,
+        service_add_on_id -> Int4,
+        price -> Float8,
+    }
+}
+
+table! {
+    appointment_segment_resource (appointment_segment_resource_id) {
+        appointment_segment_resource_id -> Int4,
+        appointment_segment_id -> Int4,
+        resource_id -> Int4,
+    }
+}
+
+table! {
+    appointment_series (appointment_series_id) {
+        appointment_series_id -> Int4,
+        customer_id -> Int4,
+        status -> Text,
+        start_time -> Timestamp,
+        rrule -> Text,
+        segments -> Jsonb,
+        excluded_times -> Array<Timestamp>,
+        materialized_until -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        store_id -> Nullable<Int4>,
+    }
+}
+
+table! {
+    audit_log (audit_log_id) {
+        audit_log_id -> Int4,
+        actor -> Text,
+        entity_type -> Text,
+        entity_id -> Int4,
+        action -> Text,
+        before -> Nullable<Jsonb>,
+        after -> Nullable<Jsonb>,
+        created_at -> Timestamp,
+    }
+}
+
 table! {
     block_extra_time (block_extra_time_it) {
         block_extra_time_it -> Int4,
@@ -7,13 +82,102 @@ table! {
     }
 }
 
+table! {
+    class_enrollment (class_enrollment_id) {
+        class_enrollment_id -> Int4,
+        class_session_id -> Int4,
+        customer_id -> Int4,
+        status -> Text,
+        price -> Float8,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    class_session (class_session_id) {
+        class_session_id -> Int4,
+        service_variant_id -> Int4,
+        staff_id -> Int4,
+        status -> Text,
+        capacity -> Int4,
+        price -> Float8,
+        start_time -> Timestamp,
+        end_time -> Timestamp,
+        blocked_from -> Timestamp,
+        blocked_until -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+table! {
+    customer (customer_id) {
+        customer_id -> Int4,
+        first_name -> Text,
+        last_name -> Text,
+        email -> Nullable<Text>,
+        phone -> Nullable<Text>,
+    }
+}
+
+table! {
+    notification_preference (notification_preference_id) {
+        notification_preference_id -> Int4,
+        customer_id -> Int4,
+        email_enabled -> Int4,
+        sms_enabled -> Int4,
+    }
+}
+
+table! {
+    resource (resource_id) {
+        resource_id -> Int4,
+        store_id -> Int4,
+        name -> Varchar,
+        resource_type -> Text,
+        is_active -> Int4,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
 table! {
     service (service_id) {
         service_id -> Int4,
         name -> Varchar,
         description -> Nullable<Varchar>,
         is_active -> Int4,
-        category -> Nullable<Varchar>,
+        deleted_at -> Nullable<Timestamp>,
+        service_category_id -> Nullable<Int4>,
+        capacity -> Int4,
+    }
+}
+
+table! {
+    service_add_on (service_add_on_id) {
+        service_add_on_id -> Int4,
+        service_id -> Int4,
+        name -> Text,
+        price -> Float8,
+        extra_duration -> Nullable<Time>,
+        is_active -> Int4,
+        deleted_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    service_category (service_category_id) {
+        service_category_id -> Int4,
+        name -> Text,
+        description -> Nullable<Text>,
+        display_order -> Int4,
+        is_active -> Int4,
+    }
+}
+
+table! {
+    service_resource (service_resource_id) {
+        service_resource_id -> Int4,
+        service_id -> Int4,
+        resource_type -> Text,
     }
 }
 
@@ -23,6 +187,21 @@ table! {
         service_id -> Int4,
         price -> Float8,
         duration -> Nullable<Time>,
+        capacity -> Nullable<Int4>,
+    }
+}
+
+table! {
+    sms_message (sms_message_id) {
+        sms_message_id -> Int4,
+        customer_id -> Int4,
+        phone -> Text,
+        body -> Text,
+        status -> Text,
+        provider_message_id -> Nullable<Text>,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
     }
 }
 
@@ -36,6 +215,7 @@ table! {
         phone -> Nullable<Text>,
         access -> Nullable<Text>,
         calendar_color -> Nullable<Text>,
+        deleted_at -> Nullable<Timestamp>,
     }
 }
 
@@ -73,7 +253,7 @@ table! {
         street_address -> Varchar,
         city -> Varchar,
         state -> Varchar,
-        zip -> Int4,
+        zip -> Varchar,
         phone -> Varchar,
         email -> Varchar,
     }
@@ -89,17 +269,85 @@ table! {
     }
 }
 
+table! {
+    webhook_delivery (webhook_delivery_id) {
+        webhook_delivery_id -> Int4,
+        webhook_subscription_id -> Int4,
+        event_type -> Text,
+        payload -> Jsonb,
+        status -> Text,
+        attempts -> Int4,
+        next_attempt_at -> Nullable<Timestamp>,
+        last_status_code -> Nullable<Int4>,
+        last_error -> Nullable<Text>,
+        created_at -> Timestamp,
+        updated_at -> Timestamp,
+    }
+}
+
+table! {
+    webhook_subscription (webhook_subscription_id) {
+        webhook_subscription_id -> Int4,
+        url -> Text,
+        secret -> Text,
+        event_types -> Array<Text>,
+        is_active -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
+joinable!(appointment -> appointment_series (appointment_series_id));
+joinable!(appointment -> customer (customer_id));
+joinable!(appointment -> store (store_id));
+joinable!(appointment_segment -> appointment (appointment_id));
+joinable!(appointment_segment -> service_variant (service_variant_id));
+joinable!(appointment_segment -> staff (staff_id));
+joinable!(appointment_segment_add_on -> appointment_segment (appointment_segment_id));
+joinable!(appointment_segment_add_on -> service_add_on (service_add_on_id));
+joinable!(appointment_segment_resource -> appointment_segment (appointment_segment_id));
+joinable!(appointment_segment_resource -> resource (resource_id));
+joinable!(appointment_series -> customer (customer_id));
+joinable!(appointment_series -> store (store_id));
+joinable!(class_enrollment -> class_session (class_session_id));
+joinable!(class_enrollment -> customer (customer_id));
+joinable!(class_session -> service_variant (service_variant_id));
+joinable!(class_session -> staff (staff_id));
+joinable!(notification_preference -> customer (customer_id));
+joinable!(resource -> store (store_id));
+joinable!(service -> service_category (service_category_id));
+joinable!(service_add_on -> service (service_id));
+joinable!(service_resource -> service (service_id));
+joinable!(sms_message -> customer (customer_id));
 joinable!(staff_service -> service (service_id));
+joinable!(staff_service -> staff (staff_id));
 joinable!(staff_service -> service_variant (service_variant_id));
+joinable!(webhook_delivery -> webhook_subscription (webhook_subscription_id));
 
 allow_tables_to_appear_in_same_query!(
+    appointment,
+    appointment_segment,
+    appointment_segment_add_on,
+    appointment_segment_resource,
+    appointment_series,
+    audit_log,
     block_extra_time,
+    class_enrollment,
+    class_session,
+    customer,
+    notification_preference,
+    resource,
     service,
+    service_add_on,
+    service_category,
+    service_resource,
     service_variant,
+    sms_message,
     staff,
     staff_hours,
     staff_service,
     store,
     store_address,
     store_hours,
+    webhook_delivery,
+    webhook_subscription,
 );
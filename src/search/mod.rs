@@ -0,0 +1,4 @@
+pub mod model;
+pub mod v1;
+
+pub use model::*;
\ No newline at end of file
@@ -0,0 +1,147 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use diesel::pg::PgConnection;
+use diesel::sql_types::{BigInt, Float4, Int4, Nullable, Text};
+use serde::{Deserialize, Serialize};
+use tracing::debug_span;
+use utoipa::{IntoParams, ToSchema};
+
+const SEARCH_TYPES: [&str; 3] = ["staff", "service", "customer"];
+
+/// `word_similarity` needed for `<%` to count as a match. The pg_trgm default
+/// of 0.6 misses a single dropped letter in most names.
+const TYPO_THRESHOLD: &str = "0.5";
+
+/// One `SELECT` per searchable type. The `doc` expressions match the indexes
+/// in the `create_search_indexes` migration; `$1` is the search text and `$2`
+/// the same text as an `ILIKE` pattern.
+const STAFF_SEARCH: &str = "
+    SELECT 'staff' AS kind, staff_id AS id, first_name || ' ' || last_name AS title, email AS subtitle, doc
+    FROM staff, LATERAL (SELECT first_name || ' ' || last_name || ' ' || email || ' ' || coalesce(phone, '') AS doc) d
+    WHERE deleted_at IS NULL";
+
+const SERVICE_SEARCH: &str = "
+    SELECT 'service' AS kind, service_id AS id, name AS title,
+        (SELECT c.name FROM service_category c WHERE c.service_category_id = service.service_category_id) AS subtitle, doc
+    FROM service, LATERAL (SELECT name || ' ' || coalesce(description, '') AS doc) d
+    WHERE deleted_at IS NULL";
+
+const CUSTOMER_SEARCH: &str = "
+    SELECT 'customer' AS kind, customer_id AS id, first_name || ' ' || last_name AS title, coalesce(email, phone) AS subtitle, doc
+    FROM customer, LATERAL (SELECT first_name || ' ' || last_name || ' ' || coalesce(email, '') || ' ' || coalesce(phone, '') AS doc) d
+    WHERE true";
+
+/// Matches whole words, substrings (so partial phone numbers and emails
+/// work) and close misspellings.
+const SEARCH_MATCH: &str = "
+    AND (to_tsvector('simple', doc) @@ plainto_tsquery('simple', $1)
+        OR doc ILIKE $2
+        OR $1 <% doc)";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchQuery {
+    /// Text to look for in names, emails, phone numbers and descriptions
+    pub q: String,
+    /// Comma separated subset of `staff`, `service` and `customer` (default all)
+    pub types: Option<String>
+}
+
+#[derive(Serialize, QueryableByName, ToSchema)]
+pub struct SearchResult {
+    /// `staff`, `service` or `customer`
+    #[sql_type = "Text"]
+    pub kind: String,
+    /// `staff_id`, `service_id` or `customer_id`, depending on `kind`
+    #[sql_type = "Int4"]
+    pub id: i32,
+    #[sql_type = "Text"]
+    pub title: String,
+    #[sql_type = "Nullable<Text>"]
+    pub subtitle: Option<String>,
+    /// Higher is a better match
+    #[sql_type = "Float4"]
+    pub rank: f32
+}
+
+#[derive(QueryableByName)]
+struct SearchTotal {
+    #[sql_type = "BigInt"]
+    total: i64
+}
+
+impl SearchQuery {
+    fn text(&self) -> Result<&str, ApiError> {
+        match self.q.trim() {
+            "" => Err(ApiError::coded(400, "search.query_required", "q is required".to_string())),
+            text => Ok(text),
+        }
+    }
+
+    /// The `UNION ALL` of the selected types, each filtered to matching rows.
+    fn matches(&self) -> Result<String, ApiError> {
+        let types: Vec<&str> = match &self.types {
+            Some(types) => types.split(',').map(str::trim).filter(|kind| !kind.is_empty()).collect(),
+            None => SEARCH_TYPES.to_vec(),
+        };
+
+        if let Some(unknown) = types.iter().find(|kind| !SEARCH_TYPES.contains(kind)) {
+            return Err(ApiError::coded(400, "search.unknown_type", format!("Cannot search {}, expected one of: {}", unknown, SEARCH_TYPES.join(", "))));
+        }
+
+        let selects: Vec<String> = SEARCH_TYPES.iter()
+            .zip([STAFF_SEARCH, SERVICE_SEARCH, CUSTOMER_SEARCH].iter())
+            .filter(|(kind, _)| types.contains(kind))
+            .map(|(_, select)| format!("{}{}", select, SEARCH_MATCH))
+            .collect();
+
+        if selects.is_empty() {
+            return Err(ApiError::coded(400, "search.unknown_type", format!("types must list at least one of: {}", SEARCH_TYPES.join(", "))));
+        }
+
+        Ok(selects.join("\n    UNION ALL"))
+    }
+
+    fn find_page(&self, page: &Pagination, conn: &PgConnection) -> Result<(Vec<SearchResult>, i64), ApiError> {
+        let text = self.text()?;
+        let matches = self.matches()?;
+        let pattern = format!("%{}%", text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_"));
+
+        conn.transaction::<_, ApiError, _>(|| {
+            // LOCAL so the setting ends with this transaction instead of staying on a pooled connection
+            diesel::sql_query(format!("SET LOCAL pg_trgm.word_similarity_threshold = {}", TYPO_THRESHOLD)).execute(conn)?;
+
+            let total = diesel::sql_query(format!("SELECT count(*) AS total FROM ({}) matches", matches))
+                .bind::<Text, _>(text)
+                .bind::<Text, _>(&pattern)
+                .get_result::<SearchTotal>(conn)?
+                .total;
+
+            let results = diesel::sql_query(format!("
+                SELECT kind, id, title, subtitle,
+                    ts_rank(to_tsvector('simple', doc), plainto_tsquery('simple', $1)) + word_similarity($1, doc) AS rank
+                FROM ({}) matches
+                ORDER BY rank DESC, kind, id
+                LIMIT $3 OFFSET $4", matches))
+                .bind::<Text, _>(text)
+                .bind::<Text, _>(&pattern)
+                .bind::<BigInt, _>(page.limit)
+                .bind::<BigInt, _>(page.offset)
+                .load::<SearchResult>(conn)?;
+
+            Ok((results, total))
+        })
+    }
+}
+
+impl SearchResult {
+    /// Staff, services and customers matching `search_query`, best match first.
+    pub fn find_all(search_query: SearchQuery, page: &Pagination) -> Result<(Vec<Self>, i64), ApiError> {
+        let span = debug_span!("Search::find_all");
+        let _entered = span.enter();
+
+        search_query.find_page(page, &db::establish_connection())
+    }
+}
\ No newline at end of file
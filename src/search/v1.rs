@@ -0,0 +1,31 @@
+use crate::api_error::ApiError;
+use crate::pagination::{PageParams, Pagination};
+use crate::search::{SearchQuery, SearchResult};
+use actix_web::{get, web, HttpResponse};
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "search",
+    operation_id = "search",
+    params(
+        SearchQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Matching staff, services and customers, best match first", body = [SearchResult], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        )),
+        (status = 400, description = "Missing `q` or an unknown type", body = ErrorBody)
+    )
+)]
+#[get("/search")]
+async fn search(query: web::Query<SearchQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (results, total) = SearchResult::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, results))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(search);
+}
\ No newline at end of file
@@ -0,0 +1,242 @@
+use crate::api_error::ApiError;
+use crate::validation::{FieldErrors, Validate};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+
+use super::model::{one_to_one, GenerateService, GenerateServiceVariant};
+
+/// Columns of a catalog CSV. Each line is one variant; consecutive lines that
+/// share a `name` are variants of the same service and must agree on the
+/// other service columns.
+#[derive(Serialize, Deserialize)]
+pub struct CatalogRow {
+    pub name: String,
+    pub description: Option<String>,
+    pub category: Option<String>,
+    pub is_active: i32,
+    pub before_time: Option<NaiveTime>,
+    pub after_time: Option<NaiveTime>,
+    pub price: f64,
+    pub duration: Option<NaiveTime>
+}
+
+/// A service as it appears in an import or export file. The same fields as
+/// `GenerateService`, except that the category is named instead of referenced
+/// by id so files can move between databases. Unknown categories are created
+/// on import. Only JSON files carry group class capacities; CSV rows are
+/// imported as one-to-one services.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct CatalogService {
+    pub name: String,
+    pub description: Option<String>,
+    pub is_active: i32,
+    #[serde(default = "one_to_one")]
+    pub capacity: i32,
+    pub category: Option<String>,
+    pub before_time: Option<NaiveTime>,
+    pub after_time: Option<NaiveTime>,
+    pub variants: Vec<GenerateServiceVariant>
+}
+
+/// A service read from an import, keyed by where it came from: `rows[3]` is
+/// the CSV line a service starts on, or the index of a JSON array entry.
+pub struct CatalogEntry {
+    pub row: String,
+    pub service: CatalogService
+}
+
+/// Parsed services plus the errors found so far. Rows that could not be read
+/// are left out of `entries` and reported in `errors` instead.
+#[derive(Default)]
+pub struct CatalogImport {
+    pub entries: Vec<CatalogEntry>,
+    pub errors: FieldErrors
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    /// Validate the file and report what would be created without writing anything
+    pub dry_run: Option<bool>
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportQuery {
+    /// `json` (default) or `csv`
+    pub format: Option<String>
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub services: usize,
+    pub variants: usize,
+    /// Ids of the services created; empty for a dry run
+    pub created: Vec<i32>,
+    /// Messages keyed by row, e.g. `rows[3].price`
+    #[schema(value_type = Object)]
+    pub errors: BTreeMap<String, Vec<String>>
+}
+
+impl CatalogService {
+    /// Trimmed category name, or `None` when blank.
+    pub fn category_name(&self) -> Option<&str> {
+        self.category.as_deref().map(str::trim).filter(|category_name| !category_name.is_empty())
+    }
+
+    pub fn into_service(self, service_category_id: Option<i32>) -> GenerateService {
+        GenerateService {
+            name: self.name,
+            description: self.description,
+            is_active: self.is_active,
+            service_category_id,
+            capacity: self.capacity,
+            before_time: self.before_time,
+            after_time: self.after_time,
+            variants: self.variants
+        }
+    }
+}
+
+impl Validate for CatalogService {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.flag("is_active", self.is_active);
+        errors.capacity("capacity", self.capacity);
+
+        if self.variants.is_empty() {
+            errors.add("variants", "must contain at least one variant");
+        }
+
+        errors.nested("variants", &self.variants);
+    }
+}
+
+impl CatalogRow {
+    fn service(&self) -> CatalogService {
+        CatalogService {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            is_active: self.is_active,
+            capacity: 1,
+            category: self.category.clone(),
+            before_time: self.before_time,
+            after_time: self.after_time,
+            variants: vec![self.variant()]
+        }
+    }
+
+    fn variant(&self) -> GenerateServiceVariant {
+        GenerateServiceVariant { price: self.price, duration: self.duration, capacity: None }
+    }
+
+    fn same_service(&self, other: &CatalogService) -> bool {
+        self.description == other.description
+            && self.category == other.category
+            && self.is_active == other.is_active
+            && self.before_time == other.before_time
+            && self.after_time == other.after_time
+    }
+}
+
+impl CatalogImport {
+    pub fn from_json(body: &[u8]) -> Result<CatalogImport, ApiError> {
+        let values: Vec<serde_json::Value> = serde_json::from_slice(body)
+            .map_err(|err| ApiError::coded(400, "request.malformed_json", err.to_string()))?;
+
+        let mut import = CatalogImport::default();
+
+        for (index, value) in values.into_iter().enumerate() {
+            let key = format!("rows[{}]", index);
+
+            match serde_json::from_value::<CatalogService>(value) {
+                Ok(service) => import.entries.push(CatalogEntry { row: key, service }),
+                Err(err) => import.errors.add(&key, &err.to_string()),
+            }
+        }
+
+        Ok(import)
+    }
+
+    pub fn from_csv(body: &[u8]) -> Result<CatalogImport, ApiError> {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body);
+        let headers = reader.headers()
+            .map_err(|err| ApiError::coded(400, "request.malformed_csv", err.to_string()))?
+            .clone();
+
+        let mut import = CatalogImport::default();
+        let mut record = csv::StringRecord::new();
+
+        loop {
+            let row = match reader.read_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => record.deserialize::<CatalogRow>(Some(&headers)),
+                Err(err) => Err(err),
+            };
+
+            let line = record.position().map(|position| position.line()).unwrap_or_default();
+            let key = format!("rows[{}]", line);
+
+            let row = match row {
+                Ok(row) => row,
+                Err(err) => {
+                    import.errors.add(&key, &err.to_string());
+                    continue;
+                },
+            };
+
+            match import.entries.last_mut() {
+                Some(entry) if entry.service.name == row.name => {
+                    if !row.same_service(&entry.service) {
+                        import.errors.add(&key, &format!("service columns differ from {} for the same name", entry.row));
+                    }
+
+                    entry.service.variants.push(row.variant());
+                },
+                _ => import.entries.push(CatalogEntry { row: key, service: row.service() }),
+            }
+        }
+
+        Ok(import)
+    }
+
+    pub fn validate(&mut self) {
+        if self.entries.is_empty() && self.errors.is_empty() {
+            self.errors.add("rows", "must contain at least one service");
+        }
+
+        for entry in &self.entries {
+            self.errors.nested(&entry.row, &entry.service);
+        }
+    }
+}
+
+pub fn to_csv(services: Vec<CatalogService>) -> Result<String, ApiError> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+
+    for service_data in services {
+        for variant in &service_data.variants {
+            let row = CatalogRow {
+                name: service_data.name.clone(),
+                description: service_data.description.clone(),
+                category: service_data.category.clone(),
+                is_active: service_data.is_active,
+                before_time: service_data.before_time,
+                after_time: service_data.after_time,
+                price: variant.price,
+                duration: variant.duration
+            };
+
+            writer.serialize(row)
+                .map_err(|err| ApiError::new(500, format!("Could not write CSV: {}", err)))?;
+        }
+    }
+
+    let bytes = writer.into_inner()
+        .map_err(|err| ApiError::new(500, format!("Could not write CSV: {}", err)))?;
+
+    String::from_utf8(bytes).map_err(|err| ApiError::new(500, format!("Could not write CSV: {}", err)))
+}
\ No newline at end of file
@@ -1,5 +1,9 @@
+pub mod catalog;
 pub mod model;
+pub mod repository;
 pub mod routes;
+pub mod v1;
 
 pub use model::*;
+pub use repository::*;
 pub use routes::init_routes;
\ No newline at end of file
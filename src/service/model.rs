@@ -1,9 +1,19 @@
 use crate::db;
 use crate::api_error::ApiError;
-use crate::{schema::service::{self, dsl::*}, schema::service_variant::{self, dsl::*}, schema::block_extra_time::{self, dsl::*}};
-use chrono::{NaiveTime};
+use crate::audit::Actor;
+use crate::{schema::service, schema::service_add_on, schema::service_category, schema::service_resource, schema::service_variant, schema::block_extra_time};
+use chrono::{Duration, NaiveDateTime, NaiveTime};
 use crate::diesel::prelude::*;
+use crate::pagination::{Pagination, Sort};
+use crate::validation::{FieldErrors, Validate};
+use diesel::dsl::sql;
+use diesel::pg::{Pg, PgConnection};
+use diesel::sql_types::Integer;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::catalog::{CatalogImport, CatalogService, ImportReport};
+use super::repository::{PgServiceRepository, ServiceRepository};
 
 pub use crate::staff::model::{
     Staff,
@@ -15,50 +25,169 @@ pub struct ServiceId {
     pub service_id: i32
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ServiceQuery {
+    pub include_deleted: Option<bool>,
+    pub service_category_id: Option<i32>,
+    pub is_active: Option<i32>,
+    /// `category` to return services in groups, in category display order and
+    /// without inactive categories, or `none` for a flat list. `GET /service`
+    /// groups by default, `GET /api/v1/services` does not.
+    pub group_by: Option<String>
+}
+
+const SERVICE_SORT_FIELDS: [&str; 3] = ["service_id", "name", "category"];
+
+/// Category display order, uncategorized services last. The category id
+/// keeps categories with the same display order from interleaving.
+const CATEGORY_ORDER_ASC: &str = "(SELECT display_order FROM service_category c WHERE c.service_category_id = service.service_category_id) ASC NULLS LAST, service.service_category_id ASC NULLS LAST, service.name ASC";
+const CATEGORY_ORDER_DESC: &str = "(SELECT display_order FROM service_category c WHERE c.service_category_id = service.service_category_id) DESC NULLS FIRST, service.service_category_id DESC NULLS FIRST, service.name ASC";
+
+impl ServiceQuery {
+    fn filtered(&self) -> service::BoxedQuery<'static, Pg> {
+        let mut query = service::table.into_boxed();
+
+        if !self.include_deleted.unwrap_or(false) {
+            query = query.filter(service::deleted_at.is_null());
+        }
+
+        if let Some(category_id) = self.service_category_id {
+            query = query.filter(service::service_category_id.eq(category_id));
+        }
+
+        if let Some(active) = self.is_active {
+            query = query.filter(service::is_active.eq(active));
+        }
+
+        // groups are what the booking page shows, so hidden categories stay out
+        if self.group_by.as_deref() == Some("category") {
+            let active_categories = service_category::table
+                .filter(service_category::is_active.eq(1))
+                .select(service_category::service_category_id.nullable());
+
+            query = query.filter(service::service_category_id.is_null().or(service::service_category_id.eq_any(active_categories)));
+        }
+
+        query
+    }
+
+    /// Whether to group by category, `by_default` when `group_by` is not
+    /// given. Anything other than `category` or `none` is rejected.
+    pub fn grouped(&self, by_default: bool) -> Result<bool, ApiError> {
+        match self.group_by.as_deref() {
+            None => Ok(by_default),
+            Some("category") => Ok(true),
+            Some("none") => Ok(false),
+            Some(other) => Err(ApiError::coded(400, "service.invalid_group_by", format!("Cannot group by {}, expected category or none", other))),
+        }
+    }
+
+    pub(super) fn find_page(&self, page: &Pagination, conn: &PgConnection) -> Result<(Vec<Service>, i64), ApiError> {
+        // groups only make sense in category order
+        let sort = match self.grouped(false)? {
+            true => Sort { field: "category".to_string(), descending: false },
+            false => page.sort(&SERVICE_SORT_FIELDS)?,
+        };
+        let total = self.filtered().count().get_result::<i64>(conn)?;
+
+        let query = match (sort.field.as_str(), sort.descending) {
+            ("name", false) => self.filtered().order(service::name.asc()),
+            ("name", true) => self.filtered().order(service::name.desc()),
+            ("category", false) => self.filtered().order(sql::<Integer>(CATEGORY_ORDER_ASC)),
+            ("category", true) => self.filtered().order(sql::<Integer>(CATEGORY_ORDER_DESC)),
+            (_, true) => self.filtered().order(service::service_id.desc()),
+            (_, false) => self.filtered().order(service::service_id.asc()),
+        };
+
+        let services = query
+            .then_order_by(service::service_id.asc())
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<Service>(conn)?;
+
+        Ok((services, total))
+    }
+}
+
 #[derive(Deserialize)]
 pub struct ServiceVariantId {
     pub staff_variant_id: i32
 }
 
+#[derive(Deserialize)]
+pub struct ServiceVariantPath {
+    pub service_id: i32,
+    pub service_variant_id: i32
+}
+
+#[derive(Deserialize)]
+pub struct ServiceAddOnPath {
+    pub service_id: i32,
+    pub service_add_on_id: i32
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QuoteQuery {
+    pub service_variant_id: i32,
+    /// Comma separated `service_add_on_id`s
+    pub add_on_ids: Option<String>,
+    /// When the service starts; the end times are left out without it
+    pub start_time: Option<NaiveDateTime>
+}
+
 #[derive(Deserialize)]
 pub struct BlockExtraTimeId {
     pub block_extra_time: i32
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct FullService {
     pub service: Service,
     pub blocked_time: BlockExtraTime,
     pub variants: Vec<ServiceVariant>
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct FullStaffService {
     pub service: Service,
     pub blocked_time: BlockExtraTime,
     pub variants: Vec<ServiceVariant>,
+    pub add_ons: Vec<ServiceAddOn>,
     pub staff: Vec<BasicStaffInfo>
 }
 
+/// Services in one active category, returned by `GET /service` and by
+/// `GET /api/v1/services?group_by=category`. `category` is null for services
+/// without one.
+#[derive(Serialize, ToSchema)]
+pub struct ServiceCategoryGroup {
+    pub category: Option<ServiceCategory>,
+    pub services: Vec<FullStaffService>
+}
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GenerateService {
     pub name: String,
     pub description: Option<String>,
     pub is_active: i32,
-    pub category: Option<String>,
+    pub service_category_id: Option<i32>,
+    #[serde(default = "one_to_one")]
+    pub capacity: i32,
     pub before_time: Option<NaiveTime>,
     pub after_time: Option<NaiveTime>,
     pub variants: Vec<GenerateServiceVariant>
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct GenerateServiceVariant {
     pub price: f64,
-    pub duration: Option<NaiveTime>
+    pub duration: Option<NaiveTime>,
+    pub capacity: Option<i32>
 }
 
-#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable)]
+#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, ToSchema)]
 #[primary_key(service_id)]
 #[table_name = "service"]
 pub struct Service {
@@ -66,19 +195,51 @@ pub struct Service {
     pub name: String,
     pub description: Option<String>,
     pub is_active: i32,
-    pub category: Option<String>
+    pub deleted_at: Option<NaiveDateTime>,
+    pub service_category_id: Option<i32>,
+    /// Customers one slot takes; 1 is a one-to-one service, more makes it a
+    /// group class booked through class sessions
+    pub capacity: i32
 }
 
-#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
 #[table_name = "service"]
 pub struct ServiceCreate {
     pub name: String,
     pub description: Option<String>,
     pub is_active: i32,
-    pub category: Option<String>
+    pub service_category_id: Option<i32>,
+    #[serde(default = "one_to_one")]
+    pub capacity: i32
+}
+
+#[derive(Deserialize)]
+pub struct ServiceCategoryId {
+    pub service_category_id: i32
+}
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[primary_key(service_category_id)]
+#[table_name = "service_category"]
+pub struct ServiceCategory {
+    pub service_category_id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    /// Lower numbers are listed first
+    pub display_order: i32,
+    pub is_active: i32
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
+#[table_name = "service_category"]
+pub struct ServiceCategoryCreate {
+    pub name: String,
+    pub description: Option<String>,
+    pub display_order: i32,
+    pub is_active: i32
 }
 
-#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Clone)]
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Clone, ToSchema)]
 #[belongs_to(Service)]
 #[primary_key(service_variant_id)]
 #[table_name = "service_variant"]
@@ -86,7 +247,9 @@ pub struct ServiceVariant {
     pub service_variant_id: i32,
     pub service_id: i32,
     pub price: f64,
-    pub duration: Option<NaiveTime>
+    pub duration: Option<NaiveTime>,
+    /// Overrides the service's capacity for this variant
+    pub capacity: Option<i32>
 }
 
 #[derive(Serialize, Deserialize, AsChangeset, Insertable)]
@@ -94,10 +257,93 @@ pub struct ServiceVariant {
 pub struct ServiceVariantCreate {
     pub service_id: i32,
     pub price: f64,
-    pub duration: Option<NaiveTime>
+    pub duration: Option<NaiveTime>,
+    pub capacity: Option<i32>
+}
+
+/// Something booked on top of a service, such as deep conditioning with a
+/// cut, that adds to its price and length.
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, Clone, ToSchema)]
+#[belongs_to(Service)]
+#[primary_key(service_add_on_id)]
+#[table_name = "service_add_on"]
+pub struct ServiceAddOn {
+    pub service_add_on_id: i32,
+    pub service_id: i32,
+    pub name: String,
+    pub price: f64,
+    /// Added to the variant duration
+    pub extra_duration: Option<NaiveTime>,
+    pub is_active: i32,
+    pub deleted_at: Option<NaiveDateTime>
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct GenerateServiceAddOn {
+    pub name: String,
+    pub price: f64,
+    pub extra_duration: Option<NaiveTime>,
+    pub is_active: i32
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[table_name = "service_add_on"]
+pub struct ServiceAddOnCreate {
+    pub service_id: i32,
+    pub name: String,
+    pub price: f64,
+    pub extra_duration: Option<NaiveTime>,
+    pub is_active: i32
+}
+
+/// A resource type every booking of the service holds one of, such as a
+/// `treatment_room` for a massage.
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, Clone)]
+#[belongs_to(Service)]
+#[primary_key(service_resource_id)]
+#[table_name = "service_resource"]
+pub struct ServiceResource {
+    pub service_resource_id: i32,
+    pub service_id: i32,
+    pub resource_type: String
+}
+
+#[derive(Insertable)]
+#[table_name = "service_resource"]
+pub struct ServiceResourceCreate {
+    pub service_id: i32,
+    pub resource_type: String
+}
+
+/// The resource types a service needs, replaced as a whole.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct ServiceResourceTypes {
+    /// e.g. `["treatment_room"]`; empty when staff alone are enough
+    pub resource_types: Vec<String>
+}
+
+/// Price and timing of one variant with its add-ons. `before_time` and
+/// `after_time` padding is blocked on the staff member's calendar but not
+/// charged, so `blocked_from` and `blocked_until` extend past the service
+/// itself.
+#[derive(Serialize, ToSchema)]
+pub struct ServiceQuote {
+    pub service_id: i32,
+    pub service_variant_id: i32,
+    pub add_ons: Vec<ServiceAddOn>,
+    /// Variant price plus every add-on
+    pub price: f64,
+    /// Variant duration plus every add-on, in minutes
+    pub duration_minutes: i64,
+    pub before_minutes: i64,
+    pub after_minutes: i64,
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: Option<NaiveDateTime>,
+    pub blocked_from: Option<NaiveDateTime>,
+    pub blocked_until: Option<NaiveDateTime>
 }
 
-#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Copy, Clone)]
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Copy, Clone, ToSchema)]
 #[belongs_to(Service)]
 #[primary_key(block_extra_time_id)]
 #[table_name = "block_extra_time"]
@@ -116,216 +362,283 @@ pub struct BlockExtraTimeCreate {
     pub after_time: Option<NaiveTime>
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 pub struct UpdateServiceAll {
     pub name: String,
     pub description: Option<String>,
     pub is_active: i32,
-    pub category: Option<String>,
+    pub service_category_id: Option<i32>,
+    #[serde(default = "one_to_one")]
+    pub capacity: i32,
     pub before_time: Option<NaiveTime>,
     pub after_time: Option<NaiveTime>,
     pub variants: Vec<ServiceVariant>
 }
 
-impl Service {
-    pub fn find_all() -> QueryResult<Vec<FullStaffService>> {
-        let conn = db::establish_connection();
-
-        let all_services = service::table.load::<Service>(&conn)?;
-        let all_blocked_time = BlockExtraTime::belonging_to(&all_services)
-            .load::<BlockExtraTime>(&conn)?
-            .grouped_by(&all_services);
-        let all_services_variants = ServiceVariant::belonging_to(&all_services)
-            .load::<ServiceVariant>(&conn)?
-            .grouped_by(&all_services);
-
-        let mut services_final_list: Vec<FullStaffService> = vec![];
-        
-        let mut temp_iter = 0;
-        for current_service in all_services {
-            let mut all_staff_in_service: Vec<BasicStaffInfo> = vec![]; 
-
-            let staff_service_list = Staff::find_staff_with_service(current_service.service_id)?;
-
-            for service_item in staff_service_list {
-                let temp_staff_info = Staff::find_basic(service_item.staff_id)?;
-                all_staff_in_service.push(temp_staff_info);
-            }
+impl Validate for GenerateService {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.flag("is_active", self.is_active);
+        errors.capacity("capacity", self.capacity);
+
+        if self.variants.is_empty() {
+            errors.add("variants", "must contain at least one variant");
+        }
+
+        errors.nested("variants", &self.variants);
+    }
+}
 
-            let current_service = FullStaffService {
-                service: current_service,
-                blocked_time: all_blocked_time[temp_iter][0].clone(),
-                variants: all_services_variants[temp_iter].clone(),
-                staff: all_staff_in_service
-            };
+impl Validate for GenerateServiceVariant {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.non_negative("price", self.price);
 
-            services_final_list.push(current_service);
-            temp_iter += 1;
-            
+        if let Some(capacity) = self.capacity {
+            errors.capacity("capacity", capacity);
         }
+    }
+}
 
-        Ok(services_final_list)
+impl Validate for GenerateServiceAddOn {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.non_negative("price", self.price);
+        errors.flag("is_active", self.is_active);
     }
+}
 
-    pub fn find(id: i32) -> QueryResult<FullStaffService> {
-        let conn = db::establish_connection();
+impl Validate for ServiceResourceTypes {
+    fn validate(&self, errors: &mut FieldErrors) {
+        for (index, resource_type) in self.resource_types.iter().enumerate() {
+            let field = format!("resource_types[{}]", index);
+            errors.resource_type(&field, resource_type);
 
-        let service_entity: Self = service.filter(service::service_id.eq(id)).first::<Self>(&conn)?;
-        let block_extra: BlockExtraTime = block_extra_time.filter(block_extra_time::service_id.eq(id)).first::<BlockExtraTime>(&conn)?;
-        let service_variants: Vec<ServiceVariant> = service_variant.filter(service_variant::service_id.eq(id)).load::<ServiceVariant>(&conn)?;
-        let mut all_staff_in_service: Vec<BasicStaffInfo> = vec![]; 
+            if self.resource_types[..index].contains(resource_type) {
+                errors.add(&field, "is listed twice");
+            }
+        }
+    }
+}
 
-        let staff_service_list = Staff::find_staff_with_service(service_entity.service_id)?;
+impl Validate for ServiceCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.flag("is_active", self.is_active);
+        errors.capacity("capacity", self.capacity);
+    }
+}
 
-        for service_item in staff_service_list {
-            let temp_staff_info = Staff::find_basic(service_item.staff_id)?;
-            all_staff_in_service.push(temp_staff_info);
+impl Validate for ServiceCategoryCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.flag("is_active", self.is_active);
+    }
+}
+
+impl Validate for UpdateServiceAll {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+        errors.flag("is_active", self.is_active);
+        errors.capacity("capacity", self.capacity);
+
+        for (index, variant) in self.variants.iter().enumerate() {
+            errors.non_negative(&format!("variants[{}].price", index), variant.price);
+
+            if let Some(capacity) = variant.capacity {
+                errors.capacity(&format!("variants[{}].capacity", index), capacity);
+            }
         }
+    }
+}
 
-        let full_service = FullStaffService {
-            service: service_entity,
-            blocked_time: block_extra,
-            variants: service_variants,
-            staff: all_staff_in_service
-        };
+pub(super) fn one_to_one() -> i32 {
+    1
+}
 
-        Ok(full_service)
+/// Length of a `TIME` column used as a duration, e.g. `00:45:00`.
+fn length(time: Option<NaiveTime>) -> Duration {
+    time.map(|time| time.signed_duration_since(NaiveTime::from_hms(0, 0, 0)))
+        .unwrap_or_else(Duration::zero)
+}
+
+impl QuoteQuery {
+    /// `add_on_ids` split on commas, with a 422 naming any entry that is not a number.
+    pub fn add_on_ids(&self) -> Result<Vec<i32>, ApiError> {
+        let mut errors = FieldErrors::default();
+        let mut add_on_ids = vec![];
+
+        let entries = self.add_on_ids.as_deref().unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty());
+
+        for (index, entry) in entries.enumerate() {
+            match entry.parse::<i32>() {
+                Ok(add_on_id) => add_on_ids.push(add_on_id),
+                Err(_) => errors.add(&format!("add_on_ids[{}]", index), "must be a number"),
+            }
+        }
+
+        errors.into_result()?;
+
+        Ok(add_on_ids)
     }
+}
 
-    pub fn create(service_data: GenerateService) -> QueryResult<Self> {
-        let conn = db::establish_connection();
+impl ServiceVariant {
+    /// Customers one slot of this variant takes.
+    pub fn capacity_of(&self, current_service: &Service) -> i32 {
+        self.capacity.unwrap_or(current_service.capacity)
+    }
+}
 
-        let service_create = ServiceCreate {
-            name: service_data.name,
-            description: service_data.description,
-            is_active: service_data.is_active,
-            category: service_data.category
-        };
+impl ServiceAddOn {
+    /// The add-ons in `available` named by `add_on_ids`, in that order.
+    /// Ids that are repeated, inactive or not in `available` are reported
+    /// under `field[index]`.
+    pub fn select(available: &[ServiceAddOn], add_on_ids: &[i32], field: &str, errors: &mut FieldErrors) -> Vec<ServiceAddOn> {
+        let mut add_ons = vec![];
 
-        let service_created: Self = diesel::insert_into(service::table)
-            .values(service_create)
-            .get_result(&conn)?;
+        for (index, add_on_id) in add_on_ids.iter().enumerate() {
+            let key = format!("{}[{}]", field, index);
 
-        // set extra blocked time
-        let block_extra_time_create = BlockExtraTimeCreate {
-            service_id: service_created.service_id,
-            before_time: service_data.before_time,
-            after_time: service_data.after_time
-        };
+            if add_on_ids[..index].contains(add_on_id) {
+                errors.add(&key, "is listed more than once");
+                continue;
+            }
 
-        diesel::insert_into(block_extra_time::table)
-            .values(block_extra_time_create)
-            .execute(&conn)?;
-
-        // Create service variants
-        for variant in service_data.variants {
-            let service_variant_create = ServiceVariantCreate {
-                service_id: service_created.service_id,
-                price: variant.price,
-                duration: variant.duration
-            };
-    
-            diesel::insert_into(service_variant::table)
-                .values(service_variant_create)
-                .execute(&conn)?;
+            match available.iter().find(|add_on| add_on.service_add_on_id == *add_on_id && add_on.is_active == 1) {
+                Some(add_on) => add_ons.push(add_on.clone()),
+                None => errors.add(&key, "is not an active add-on of this service"),
+            }
         }
 
-        Ok(service_created)
+        add_ons
     }
+}
 
-    pub fn update(id: i32, service_update: ServiceCreate) -> Result<Self, ApiError> {
-        let conn = db::establish_connection();
+impl ServiceQuote {
+    pub fn new(variant: &ServiceVariant, blocked_time: &BlockExtraTime, add_ons: Vec<ServiceAddOn>, start_time: Option<NaiveDateTime>) -> ServiceQuote {
+        let duration = add_ons.iter().fold(length(variant.duration), |total, add_on| total + length(add_on.extra_duration));
+        let before = length(blocked_time.before_time);
+        let after = length(blocked_time.after_time);
+        let end_time = start_time.map(|start| start + duration);
+
+        ServiceQuote {
+            service_id: variant.service_id,
+            service_variant_id: variant.service_variant_id,
+            price: add_ons.iter().fold(variant.price, |total, add_on| total + add_on.price),
+            add_ons,
+            duration_minutes: duration.num_minutes(),
+            before_minutes: before.num_minutes(),
+            after_minutes: after.num_minutes(),
+            start_time,
+            end_time,
+            blocked_from: start_time.map(|start| start - before),
+            blocked_until: end_time.map(|end| end + after)
+        }
+    }
+}
 
-        let service_updated = diesel::update(service::table)
-            .filter(service::service_id.eq(id))
-            .set(service_update)
-            .get_result(&conn)?;
+/// Shortcuts that run `ServiceRepository` on a connection from the server pool.
+impl Service {
+    pub fn find_all(service_query: ServiceQuery, page: &Pagination) -> Result<(Vec<FullStaffService>, i64), ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).find_all(service_query, page)
+    }
 
-        Ok(service_updated)
+    pub fn find_all_grouped(service_query: ServiceQuery, page: &Pagination) -> Result<(Vec<ServiceCategoryGroup>, i64), ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).find_all_grouped(service_query, page)
     }
 
-    pub fn update_variant(id: i32, variant_update: ServiceVariantCreate) -> Result<ServiceVariant, ApiError> {
-        let conn = db::establish_connection();
+    pub fn find(id: i32) -> QueryResult<FullStaffService> {
+        PgServiceRepository::new(&db::establish_connection()).find(id)
+    }
 
-        let variant_updated = diesel::update(service_variant::table)
-            .filter(service_variant::service_variant_id.eq(id))
-            .set(variant_update)
-            .get_result(&conn)?;
+    pub fn create(service_data: GenerateService, actor: &Actor) -> QueryResult<Self> {
+        PgServiceRepository::new(&db::establish_connection()).create(service_data, actor)
+    }
 
-        Ok(variant_updated)
+    pub fn update(id: i32, service_update: ServiceCreate, actor: &Actor) -> Result<Self, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update(id, service_update, actor)
     }
 
-    pub fn update_time_block(id: i32, block_update: BlockExtraTimeCreate) -> Result<BlockExtraTime, ApiError> {
-        let conn = db::establish_connection();
+    pub fn find_variants(id: i32) -> QueryResult<Vec<ServiceVariant>> {
+        PgServiceRepository::new(&db::establish_connection()).find_variants(id)
+    }
 
-        let block_updated = diesel::update(block_extra_time::table)
-            .filter(block_extra_time::service_id.eq(id))
-            .set(block_update)
-            .get_result(&conn)?;
+    pub fn update_variant(current_service_id: i32, id: i32, variant_update: GenerateServiceVariant, actor: &Actor) -> Result<ServiceVariant, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update_variant(current_service_id, id, variant_update, actor)
+    }
 
-        Ok(block_updated)
+    pub fn update_time_block(id: i32, block_update: BlockExtraTimeCreate, actor: &Actor) -> Result<BlockExtraTime, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update_time_block(id, block_update, actor)
     }
 
-    pub fn update_all(id: i32, service_update: UpdateServiceAll) -> Result<Self, ApiError> {
-        let conn = db::establish_connection();
+    pub fn update_all(id: i32, service_update: UpdateServiceAll, actor: &Actor) -> Result<Self, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update_all(id, service_update, actor)
+    }
 
-        let service_update_info = ServiceCreate {
-            name: service_update.name,
-            description: service_update.description,
-            is_active: service_update.is_active,
-            category: service_update.category
-        };
+    pub fn delete(id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).delete(id, actor)
+    }
 
-        let service_updated = diesel::update(service::table)
-            .filter(service::service_id.eq(id))
-            .set(service_update_info)
-            .get_result(&conn)?;
-        
-        for variant in service_update.variants {
-            let service_variant_update = ServiceVariantCreate {
-                service_id: id,
-                price: variant.price,
-                duration: variant.duration
-            };
-            
-            diesel::update(service_variant::table)
-                .filter(service_variant::service_variant_id.eq(variant.service_variant_id))
-                .set(service_variant_update)
-                .execute(&conn)?;
-        }
+    pub fn restore(id: i32, actor: &Actor) -> Result<Self, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).restore(id, actor)
+    }
 
-        let blocked_time_update = BlockExtraTimeCreate {
-            service_id: id,
-            before_time: service_update.before_time,
-            after_time: service_update.after_time
-        };
-        
-        diesel::update(block_extra_time::table)
-            .filter(block_extra_time::service_id.eq(id))
-            .set(blocked_time_update)
-            .execute(&conn)?;
+    pub fn import(import: CatalogImport, dry_run: bool, actor: &Actor) -> Result<ImportReport, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).import(import, dry_run, actor)
+    }
+
+    pub fn export() -> QueryResult<Vec<CatalogService>> {
+        PgServiceRepository::new(&db::establish_connection()).export()
+    }
+
+    pub fn find_add_ons(id: i32) -> QueryResult<Vec<ServiceAddOn>> {
+        PgServiceRepository::new(&db::establish_connection()).find_add_ons(id)
+    }
 
-        Ok(service_updated)
+    pub fn create_add_on(id: i32, add_on: GenerateServiceAddOn, actor: &Actor) -> Result<ServiceAddOn, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).create_add_on(id, add_on, actor)
     }
 
-    pub fn delete(id: i32) -> Result<usize, ApiError> {
-        let conn = db::establish_connection();
+    pub fn update_add_on(current_service_id: i32, id: i32, add_on: GenerateServiceAddOn, actor: &Actor) -> Result<ServiceAddOn, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update_add_on(current_service_id, id, add_on, actor)
+    }
 
-        // also make sure to delete other service data
-        diesel::delete(service_variant::table)
-            .filter(service_variant::service_id.eq(id))
-            .execute(&conn)?;
+    pub fn delete_add_on(current_service_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).delete_add_on(current_service_id, id, actor)
+    }
 
-        diesel::delete(block_extra_time::table)
-            .filter(block_extra_time::service_id.eq(id))
-            .execute(&conn)?;
+    pub fn find_resource_types(id: i32) -> QueryResult<ServiceResourceTypes> {
+        PgServiceRepository::new(&db::establish_connection()).find_resource_types(id)
+    }
 
-        let res = diesel::delete(
-                service::table
-                    .filter(service::service_id.eq(id))
-            )
-            .execute(&conn)?;
+    pub fn update_resource_types(id: i32, resource_types: ServiceResourceTypes, actor: &Actor) -> Result<ServiceResourceTypes, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update_resource_types(id, resource_types, actor)
+    }
 
-        Ok(res)
+    pub fn quote(id: i32, quote_query: QuoteQuery) -> Result<ServiceQuote, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).quote(id, quote_query)
     }
-}
+
+    pub fn find_categories() -> QueryResult<Vec<ServiceCategory>> {
+        PgServiceRepository::new(&db::establish_connection()).find_categories()
+    }
+
+    pub fn find_category(id: i32) -> QueryResult<ServiceCategory> {
+        PgServiceRepository::new(&db::establish_connection()).find_category(id)
+    }
+
+    pub fn create_category(category_create: ServiceCategoryCreate, actor: &Actor) -> Result<ServiceCategory, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).create_category(category_create, actor)
+    }
+
+    pub fn update_category(id: i32, category_update: ServiceCategoryCreate, actor: &Actor) -> Result<ServiceCategory, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).update_category(id, category_update, actor)
+    }
+
+    pub fn delete_category(id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgServiceRepository::new(&db::establish_connection()).delete_category(id, actor)
+    }
+}
\ No newline at end of file
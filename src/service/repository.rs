@@ -0,0 +1,845 @@
+use crate::api_error::ApiError;
+use crate::audit::{self, Actor};
+use crate::{schema::service::{self, dsl::*}, schema::service_add_on, schema::service_category, schema::service_resource, schema::service_variant::{self, dsl::*}, schema::block_extra_time::{self, dsl::*}};
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::validation::FieldErrors;
+use crate::staff::{PgStaffRepository, StaffRepository};
+use crate::webhook;
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use diesel::sql_types::Text;
+use serde_json::json;
+use tracing::debug_span;
+
+sql_function!(fn lower(x: Text) -> Text);
+
+use super::catalog::{CatalogImport, CatalogService, ImportReport};
+use super::model::*;
+
+/// Services with their variants and the extra time blocked around them.
+///
+/// Every call runs on the connection the repository was built with, so
+/// callers choose the database and can wrap several calls in one
+/// transaction.
+pub trait ServiceRepository {
+    fn find_all(&self, service_query: ServiceQuery, page: &Pagination) -> Result<(Vec<FullStaffService>, i64), ApiError>;
+
+    /// Like `find_all`, with the page split into category groups in display
+    /// order.
+    fn find_all_grouped(&self, service_query: ServiceQuery, page: &Pagination) -> Result<(Vec<ServiceCategoryGroup>, i64), ApiError>;
+
+    fn find(&self, id: i32) -> QueryResult<FullStaffService>;
+
+    fn create(&self, service_data: GenerateService, actor: &Actor) -> QueryResult<Service>;
+
+    fn update(&self, id: i32, service_update: ServiceCreate, actor: &Actor) -> Result<Service, ApiError>;
+
+    fn find_variants(&self, id: i32) -> QueryResult<Vec<ServiceVariant>>;
+
+    fn update_variant(&self, current_service_id: i32, id: i32, variant_update: GenerateServiceVariant, actor: &Actor) -> Result<ServiceVariant, ApiError>;
+
+    fn update_time_block(&self, id: i32, block_update: BlockExtraTimeCreate, actor: &Actor) -> Result<BlockExtraTime, ApiError>;
+
+    fn update_all(&self, id: i32, service_update: UpdateServiceAll, actor: &Actor) -> Result<Service, ApiError>;
+
+    fn delete(&self, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+
+    fn restore(&self, id: i32, actor: &Actor) -> Result<Service, ApiError>;
+
+    /// Creates every service in `import`, or none of them if any row is
+    /// invalid or names a service that already exists. A dry run reports the
+    /// same errors without writing anything.
+    fn import(&self, import: CatalogImport, dry_run: bool, actor: &Actor) -> Result<ImportReport, ApiError>;
+
+    /// Every service that is not deleted, in the shape `import` accepts.
+    fn export(&self) -> QueryResult<Vec<CatalogService>>;
+
+    /// Add-ons of a service that is not deleted, including inactive ones.
+    fn find_add_ons(&self, id: i32) -> QueryResult<Vec<ServiceAddOn>>;
+
+    fn create_add_on(&self, id: i32, add_on: GenerateServiceAddOn, actor: &Actor) -> Result<ServiceAddOn, ApiError>;
+
+    fn update_add_on(&self, current_service_id: i32, id: i32, add_on: GenerateServiceAddOn, actor: &Actor) -> Result<ServiceAddOn, ApiError>;
+
+    fn delete_add_on(&self, current_service_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+
+    /// Resource types a service that is not deleted needs, in name order.
+    fn find_resource_types(&self, id: i32) -> QueryResult<ServiceResourceTypes>;
+
+    fn update_resource_types(&self, id: i32, resource_types: ServiceResourceTypes, actor: &Actor) -> Result<ServiceResourceTypes, ApiError>;
+
+    /// Price and end time of a variant with the chosen add-ons, which must
+    /// be active and belong to the same service.
+    fn quote(&self, id: i32, quote_query: QuoteQuery) -> Result<ServiceQuote, ApiError>;
+
+    /// Every category in display order.
+    fn find_categories(&self) -> QueryResult<Vec<ServiceCategory>>;
+
+    fn find_category(&self, id: i32) -> QueryResult<ServiceCategory>;
+
+    /// Category names are unique ignoring case and surrounding spaces.
+    fn create_category(&self, category_create: ServiceCategoryCreate, actor: &Actor) -> Result<ServiceCategory, ApiError>;
+
+    fn update_category(&self, id: i32, category_update: ServiceCategoryCreate, actor: &Actor) -> Result<ServiceCategory, ApiError>;
+
+    /// Services in the category are left uncategorized.
+    fn delete_category(&self, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+}
+
+pub struct PgServiceRepository<'a> {
+    conn: &'a PgConnection
+}
+
+impl<'a> PgServiceRepository<'a> {
+    pub fn new(conn: &'a PgConnection) -> PgServiceRepository<'a> {
+        PgServiceRepository { conn }
+    }
+}
+
+impl<'a> ServiceRepository for PgServiceRepository<'a> {
+    fn find_all(&self, service_query: ServiceQuery, page: &Pagination) -> Result<(Vec<FullStaffService>, i64), ApiError> {
+        let span = debug_span!("Service::find_all");
+        let _entered = span.enter();
+
+        let (all_services, total) = service_query.find_page(page, self.conn)?;
+        let all_blocked_time = BlockExtraTime::belonging_to(&all_services)
+            .load::<BlockExtraTime>(self.conn)?
+            .grouped_by(&all_services);
+        let all_services_variants = ServiceVariant::belonging_to(&all_services)
+            .load::<ServiceVariant>(self.conn)?
+            .grouped_by(&all_services);
+        let all_add_ons = ServiceAddOn::belonging_to(&all_services)
+            .filter(service_add_on::deleted_at.is_null())
+            .order(service_add_on::service_add_on_id.asc())
+            .load::<ServiceAddOn>(self.conn)?
+            .grouped_by(&all_services);
+
+        let staff_repository = PgStaffRepository::new(self.conn);
+        let mut services_final_list: Vec<FullStaffService> = vec![];
+        
+        let services_with_details = all_services
+            .into_iter()
+            .zip(all_blocked_time)
+            .zip(all_services_variants)
+            .zip(all_add_ons);
+
+        for (((current_service, blocked_time), variants), add_ons) in services_with_details {
+            let mut all_staff_in_service: Vec<BasicStaffInfo> = vec![]; 
+
+            let staff_service_list = staff_repository.find_staff_with_service(current_service.service_id)?;
+
+            for service_item in staff_service_list {
+                let temp_staff_info = staff_repository.find_basic(service_item.staff_id)?;
+                all_staff_in_service.push(temp_staff_info);
+            }
+
+            let current_service = FullStaffService {
+                service: current_service,
+                blocked_time: blocked_time[0],
+                variants,
+                add_ons,
+                staff: all_staff_in_service
+            };
+
+            services_final_list.push(current_service);
+        }
+
+        Ok((services_final_list, total))
+    }
+
+    fn find_all_grouped(&self, service_query: ServiceQuery, page: &Pagination) -> Result<(Vec<ServiceCategoryGroup>, i64), ApiError> {
+        let span = debug_span!("Service::find_all_grouped");
+        let _entered = span.enter();
+
+        let (services_list, total) = self.find_all(ServiceQuery { group_by: Some("category".to_string()), ..service_query }, page)?;
+
+        let category_ids: Vec<i32> = services_list.iter().filter_map(|full| full.service.service_category_id).collect();
+        let categories = service_category::table
+            .filter(service_category::service_category_id.eq_any(category_ids))
+            .load::<ServiceCategory>(self.conn)?;
+
+        // the page is already in category order, so each group is one run
+        let mut groups: Vec<ServiceCategoryGroup> = vec![];
+
+        for full in services_list {
+            let current_category_id = full.service.service_category_id;
+
+            match groups.last_mut() {
+                Some(group) if group.category.as_ref().map(|category| category.service_category_id) == current_category_id => {
+                    group.services.push(full);
+                },
+                _ => groups.push(ServiceCategoryGroup {
+                    category: categories.iter().find(|category| Some(category.service_category_id) == current_category_id).cloned(),
+                    services: vec![full]
+                }),
+            }
+        }
+
+        Ok((groups, total))
+    }
+
+    fn find(&self, id: i32) -> QueryResult<FullStaffService> {
+        let span = debug_span!("Service::find");
+        let _entered = span.enter();
+
+        let service_entity: Service = service.filter(service::service_id.eq(id)).first::<Service>(self.conn)?;
+        let block_extra: BlockExtraTime = block_extra_time.filter(block_extra_time::service_id.eq(id)).first::<BlockExtraTime>(self.conn)?;
+        let service_variants: Vec<ServiceVariant> = service_variant.filter(service_variant::service_id.eq(id)).load::<ServiceVariant>(self.conn)?;
+        let add_ons: Vec<ServiceAddOn> = ServiceAddOn::belonging_to(&service_entity)
+            .filter(service_add_on::deleted_at.is_null())
+            .order(service_add_on::service_add_on_id.asc())
+            .load::<ServiceAddOn>(self.conn)?;
+        let staff_repository = PgStaffRepository::new(self.conn);
+        let mut all_staff_in_service: Vec<BasicStaffInfo> = vec![]; 
+
+        let staff_service_list = staff_repository.find_staff_with_service(service_entity.service_id)?;
+
+        for service_item in staff_service_list {
+            let temp_staff_info = staff_repository.find_basic(service_item.staff_id)?;
+            all_staff_in_service.push(temp_staff_info);
+        }
+
+        let full_service = FullStaffService {
+            service: service_entity,
+            blocked_time: block_extra,
+            variants: service_variants,
+            add_ons,
+            staff: all_staff_in_service
+        };
+
+        Ok(full_service)
+    }
+
+    fn create(&self, service_data: GenerateService, actor: &Actor) -> QueryResult<Service> {
+        let span = debug_span!("Service::create");
+        let _entered = span.enter();
+
+        let service_created = self.conn.transaction::<_, diesel::result::Error, _>(|| {
+            let service_create = ServiceCreate {
+                name: service_data.name,
+                description: service_data.description,
+                is_active: service_data.is_active,
+                service_category_id: service_data.service_category_id,
+                capacity: service_data.capacity
+            };
+
+            let service_created: Service = diesel::insert_into(service::table)
+                .values(service_create)
+                .get_result(self.conn)?;
+
+            // set extra blocked time
+            let block_extra_time_create = BlockExtraTimeCreate {
+                service_id: service_created.service_id,
+                before_time: service_data.before_time,
+                after_time: service_data.after_time
+            };
+
+            let block_created: BlockExtraTime = diesel::insert_into(block_extra_time::table)
+                .values(block_extra_time_create)
+                .get_result(self.conn)?;
+
+            // Create service variants
+            let mut variants_created: Vec<ServiceVariant> = vec![];
+
+            for variant in service_data.variants {
+                let service_variant_create = ServiceVariantCreate {
+                    service_id: service_created.service_id,
+                    price: variant.price,
+                    duration: variant.duration,
+                    capacity: variant.capacity
+                };
+
+                let variant_created: ServiceVariant = diesel::insert_into(service_variant::table)
+                    .values(service_variant_create)
+                    .get_result(self.conn)?;
+
+                variants_created.push(variant_created);
+            }
+
+            audit::created(self.conn, actor, "service", service_created.service_id, &json!({
+                "service": service_created,
+                "blocked_time": block_created,
+                "variants": variants_created
+            }))?;
+
+            Ok(service_created)
+        })?;
+
+        webhook::emit(self.conn, webhook::SERVICE_CREATED, json!(service_created));
+
+        Ok(service_created)
+    }
+
+    fn update(&self, id: i32, service_update: ServiceCreate, actor: &Actor) -> Result<Service, ApiError> {
+        let span = debug_span!("Service::update");
+        let _entered = span.enter();
+
+        let service_updated = self.conn.transaction::<_, ApiError, _>(|| {
+            let service_before = service.filter(service::service_id.eq(id)).first::<Service>(self.conn)?;
+            let service_updated: Service = diesel::update(service::table)
+                .filter(service::service_id.eq(id))
+                .set(service_update)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "service", id, &service_before, &service_updated)?;
+
+            Ok(service_updated)
+        })?;
+
+        webhook::emit(self.conn, webhook::SERVICE_UPDATED, json!(service_updated));
+
+        Ok(service_updated)
+    }
+
+    fn find_variants(&self, id: i32) -> QueryResult<Vec<ServiceVariant>> {
+        let span = debug_span!("Service::find_variants");
+        let _entered = span.enter();
+
+        let current_service = service
+            .filter(service::service_id.eq(id))
+            .filter(service::deleted_at.is_null())
+            .first::<Service>(self.conn)?;
+
+        ServiceVariant::belonging_to(&current_service)
+            .order(service_variant::service_variant_id.asc())
+            .load::<ServiceVariant>(self.conn)
+    }
+
+    fn update_variant(&self, current_service_id: i32, id: i32, variant_update: GenerateServiceVariant, actor: &Actor) -> Result<ServiceVariant, ApiError> {
+        let span = debug_span!("Service::update_variant");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let variant_before = service_variant
+                .filter(service_variant::service_variant_id.eq(id))
+                .filter(service_variant::service_id.eq(current_service_id))
+                .first::<ServiceVariant>(self.conn)?;
+            let variant_updated: ServiceVariant = diesel::update(service_variant::table)
+                .filter(service_variant::service_variant_id.eq(id))
+                .set(ServiceVariantCreate {
+                    service_id: current_service_id,
+                    price: variant_update.price,
+                    duration: variant_update.duration,
+                    capacity: variant_update.capacity
+                })
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "service_variant", id, &variant_before, &variant_updated)?;
+
+            Ok(variant_updated)
+        })
+    }
+
+    fn update_time_block(&self, id: i32, block_update: BlockExtraTimeCreate, actor: &Actor) -> Result<BlockExtraTime, ApiError> {
+        let span = debug_span!("Service::update_time_block");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let block_before = block_extra_time
+                .filter(block_extra_time::service_id.eq(id))
+                .first::<BlockExtraTime>(self.conn)?;
+            let block_updated: BlockExtraTime = diesel::update(block_extra_time::table)
+                .filter(block_extra_time::service_id.eq(id))
+                .set(block_update)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "block_extra_time", block_updated.block_extra_time_id, &block_before, &block_updated)?;
+
+            Ok(block_updated)
+        })
+    }
+
+    fn update_all(&self, id: i32, service_update: UpdateServiceAll, actor: &Actor) -> Result<Service, ApiError> {
+        let span = debug_span!("Service::update_all");
+        let _entered = span.enter();
+
+        let service_updated = self.conn.transaction::<_, ApiError, _>(|| {
+            let service_update_info = ServiceCreate {
+                name: service_update.name,
+                description: service_update.description,
+                is_active: service_update.is_active,
+                service_category_id: service_update.service_category_id,
+                capacity: service_update.capacity
+            };
+
+            let service_before = service.filter(service::service_id.eq(id)).first::<Service>(self.conn)?;
+            let service_updated: Service = diesel::update(service::table)
+                .filter(service::service_id.eq(id))
+                .set(service_update_info)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "service", id, &service_before, &service_updated)?;
+
+            for variant in service_update.variants {
+                let service_variant_update = ServiceVariantCreate {
+                    service_id: id,
+                    price: variant.price,
+                    duration: variant.duration,
+                    capacity: variant.capacity
+                };
+
+                let variants_before = service_variant
+                    .filter(service_variant::service_variant_id.eq(variant.service_variant_id))
+                    .load::<ServiceVariant>(self.conn)?;
+                let variants_updated = diesel::update(service_variant::table)
+                    .filter(service_variant::service_variant_id.eq(variant.service_variant_id))
+                    .set(service_variant_update)
+                    .get_results::<ServiceVariant>(self.conn)?;
+
+                for (variant_before, variant_updated) in variants_before.iter().zip(variants_updated.iter()) {
+                    audit::updated(self.conn, actor, "service_variant", variant_updated.service_variant_id, variant_before, variant_updated)?;
+                }
+            }
+
+            let blocked_time_update = BlockExtraTimeCreate {
+                service_id: id,
+                before_time: service_update.before_time,
+                after_time: service_update.after_time
+            };
+
+            let blocks_before = block_extra_time
+                .filter(block_extra_time::service_id.eq(id))
+                .load::<BlockExtraTime>(self.conn)?;
+            let blocks_updated = diesel::update(block_extra_time::table)
+                .filter(block_extra_time::service_id.eq(id))
+                .set(blocked_time_update)
+                .get_results::<BlockExtraTime>(self.conn)?;
+
+            for (block_before, block_updated) in blocks_before.iter().zip(blocks_updated.iter()) {
+                audit::updated(self.conn, actor, "block_extra_time", block_updated.block_extra_time_id, block_before, block_updated)?;
+            }
+
+            Ok(service_updated)
+        })?;
+
+        webhook::emit(self.conn, webhook::SERVICE_UPDATED, json!(service_updated));
+
+        Ok(service_updated)
+    }
+
+    fn delete(&self, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Service::delete");
+        let _entered = span.enter();
+
+        let res = self.conn.transaction::<_, ApiError, _>(|| {
+            let service_before = service
+                .filter(service::service_id.eq(id))
+                .filter(service::deleted_at.is_null())
+                .first::<Service>(self.conn)
+                .optional()?;
+
+            // soft delete so past appointments keep their variants and padding
+            let res = diesel::update(service::table)
+                .filter(service::service_id.eq(id))
+                .filter(service::deleted_at.is_null())
+                .set(service::deleted_at.eq(Utc::now().naive_utc()))
+                .execute(self.conn)?;
+
+            if let Some(service_before) = service_before {
+                audit::deleted(self.conn, actor, "service", id, &service_before)?;
+            }
+
+            Ok(res)
+        })?;
+
+        if res > 0 {
+            webhook::emit(self.conn, webhook::SERVICE_DELETED, json!({ "service_id": id }));
+        }
+
+        Ok(res)
+    }
+
+    fn restore(&self, id: i32, actor: &Actor) -> Result<Service, ApiError> {
+        let span = debug_span!("Service::restore");
+        let _entered = span.enter();
+
+        let service_restored = self.conn.transaction::<_, ApiError, _>(|| {
+            let service_entity = service.filter(service::service_id.eq(id)).for_update().first::<Service>(self.conn)?;
+
+            if service_entity.deleted_at.is_none() {
+                return Err(ApiError::coded(409, "service.not_deleted", "Service is not deleted".to_string()));
+            }
+
+            let service_restored: Service = diesel::update(service::table)
+                .filter(service::service_id.eq(id))
+                .set(service::deleted_at.eq(None::<NaiveDateTime>))
+                .get_result(self.conn)?;
+
+            audit::restored(self.conn, actor, "service", id, &service_restored)?;
+
+            Ok(service_restored)
+        })?;
+
+        webhook::emit(self.conn, webhook::SERVICE_RESTORED, json!(service_restored));
+
+        Ok(service_restored)
+    }
+
+    fn import(&self, mut import: CatalogImport, dry_run: bool, actor: &Actor) -> Result<ImportReport, ApiError> {
+        let span = debug_span!("Service::import");
+        let _entered = span.enter();
+
+        import.validate();
+
+        let mut names: Vec<String> = service
+            .filter(service::deleted_at.is_null())
+            .select(service::name)
+            .load::<String>(self.conn)?;
+
+        for entry in &import.entries {
+            if names.contains(&entry.service.name) {
+                import.errors.add(&format!("{}.name", entry.row), "a service with this name already exists");
+            }
+
+            names.push(entry.service.name.clone());
+        }
+
+        let mut report = ImportReport {
+            dry_run,
+            services: import.entries.len(),
+            variants: import.entries.iter().map(|entry| entry.service.variants.len()).sum(),
+            created: vec![],
+            errors: Default::default()
+        };
+
+        if dry_run {
+            report.errors = import.errors.into_map();
+            return Ok(report);
+        }
+
+        let CatalogImport { entries, errors } = import;
+        errors.into_result()?;
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            for entry in entries {
+                let category_id = match entry.service.category_name() {
+                    Some(category_name) => Some(self.category_named(category_name, actor)?.service_category_id),
+                    None => None,
+                };
+
+                report.created.push(self.create(entry.service.into_service(category_id), actor)?.service_id);
+            }
+
+            Ok(())
+        })?;
+
+        Ok(report)
+    }
+
+    fn export(&self) -> QueryResult<Vec<CatalogService>> {
+        let span = debug_span!("Service::export");
+        let _entered = span.enter();
+
+        let all_services = service
+            .filter(service::deleted_at.is_null())
+            .order(service::service_id.asc())
+            .load::<Service>(self.conn)?;
+        let all_blocked_time = BlockExtraTime::belonging_to(&all_services)
+            .load::<BlockExtraTime>(self.conn)?
+            .grouped_by(&all_services);
+        let all_services_variants = ServiceVariant::belonging_to(&all_services)
+            .order(service_variant::service_variant_id.asc())
+            .load::<ServiceVariant>(self.conn)?
+            .grouped_by(&all_services);
+        let categories = self.find_categories()?;
+
+        let services_with_details = all_services
+            .into_iter()
+            .zip(all_blocked_time)
+            .zip(all_services_variants);
+
+        let exported = services_with_details
+            .map(|((current_service, blocked_time), variants)| CatalogService {
+                category: categories.iter()
+                    .find(|category| Some(category.service_category_id) == current_service.service_category_id)
+                    .map(|category| category.name.clone()),
+                name: current_service.name,
+                description: current_service.description,
+                is_active: current_service.is_active,
+                capacity: current_service.capacity,
+                before_time: blocked_time.first().and_then(|block| block.before_time),
+                after_time: blocked_time.first().and_then(|block| block.after_time),
+                variants: variants
+                    .into_iter()
+                    .map(|variant| GenerateServiceVariant { price: variant.price, duration: variant.duration, capacity: variant.capacity })
+                    .collect()
+            })
+            .collect();
+
+        Ok(exported)
+    }
+
+    fn find_add_ons(&self, id: i32) -> QueryResult<Vec<ServiceAddOn>> {
+        let span = debug_span!("Service::find_add_ons");
+        let _entered = span.enter();
+
+        let current_service = service
+            .filter(service::service_id.eq(id))
+            .filter(service::deleted_at.is_null())
+            .first::<Service>(self.conn)?;
+
+        ServiceAddOn::belonging_to(&current_service)
+            .filter(service_add_on::deleted_at.is_null())
+            .order(service_add_on::service_add_on_id.asc())
+            .load::<ServiceAddOn>(self.conn)
+    }
+
+    fn create_add_on(&self, id: i32, add_on: GenerateServiceAddOn, actor: &Actor) -> Result<ServiceAddOn, ApiError> {
+        let span = debug_span!("Service::create_add_on");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            service
+                .filter(service::service_id.eq(id))
+                .filter(service::deleted_at.is_null())
+                .first::<Service>(self.conn)?;
+
+            let add_on_created: ServiceAddOn = diesel::insert_into(service_add_on::table)
+                .values(ServiceAddOnCreate {
+                    service_id: id,
+                    name: add_on.name,
+                    price: add_on.price,
+                    extra_duration: add_on.extra_duration,
+                    is_active: add_on.is_active
+                })
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "service_add_on", add_on_created.service_add_on_id, &add_on_created)?;
+
+            Ok(add_on_created)
+        })
+    }
+
+    fn update_add_on(&self, current_service_id: i32, id: i32, add_on: GenerateServiceAddOn, actor: &Actor) -> Result<ServiceAddOn, ApiError> {
+        let span = debug_span!("Service::update_add_on");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let add_on_before = service_add_on::table
+                .filter(service_add_on::service_add_on_id.eq(id))
+                .filter(service_add_on::service_id.eq(current_service_id))
+                .filter(service_add_on::deleted_at.is_null())
+                .first::<ServiceAddOn>(self.conn)?;
+            let add_on_updated: ServiceAddOn = diesel::update(service_add_on::table)
+                .filter(service_add_on::service_add_on_id.eq(id))
+                .set(ServiceAddOnCreate {
+                    service_id: current_service_id,
+                    name: add_on.name,
+                    price: add_on.price,
+                    extra_duration: add_on.extra_duration,
+                    is_active: add_on.is_active
+                })
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "service_add_on", id, &add_on_before, &add_on_updated)?;
+
+            Ok(add_on_updated)
+        })
+    }
+
+    fn delete_add_on(&self, current_service_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Service::delete_add_on");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let add_on_before = service_add_on::table
+                .filter(service_add_on::service_add_on_id.eq(id))
+                .filter(service_add_on::service_id.eq(current_service_id))
+                .filter(service_add_on::deleted_at.is_null())
+                .first::<ServiceAddOn>(self.conn)
+                .optional()?;
+
+            // soft delete so past appointments keep their price and length
+            let res = diesel::update(service_add_on::table)
+                .filter(service_add_on::service_add_on_id.eq(id))
+                .filter(service_add_on::service_id.eq(current_service_id))
+                .filter(service_add_on::deleted_at.is_null())
+                .set(service_add_on::deleted_at.eq(Utc::now().naive_utc()))
+                .execute(self.conn)?;
+
+            if let Some(add_on_before) = add_on_before {
+                audit::deleted(self.conn, actor, "service_add_on", id, &add_on_before)?;
+            }
+
+            Ok(res)
+        })
+    }
+
+    fn find_resource_types(&self, id: i32) -> QueryResult<ServiceResourceTypes> {
+        let span = debug_span!("Service::find_resource_types");
+        let _entered = span.enter();
+
+        let current_service = service
+            .filter(service::service_id.eq(id))
+            .filter(service::deleted_at.is_null())
+            .first::<Service>(self.conn)?;
+
+        let resource_types = ServiceResource::belonging_to(&current_service)
+            .order(service_resource::resource_type.asc())
+            .select(service_resource::resource_type)
+            .load::<String>(self.conn)?;
+
+        Ok(ServiceResourceTypes { resource_types })
+    }
+
+    fn update_resource_types(&self, id: i32, resource_types: ServiceResourceTypes, actor: &Actor) -> Result<ServiceResourceTypes, ApiError> {
+        let span = debug_span!("Service::update_resource_types");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let resource_types_before = self.find_resource_types(id)?;
+
+            diesel::delete(service_resource::table)
+                .filter(service_resource::service_id.eq(id))
+                .execute(self.conn)?;
+
+            let rows: Vec<ServiceResourceCreate> = resource_types.resource_types.into_iter()
+                .map(|resource_type| ServiceResourceCreate { service_id: id, resource_type })
+                .collect();
+
+            diesel::insert_into(service_resource::table)
+                .values(&rows)
+                .execute(self.conn)?;
+
+            let resource_types_updated = self.find_resource_types(id)?;
+
+            audit::updated(self.conn, actor, "service_resource", id, &resource_types_before, &resource_types_updated)?;
+
+            Ok(resource_types_updated)
+        })
+    }
+
+    fn quote(&self, id: i32, quote_query: QuoteQuery) -> Result<ServiceQuote, ApiError> {
+        let span = debug_span!("Service::quote");
+        let _entered = span.enter();
+
+        let add_on_ids = quote_query.add_on_ids()?;
+        let variant = self.find_variants(id)?
+            .into_iter()
+            .find(|variant| variant.service_variant_id == quote_query.service_variant_id)
+            .ok_or_else(|| ApiError::coded(404, "service.variant_not_found", format!("Service {} has no variant {}", id, quote_query.service_variant_id)))?;
+        let blocked_time = block_extra_time
+            .filter(block_extra_time::service_id.eq(id))
+            .first::<BlockExtraTime>(self.conn)?;
+
+        let mut errors = FieldErrors::default();
+        let add_ons = ServiceAddOn::select(&self.find_add_ons(id)?, &add_on_ids, "add_on_ids", &mut errors);
+        errors.into_result()?;
+
+        Ok(ServiceQuote::new(&variant, &blocked_time, add_ons, quote_query.start_time))
+    }
+
+    fn find_categories(&self) -> QueryResult<Vec<ServiceCategory>> {
+        let span = debug_span!("Service::find_categories");
+        let _entered = span.enter();
+
+        service_category::table
+            .order((service_category::display_order.asc(), service_category::service_category_id.asc()))
+            .load::<ServiceCategory>(self.conn)
+    }
+
+    fn find_category(&self, id: i32) -> QueryResult<ServiceCategory> {
+        let span = debug_span!("Service::find_category");
+        let _entered = span.enter();
+
+        service_category::table
+            .filter(service_category::service_category_id.eq(id))
+            .first::<ServiceCategory>(self.conn)
+    }
+
+    fn create_category(&self, category_create: ServiceCategoryCreate, actor: &Actor) -> Result<ServiceCategory, ApiError> {
+        let span = debug_span!("Service::create_category");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let category_create = ServiceCategoryCreate { name: category_create.name.trim().to_string(), ..category_create };
+            self.check_category_name(&category_create.name, None)?;
+
+            let category_created: ServiceCategory = diesel::insert_into(service_category::table)
+                .values(category_create)
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "service_category", category_created.service_category_id, &category_created)?;
+
+            Ok(category_created)
+        })
+    }
+
+    fn update_category(&self, id: i32, category_update: ServiceCategoryCreate, actor: &Actor) -> Result<ServiceCategory, ApiError> {
+        let span = debug_span!("Service::update_category");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let category_update = ServiceCategoryCreate { name: category_update.name.trim().to_string(), ..category_update };
+            self.check_category_name(&category_update.name, Some(id))?;
+
+            let category_before = self.find_category(id)?;
+            let category_updated: ServiceCategory = diesel::update(service_category::table)
+                .filter(service_category::service_category_id.eq(id))
+                .set(category_update)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "service_category", id, &category_before, &category_updated)?;
+
+            Ok(category_updated)
+        })
+    }
+
+    fn delete_category(&self, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Service::delete_category");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let category_before = self.find_category(id).optional()?;
+            let res = diesel::delete(service_category::table.filter(service_category::service_category_id.eq(id)))
+                .execute(self.conn)?;
+
+            if let Some(category_before) = category_before {
+                audit::deleted(self.conn, actor, "service_category", id, &category_before)?;
+            }
+
+            Ok(res)
+        })
+    }
+}
+
+impl<'a> PgServiceRepository<'a> {
+    fn check_category_name(&self, category_name: &str, except_id: Option<i32>) -> Result<(), ApiError> {
+        let taken = service_category::table
+            .filter(lower(service_category::name).eq(lower(category_name)))
+            .filter(service_category::service_category_id.ne(except_id.unwrap_or(0)))
+            .select(service_category::service_category_id)
+            .first::<i32>(self.conn)
+            .optional()?;
+
+        match taken {
+            Some(_) => Err(ApiError::coded(409, "service_category.name_taken", "name is already taken".to_string())),
+            None => Ok(()),
+        }
+    }
+
+    /// The category called `category_name` ignoring case, created at the end
+    /// of the display order if there is none.
+    fn category_named(&self, category_name: &str, actor: &Actor) -> Result<ServiceCategory, ApiError> {
+        let existing = service_category::table
+            .filter(lower(service_category::name).eq(lower(category_name)))
+            .first::<ServiceCategory>(self.conn)
+            .optional()?;
+
+        if let Some(existing) = existing {
+            return Ok(existing);
+        }
+
+        let last_order = service_category::table
+            .select(diesel::dsl::max(service_category::display_order))
+            .first::<Option<i32>>(self.conn)?;
+
+        self.create_category(ServiceCategoryCreate {
+            name: category_name.to_string(),
+            description: None,
+            display_order: last_order.unwrap_or(0) + 1,
+            is_active: 1
+        }, actor)
+    }
+}
\ No newline at end of file
@@ -1,41 +1,142 @@
 use crate::api_error::ApiError;
-use crate::service::{Service, ServiceId, ServiceCreate, GenerateService, UpdateServiceAll};
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::service::{Service, ServiceId, ServiceQuery, ServiceCreate, GenerateService, UpdateServiceAll};
+use crate::validation::Valid;
 use actix_web::{get, post, put, delete, web, HttpResponse};
 use serde_json::json;
 
+#[utoipa::path(
+    get,
+    path = "/service",
+    tag = "service",
+    operation_id = "legacy_service_find_all",
+    params(
+        ServiceQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "`ServiceCategoryGroup`s in category display order, or services with their variants, blocked time and staff with `group_by=none`", body = [ServiceCategoryGroup], headers(
+            ("X-Total-Count" = i64, description = "Total matching services"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
 #[get("/service")]
-async fn find_all() -> Result<HttpResponse, ApiError> {
-    let service = Service::find_all()?;
-    Ok(HttpResponse::Ok().json(service))
+async fn find_all(query: web::Query<ServiceQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+
+    if query.grouped(true)? {
+        let (groups, total) = Service::find_all_grouped(query, &page)?;
+        return Ok(page.respond(total, groups));
+    }
+
+    let (service, total) = Service::find_all(query, &page)?;
+    Ok(page.respond(total, service))
 }
 
+#[utoipa::path(
+    get,
+    path = "/service/{service_id}",
+    tag = "service",
+    operation_id = "legacy_service_find",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Service with its variants, blocked time and staff", body = FullStaffService)
+    )
+)]
 #[get("/service/{service_id}")]
 async fn find(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
     let service = Service::find(id.service_id)?;
     Ok(HttpResponse::Ok().json(service))
 }
 
+#[utoipa::path(
+    post,
+    path = "/service",
+    tag = "service",
+    operation_id = "legacy_service_create",
+    request_body = GenerateService,
+    responses(
+        (status = 200, description = "Created service", body = Service)
+    ),
+    security(("staff_id" = []))
+)]
 #[post("/service")]
-async fn create(service: web::Json<GenerateService>) -> Result<HttpResponse, ApiError> {
-    let service = Service::create(service.into_inner())?;
+async fn create(service: Valid<GenerateService>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::create(service.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(service))
 }
 
+#[utoipa::path(
+    put,
+    path = "/service/{service_id}",
+    tag = "service",
+    operation_id = "legacy_service_update",
+    params(("service_id" = i32, Path, description = "Service id")),
+    request_body = ServiceCreate,
+    responses(
+        (status = 200, description = "Updated service", body = Service)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/service/{service_id}")]
-async fn update(id: web::Path<ServiceId>, service: web::Json<ServiceCreate>) -> Result<HttpResponse, ApiError> {
-    let service = Service::update(id.service_id, service.into_inner())?;
+async fn update(id: web::Path<ServiceId>, service: Valid<ServiceCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::update(id.service_id, service.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(service))
 }
 
+#[utoipa::path(
+    put,
+    path = "/full_service/{service_id}",
+    tag = "service",
+    operation_id = "legacy_service_update_all",
+    params(("service_id" = i32, Path, description = "Service id")),
+    request_body = UpdateServiceAll,
+    responses(
+        (status = 200, description = "Updated service", body = Service)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/full_service/{service_id}")]
-async fn update_all(id: web::Path<ServiceId>, service: web::Json<UpdateServiceAll>) -> Result<HttpResponse, ApiError> {
-    let service = Service::update_all(id.service_id, service.into_inner())?;
+async fn update_all(id: web::Path<ServiceId>, service: Valid<UpdateServiceAll>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::update_all(id.service_id, service.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(service))
+}
+
+#[utoipa::path(
+    post,
+    path = "/service/{service_id}/restore",
+    tag = "service",
+    operation_id = "legacy_service_restore",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Restored service", body = Service),
+        (status = 404, description = "No such service", body = ErrorBody),
+        (status = 409, description = "The service is not deleted", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/service/{service_id}/restore")]
+async fn restore(id: web::Path<ServiceId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::restore(id.service_id, &actor)?;
     Ok(HttpResponse::Ok().json(service))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/service/{service_id}",
+    tag = "service",
+    operation_id = "legacy_service_delete",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Number of services deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
 #[delete("/service/{service_id}")]
-async fn delete(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
-    let service_deleted = Service::delete(id.service_id)?;
+async fn delete(id: web::Path<ServiceId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service_deleted = Service::delete(id.service_id, &actor)?;
     Ok(HttpResponse::Ok().json(json!({ "deleted": service_deleted })))
 }
 
@@ -45,5 +146,6 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(create);
     cfg.service(update);
     cfg.service(update_all);
+    cfg.service(restore);
     cfg.service(delete);
 }
\ No newline at end of file
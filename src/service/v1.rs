@@ -0,0 +1,505 @@
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::service::{Service, ServiceId, ServiceQuery, ServiceCreate, ServiceVariantPath, GenerateService, GenerateServiceVariant, UpdateServiceAll, ServiceCategoryCreate, ServiceCategoryId, GenerateServiceAddOn, ServiceAddOnPath, QuoteQuery, ServiceResourceTypes};
+use crate::service::catalog::{self, CatalogImport, ExportQuery, ImportQuery};
+use crate::staff::Staff;
+use crate::validation::Valid;
+use actix_web::http::header;
+use actix_web::{get, patch, post, put, delete, web, HttpMessage, HttpRequest, HttpResponse};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services",
+    tag = "service",
+    operation_id = "service_find_all",
+    params(
+        ServiceQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Services with their variants, blocked time and staff, or `ServiceCategoryGroup`s with `group_by=category`", body = [FullStaffService], headers(
+            ("X-Total-Count" = i64, description = "Total matching services"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/services")]
+async fn find_all(query: web::Query<ServiceQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let query = query.into_inner();
+
+    if query.grouped(false)? {
+        let (groups, total) = Service::find_all_grouped(query, &page)?;
+        return Ok(page.respond(total, groups));
+    }
+
+    let (service, total) = Service::find_all(query, &page)?;
+    Ok(page.respond(total, service))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/services",
+    tag = "service",
+    operation_id = "service_create",
+    request_body = GenerateService,
+    responses(
+        (status = 201, description = "Created service", body = Service)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/services")]
+async fn create(service: Valid<GenerateService>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::create(service.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(service))
+}
+
+/// Creates services in bulk from a CSV (`text/csv`) or JSON
+/// (`application/json`) body. CSV files have one line per variant with the
+/// columns `name,description,category,is_active,before_time,after_time,price,duration`;
+/// JSON bodies are an array of `CatalogService`. Categories are matched by
+/// name, ignoring case, and created when missing.
+#[utoipa::path(
+    post,
+    path = "/api/v1/services/import",
+    tag = "service",
+    operation_id = "service_import",
+    params(ImportQuery),
+    request_body(content = [CatalogService], description = "JSON array of services, or the same as CSV with one line per variant", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Dry run report, including any row errors", body = ImportReport),
+        (status = 201, description = "Every service was created", body = ImportReport),
+        (status = 415, description = "Body is neither CSV nor JSON", body = ErrorBody),
+        (status = 422, description = "Rows failed validation and nothing was created", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/services/import")]
+async fn import(req: HttpRequest, body: web::Bytes, query: web::Query<ImportQuery>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let import = match req.content_type() {
+        "text/csv" => CatalogImport::from_csv(&body)?,
+        "application/json" => CatalogImport::from_json(&body)?,
+        _ => return Err(ApiError::coded(415, "request.content_type", "Expected a text/csv or application/json body".to_string())),
+    };
+
+    let dry_run = query.dry_run.unwrap_or(false);
+    let report = Service::import(import, dry_run, &actor)?;
+
+    match dry_run {
+        true => Ok(HttpResponse::Ok().json(report)),
+        false => Ok(HttpResponse::Created().json(report)),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/export",
+    tag = "service",
+    operation_id = "service_export",
+    params(ExportQuery),
+    responses(
+        (status = 200, description = "Every service that is not deleted, in the format `import` accepts", body = [CatalogService])
+    )
+)]
+#[get("/services/export")]
+async fn export(query: web::Query<ExportQuery>) -> Result<HttpResponse, ApiError> {
+    let services = Service::export()?;
+
+    match query.format.as_deref().unwrap_or("json") {
+        "json" => Ok(HttpResponse::Ok().json(services)),
+        "csv" => Ok(HttpResponse::Ok()
+            .content_type("text/csv")
+            .header(header::CONTENT_DISPOSITION, "attachment; filename=\"services.csv\"")
+            .body(catalog::to_csv(services)?)),
+        other => Err(ApiError::coded(400, "service.unknown_format", format!("Unknown export format: {}", other))),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}",
+    tag = "service",
+    operation_id = "service_find",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Service with its variants, blocked time and staff", body = FullStaffService)
+    )
+)]
+#[get("/services/{service_id}")]
+async fn find(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
+    let service = Service::find(id.service_id)?;
+    Ok(HttpResponse::Ok().json(service))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/services/{service_id}",
+    tag = "service",
+    operation_id = "service_update",
+    params(("service_id" = i32, Path, description = "Service id")),
+    request_body = UpdateServiceAll,
+    responses(
+        (status = 200, description = "Updated service", body = Service)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/services/{service_id}")]
+async fn update(id: web::Path<ServiceId>, service: Valid<UpdateServiceAll>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::update_all(id.service_id, service.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(service))
+}
+
+#[utoipa::path(
+    patch,
+    path = "/api/v1/services/{service_id}",
+    tag = "service",
+    operation_id = "service_update_details",
+    params(("service_id" = i32, Path, description = "Service id")),
+    request_body = ServiceCreate,
+    responses(
+        (status = 200, description = "Updated service", body = Service)
+    ),
+    security(("staff_id" = []))
+)]
+#[patch("/services/{service_id}")]
+async fn update_details(id: web::Path<ServiceId>, service: Valid<ServiceCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::update(id.service_id, service.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(service))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/services/{service_id}",
+    tag = "service",
+    operation_id = "service_delete",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Number of services deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/services/{service_id}")]
+async fn delete(id: web::Path<ServiceId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service_deleted = Service::delete(id.service_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": service_deleted })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/services/{service_id}/restore",
+    tag = "service",
+    operation_id = "service_restore",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Restored service", body = Service),
+        (status = 404, description = "No such service", body = ErrorBody),
+        (status = 409, description = "The service is not deleted", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/services/{service_id}/restore")]
+async fn restore(id: web::Path<ServiceId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let service = Service::restore(id.service_id, &actor)?;
+    Ok(HttpResponse::Ok().json(service))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}/variants",
+    tag = "service",
+    operation_id = "service_find_variants",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Variants of the service", body = [ServiceVariant])
+    )
+)]
+#[get("/services/{service_id}/variants")]
+async fn find_variants(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
+    let variants = Service::find_variants(id.service_id)?;
+    Ok(HttpResponse::Ok().json(variants))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/services/{service_id}/variants/{service_variant_id}",
+    tag = "service",
+    operation_id = "service_update_variant",
+    params(
+        ("service_id" = i32, Path, description = "Service id"),
+        ("service_variant_id" = i32, Path, description = "Service variant id")
+    ),
+    request_body = GenerateServiceVariant,
+    responses(
+        (status = 200, description = "Updated variant", body = ServiceVariant)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/services/{service_id}/variants/{service_variant_id}")]
+async fn update_variant(path: web::Path<ServiceVariantPath>, variant: Valid<GenerateServiceVariant>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let variant = Service::update_variant(path.service_id, path.service_variant_id, variant.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(variant))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}/add_ons",
+    tag = "service",
+    operation_id = "service_find_add_ons",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Add-ons of the service", body = [ServiceAddOn])
+    )
+)]
+#[get("/services/{service_id}/add_ons")]
+async fn find_add_ons(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
+    let add_ons = Service::find_add_ons(id.service_id)?;
+    Ok(HttpResponse::Ok().json(add_ons))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/services/{service_id}/add_ons",
+    tag = "service",
+    operation_id = "service_create_add_on",
+    params(("service_id" = i32, Path, description = "Service id")),
+    request_body = GenerateServiceAddOn,
+    responses(
+        (status = 201, description = "Created add-on", body = ServiceAddOn)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/services/{service_id}/add_ons")]
+async fn create_add_on(id: web::Path<ServiceId>, add_on: Valid<GenerateServiceAddOn>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let add_on = Service::create_add_on(id.service_id, add_on.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(add_on))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/services/{service_id}/add_ons/{service_add_on_id}",
+    tag = "service",
+    operation_id = "service_update_add_on",
+    params(
+        ("service_id" = i32, Path, description = "Service id"),
+        ("service_add_on_id" = i32, Path, description = "Service add-on id")
+    ),
+    request_body = GenerateServiceAddOn,
+    responses(
+        (status = 200, description = "Updated add-on", body = ServiceAddOn)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/services/{service_id}/add_ons/{service_add_on_id}")]
+async fn update_add_on(path: web::Path<ServiceAddOnPath>, add_on: Valid<GenerateServiceAddOn>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let add_on = Service::update_add_on(path.service_id, path.service_add_on_id, add_on.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(add_on))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/services/{service_id}/add_ons/{service_add_on_id}",
+    tag = "service",
+    operation_id = "service_delete_add_on",
+    params(
+        ("service_id" = i32, Path, description = "Service id"),
+        ("service_add_on_id" = i32, Path, description = "Service add-on id")
+    ),
+    responses(
+        (status = 200, description = "Number of add-ons deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/services/{service_id}/add_ons/{service_add_on_id}")]
+async fn delete_add_on(path: web::Path<ServiceAddOnPath>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let add_ons_deleted = Service::delete_add_on(path.service_id, path.service_add_on_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": add_ons_deleted })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}/resource_types",
+    tag = "service",
+    operation_id = "service_find_resource_types",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Resource types a booking of the service holds one of each", body = ServiceResourceTypes)
+    )
+)]
+#[get("/services/{service_id}/resource_types")]
+async fn find_resource_types(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
+    let resource_types = Service::find_resource_types(id.service_id)?;
+    Ok(HttpResponse::Ok().json(resource_types))
+}
+
+/// Replaces the resource types the service needs. Appointments already
+/// booked keep the resources they hold.
+#[utoipa::path(
+    put,
+    path = "/api/v1/services/{service_id}/resource_types",
+    tag = "service",
+    operation_id = "service_update_resource_types",
+    params(("service_id" = i32, Path, description = "Service id")),
+    request_body = ServiceResourceTypes,
+    responses(
+        (status = 200, description = "Resource types the service now needs", body = ServiceResourceTypes)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/services/{service_id}/resource_types")]
+async fn update_resource_types(id: web::Path<ServiceId>, resource_types: Valid<ServiceResourceTypes>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let resource_types = Service::update_resource_types(id.service_id, resource_types.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(resource_types))
+}
+
+/// Total price and length of a variant with add-ons, and with `start_time`
+/// the span blocked on the calendar including the service's padding.
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}/quote",
+    tag = "service",
+    operation_id = "service_quote",
+    params(
+        ("service_id" = i32, Path, description = "Service id"),
+        QuoteQuery
+    ),
+    responses(
+        (status = 200, description = "Price and timing", body = ServiceQuote),
+        (status = 404, description = "The variant does not belong to the service", body = ErrorBody),
+        (status = 422, description = "An add-on is unknown, inactive or listed twice", body = ErrorBody)
+    )
+)]
+#[get("/services/{service_id}/quote")]
+async fn quote(id: web::Path<ServiceId>, query: web::Query<QuoteQuery>) -> Result<HttpResponse, ApiError> {
+    let quote = Service::quote(id.service_id, query.into_inner())?;
+    Ok(HttpResponse::Ok().json(quote))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/services/{service_id}/staff",
+    tag = "service",
+    operation_id = "service_find_staff",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Staff assignments for the service", body = [StaffService])
+    )
+)]
+#[get("/services/{service_id}/staff")]
+async fn find_staff(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
+    let staff_services = Staff::find_staff_with_service(id.service_id)?;
+    Ok(HttpResponse::Ok().json(staff_services))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/service_categories",
+    tag = "service",
+    operation_id = "service_category_find_all",
+    responses(
+        (status = 200, description = "Categories in display order", body = [ServiceCategory])
+    )
+)]
+#[get("/service_categories")]
+async fn find_categories() -> Result<HttpResponse, ApiError> {
+    let categories = Service::find_categories()?;
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/service_categories",
+    tag = "service",
+    operation_id = "service_category_create",
+    request_body = ServiceCategoryCreate,
+    responses(
+        (status = 201, description = "Created category", body = ServiceCategory),
+        (status = 409, description = "Another category has the same name", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/service_categories")]
+async fn create_category(category: Valid<ServiceCategoryCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let category = Service::create_category(category.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(category))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/service_categories/{service_category_id}",
+    tag = "service",
+    operation_id = "service_category_find",
+    params(("service_category_id" = i32, Path, description = "Service category id")),
+    responses(
+        (status = 200, description = "Category", body = ServiceCategory)
+    )
+)]
+#[get("/service_categories/{service_category_id}")]
+async fn find_category(id: web::Path<ServiceCategoryId>) -> Result<HttpResponse, ApiError> {
+    let category = Service::find_category(id.service_category_id)?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/service_categories/{service_category_id}",
+    tag = "service",
+    operation_id = "service_category_update",
+    params(("service_category_id" = i32, Path, description = "Service category id")),
+    request_body = ServiceCategoryCreate,
+    responses(
+        (status = 200, description = "Updated category", body = ServiceCategory),
+        (status = 409, description = "Another category has the same name", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/service_categories/{service_category_id}")]
+async fn update_category(id: web::Path<ServiceCategoryId>, category: Valid<ServiceCategoryCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let category = Service::update_category(id.service_category_id, category.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(category))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/service_categories/{service_category_id}",
+    tag = "service",
+    operation_id = "service_category_delete",
+    params(("service_category_id" = i32, Path, description = "Service category id")),
+    responses(
+        (status = 200, description = "Number of categories deleted; their services become uncategorized", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/service_categories/{service_category_id}")]
+async fn delete_category(id: web::Path<ServiceCategoryId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let categories_deleted = Service::delete_category(id.service_category_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": categories_deleted })))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(create);
+    // registered before `find` so the literal paths win over `{service_id}`
+    cfg.service(import);
+    cfg.service(export);
+    cfg.service(find);
+    cfg.service(update);
+    cfg.service(update_details);
+    cfg.service(delete);
+    cfg.service(restore);
+    cfg.service(find_variants);
+    cfg.service(update_variant);
+    cfg.service(find_add_ons);
+    cfg.service(create_add_on);
+    cfg.service(update_add_on);
+    cfg.service(delete_add_on);
+    cfg.service(find_resource_types);
+    cfg.service(update_resource_types);
+    cfg.service(quote);
+    cfg.service(find_staff);
+    cfg.service(find_categories);
+    cfg.service(create_category);
+    cfg.service(find_category);
+    cfg.service(update_category);
+    cfg.service(delete_category);
+}
\ No newline at end of file
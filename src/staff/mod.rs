@@ -1,5 +1,10 @@
 pub mod model;
+pub mod password;
+pub mod repository;
+pub mod roster;
 pub mod routes;
+pub mod v1;
 
 pub use model::*;
+pub use repository::*;
 pub use routes::init_routes;
\ No newline at end of file
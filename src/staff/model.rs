@@ -1,16 +1,17 @@
 use crate::db;
 use crate::api_error::ApiError;
-use crate::{
-    schema::staff::{self, dsl::*}, 
-    schema::staff_hours::{self, dsl::*}, 
-    schema::staff_service::{self, dsl::*}, 
-    schema::service_variant::{self, dsl::*},
-    schema::service::{self, dsl::*},
-    schema::block_extra_time::{self, dsl::*}
-};
-use chrono::{NaiveTime};
+use crate::audit::Actor;
+use crate::{schema::staff, schema::staff_hours, schema::staff_service};
+use chrono::{NaiveDateTime, NaiveTime};
 use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::validation::{FieldErrors, Validate};
+use diesel::pg::{Pg, PgConnection};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::repository::{PgStaffRepository, StaffRepository};
+use super::roster::{RosterImport, RosterResult};
 
 pub use crate::service::model::{
     FullService, BlockExtraTime, ServiceVariant, Service
@@ -26,12 +27,61 @@ pub struct StaffHourId {
     pub staff_hour_id: i32
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StaffQuery {
+    pub include_deleted: Option<bool>,
+    pub access: Option<String>
+}
+
+const STAFF_SORT_FIELDS: [&str; 4] = ["staff_id", "first_name", "last_name", "email"];
+
+impl StaffQuery {
+    fn filtered(&self) -> staff::BoxedQuery<'static, Pg> {
+        let mut query = staff::table.into_boxed();
+
+        if !self.include_deleted.unwrap_or(false) {
+            query = query.filter(staff::deleted_at.is_null());
+        }
+
+        if let Some(level) = &self.access {
+            query = query.filter(staff::access.eq(level.clone()));
+        }
+
+        query
+    }
+
+    pub(super) fn find_page(&self, page: &Pagination, conn: &PgConnection) -> Result<(Vec<Staff>, i64), ApiError> {
+        let sort = page.sort(&STAFF_SORT_FIELDS)?;
+        let total = self.filtered().count().get_result::<i64>(conn)?;
+
+        let query = match (sort.field.as_str(), sort.descending) {
+            ("first_name", false) => self.filtered().order(staff::first_name.asc()),
+            ("first_name", true) => self.filtered().order(staff::first_name.desc()),
+            ("last_name", false) => self.filtered().order(staff::last_name.asc()),
+            ("last_name", true) => self.filtered().order(staff::last_name.desc()),
+            ("email", false) => self.filtered().order(staff::email.asc()),
+            ("email", true) => self.filtered().order(staff::email.desc()),
+            (_, true) => self.filtered().order(staff::staff_id.desc()),
+            (_, false) => self.filtered().order(staff::staff_id.asc()),
+        };
+
+        let staff_members = query
+            .then_order_by(staff::staff_id.asc())
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<Staff>(conn)?;
+
+        Ok((staff_members, total))
+    }
+}
+
 #[derive(Deserialize)]
 pub struct StaffServiceId {
     pub staff_service_id: i32
 }
 
-#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
 #[table_name = "staff"]
 pub struct StaffCreate {
     pub first_name: String,
@@ -43,15 +93,50 @@ pub struct StaffCreate {
     pub calendar_color: String
 }
 
+/// Changes to a staff member. The stored password is kept unless a new one
+/// is sent.
+#[derive(Serialize, Deserialize, AsChangeset, ToSchema)]
+#[table_name = "staff"]
+pub struct StaffUpdate {
+    pub first_name: String,
+    pub last_name: String,
+    pub password: Option<String>,
+    pub email: String,
+    pub phone: String,
+    pub access: String,
+    pub calendar_color: String
+}
+
+#[derive(Deserialize)]
+pub struct StaffHourPath {
+    pub staff_id: i32,
+    pub staff_hour_id: i32
+}
+
+#[derive(Deserialize)]
+pub struct StaffServicePath {
+    pub staff_id: i32,
+    pub staff_service_id: i32
+}
+
 #[derive(Serialize, Deserialize, AsChangeset, Insertable)]
 #[table_name = "staff_service"]
 pub struct StaffServiceCreate {
     pub staff_id: i32,
+    pub service_id: i32,
+    pub service_variant_id: i32,
+    pub is_active: i32
+}
+
+/// A service variant the staff member performs. The service is looked up
+/// from the variant.
+#[derive(Serialize, Deserialize, ToSchema)]
+pub struct StaffServiceAssign {
     pub service_variant_id: i32,
     pub is_active: i32
 }
 
-#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, Debug)]
+#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, Debug, ToSchema)]
 #[primary_key(staff_service_id)]
 #[table_name = "staff_service"]
 pub struct StaffService {
@@ -62,21 +147,23 @@ pub struct StaffService {
     pub is_active: Option<i32>
 }
 
-#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable)]
+#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, ToSchema)]
 #[primary_key(staff_id)]
 #[table_name = "staff"]
 pub struct Staff {
     pub staff_id: i32,
     pub first_name: String,
     pub last_name: String,
+    #[serde(skip_serializing)]
     pub password: String,
     pub email: String,
     pub phone: Option<String>,
     pub access: Option<String>,
-    pub calendar_color: Option<String>
+    pub calendar_color: Option<String>,
+    pub deleted_at: Option<NaiveDateTime>
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct BasicStaffInfo {
     pub staff_id: i32,
     pub first_name: String,
@@ -86,7 +173,20 @@ pub struct BasicStaffInfo {
     pub calendar_color: Option<String>
 }
 
-#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+impl From<&Staff> for BasicStaffInfo {
+    fn from(staff_member: &Staff) -> Self {
+        BasicStaffInfo {
+            staff_id: staff_member.staff_id,
+            first_name: staff_member.first_name.clone(),
+            last_name: staff_member.last_name.clone(),
+            email: staff_member.email.clone(),
+            phone: staff_member.phone.clone(),
+            calendar_color: staff_member.calendar_color.clone()
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
 #[table_name = "staff_hours"]
 pub struct StaffHoursCreate {
     pub staff_id: i32,
@@ -95,7 +195,7 @@ pub struct StaffHoursCreate {
     pub end_time: Option<NaiveTime>
 }
 
-#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Copy, Clone)]
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Copy, Clone, ToSchema)]
 #[belongs_to(Staff)]
 #[primary_key(staff_hours_id)]
 #[table_name = "staff_hours"]
@@ -107,253 +207,123 @@ pub struct StaffHours {
     pub end_time: Option<NaiveTime>
 }
 
-#[derive(Serialize, Deserialize, Queryable)]
+#[derive(Serialize, Deserialize, Queryable, ToSchema)]
 pub struct StaffWithHours {
     pub staff: Staff,
     pub staff_hours: Vec<StaffHours>
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct StaffWithServices {
     pub staff: Staff,
     pub services: Vec<FullService>
 }
 
-impl Staff {
-    pub fn find_all() -> QueryResult<Vec<Self>> {
-        let conn = db::establish_connection();
-        staff.order(staff::staff_id.asc()).load::<Self>(&conn)
+impl Validate for StaffCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("first_name", &self.first_name);
+        errors.required("last_name", &self.last_name);
+        errors.required("password", &self.password);
+        errors.email("email", &self.email);
+        errors.phone("phone", &self.phone);
+        errors.required("access", &self.access);
     }
+}
 
-    pub fn find(id: i32) -> QueryResult<Self> {
-        let conn = db::establish_connection();
-
-        staff.filter(staff::staff_id.eq(id)).first::<Self>(&conn)
+impl Validate for StaffUpdate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("first_name", &self.first_name);
+        errors.required("last_name", &self.last_name);
+        if let Some(new_password) = &self.password {
+            errors.required("password", new_password);
+        }
+        errors.email("email", &self.email);
+        errors.phone("phone", &self.phone);
+        errors.required("access", &self.access);
     }
+}
 
-    pub fn find_basic(id: i32) -> QueryResult<BasicStaffInfo> {
-        let conn = db::establish_connection();
-
-        let staff_data: Staff = staff.filter(staff::staff_id.eq(id)).first::<Self>(&conn)?;
-
-        let staff_member = BasicStaffInfo {
-            staff_id: staff_data.staff_id,
-            first_name: staff_data.first_name,
-            last_name: staff_data.last_name,
-            email: staff_data.email,
-            phone: staff_data.phone,
-            calendar_color: staff_data.calendar_color
-        };
-
-        Ok(staff_member)
+impl Validate for StaffServiceAssign {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.flag("is_active", self.is_active);
     }
+}
 
-    pub fn find_all_staff_hours() -> QueryResult<Vec<StaffWithHours>> {
-        let conn = db::establish_connection();
+impl Validate for StaffHoursCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.day_of_week("day_of_week", self.day_of_week);
+        errors.time_range(self.start_time, self.end_time);
+    }
+}
 
-        let staff_members = staff::table.load::<Staff>(&conn)?;
-        let staff_hours_list = StaffHours::belonging_to(&staff_members)
-            .load::<StaffHours>(&conn)?
-            .grouped_by(&staff_members);
+/// Shortcuts that run `StaffRepository` on a connection from the server pool.
+impl Staff {
+    pub fn find_all(staff_query: StaffQuery, page: &Pagination) -> Result<(Vec<Self>, i64), ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).find_all(staff_query, page)
+    }
 
-        let mut staff_final_list: Vec<StaffWithHours> = vec![];
+    pub fn find(id: i32) -> QueryResult<Self> {
+        PgStaffRepository::new(&db::establish_connection()).find(id)
+    }
 
-        let mut temp_iter = 0;
-        for member in staff_members {
-            let current_staff = StaffWithHours {
-                staff: member,
-                staff_hours: staff_hours_list[temp_iter].clone(),
-            };
+    pub fn find_basic(id: i32) -> QueryResult<BasicStaffInfo> {
+        PgStaffRepository::new(&db::establish_connection()).find_basic(id)
+    }
 
-            staff_final_list.push(current_staff);
-            temp_iter += 1;
-        }
-        
-        Ok(staff_final_list)
+    pub fn find_all_staff_hours(staff_query: StaffQuery, page: &Pagination) -> Result<(Vec<StaffWithHours>, i64), ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).find_all_staff_hours(staff_query, page)
     }
 
     pub fn find_staff_hours(id: i32) -> QueryResult<StaffWithHours> {
-        let conn = db::establish_connection();
-
-        let staff_member = staff.filter(staff::staff_id.eq(id)).first::<Self>(&conn)?;
-        let staff_member_hours = staff_hours
-            .filter(staff_hours::staff_id.eq(id))
-            .load::<StaffHours>(&conn)?;
-
-        let staff_hour = StaffWithHours {
-            staff: staff_member,
-            staff_hours: staff_member_hours,
-        };
-
-        Ok(staff_hour)
+        PgStaffRepository::new(&db::establish_connection()).find_staff_hours(id)
     }
 
-    pub fn create(staff_create: StaffCreate) -> QueryResult<Self> {
-        let conn = db::establish_connection();
-
-        let staff_created: Self = diesel::insert_into(staff::table)
-            .values(staff_create)
-            .get_result(&conn)?;
-
-        // generate staff hours
-        let mut hours_list: Vec<StaffHoursCreate> = vec![];
-
-        for x in 0..7 {
-            let day_hours = StaffHoursCreate {
-                staff_id: staff_created.staff_id,
-                day_of_week: x,
-                start_time: None,
-                end_time: None,
-            };
-
-            hours_list.push(day_hours);
-        }
-
-        diesel::insert_into(staff_hours::table).values(hours_list).execute(&conn)?;
-
-        Ok(staff_created)
+    pub fn create(staff_create: StaffCreate, actor: &Actor) -> Result<Self, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).create(staff_create, actor)
     }
 
-    pub fn update(id: i32, staff_update: StaffCreate) -> Result<Self, ApiError> {
-        let conn = db::establish_connection();
-
-        let staff_updated = diesel::update(staff::table)
-            .filter(staff::staff_id.eq(id))
-            .set(staff_update)
-            .get_result(&conn)?;
-
-        Ok(staff_updated)
+    pub fn update(id: i32, staff_update: StaffUpdate, actor: &Actor) -> Result<Self, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).update(id, staff_update, actor)
     }
 
-    pub fn update_one_hour(id: i32, staff_hour: StaffHoursCreate) -> Result<StaffHours, ApiError> {
-        let conn = db::establish_connection();
-
-        let staff_hour_updated = diesel::update(staff_hours::table)
-            .filter(staff_hours::staff_hours_id.eq(id))
-            .set(staff_hour)
-            .get_result(&conn)?;
-
-        Ok(staff_hour_updated)
+    pub fn update_one_hour(id: i32, staff_hour: StaffHoursCreate, actor: &Actor) -> Result<StaffHours, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).update_one_hour(id, staff_hour, actor)
     }
 
-    pub fn update_hours(staff_hours_update: Vec<StaffHoursCreate>) -> Result<(), ApiError> {
-        let conn = db::establish_connection();
-
-        for staff_member in staff_hours_update {
-            diesel::update(staff_hours::table)
-                .filter(staff_hours::staff_id.eq(staff_member.staff_id))
-                .filter(staff_hours::day_of_week.eq(staff_member.day_of_week))
-                .set(staff_member).execute(&conn);
-        }
-
-        Ok(())
+    pub fn update_hours(staff_hours_update: Vec<StaffHoursCreate>, actor: &Actor) -> Result<(), ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).update_hours(staff_hours_update, actor)
     }
 
-    pub fn delete(id: i32) -> Result<usize, ApiError> {
-        let conn = db::establish_connection();
-
-        // also make sure to delete other staff data
-        diesel::delete(staff_hours::table)
-            .filter(staff_hours::staff_id.eq(id))
-            .execute(&conn)?;
-
-        let res = diesel::delete(
-                staff::table
-                    .filter(staff::staff_id.eq(id))
-            )
-            .execute(&conn)?;
+    pub fn delete(id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).delete(id, actor)
+    }
 
-        Ok(res)
+    pub fn restore(id: i32, actor: &Actor) -> Result<Self, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).restore(id, actor)
     }
 
     pub fn find_staff_with_service(passed_service_id: i32) -> QueryResult<Vec<StaffService>> {
-        let conn = db::establish_connection();
-
-        staff_service.filter(staff_service::service_id.eq(passed_service_id)).load::<StaffService>(&conn)
+        PgStaffRepository::new(&db::establish_connection()).find_staff_with_service(passed_service_id)
     }
 
     pub fn find_service(id: i32) -> QueryResult<StaffWithServices> {
-        let conn = db::establish_connection();
-
-        let staff_member = staff.filter(staff::staff_id.eq(id)).first::<Self>(&conn)?;
-        let staff_service_join = staff_service
-            .filter(staff_service::staff_id.eq(id))
-            .load::<StaffService>(&conn)?;
-
-        let mut all_staff_services: Vec<FullService> = vec![];
-
-        for current_service in staff_service_join {
-            let current_variant: ServiceVariant = service_variant
-                .filter(service_variant::service_variant_id
-                .eq(current_service.service_variant_id))
-                .first::<ServiceVariant>(&conn)?;
-            let current_service: Service = service
-                .filter(service::service_id
-                .eq(current_variant.service_id))
-                .first::<Service>(&conn)?;
-            let block_extra: BlockExtraTime = block_extra_time
-                .filter(block_extra_time::service_id
-                .eq(current_service.service_id))
-                .first::<BlockExtraTime>(&conn)?;
-            
-            let complete_staff_service = FullService {
-                service: current_service,
-                blocked_time: block_extra,
-                variants: vec![current_variant]
-            };
-
-            all_staff_services.push(complete_staff_service);
-        }
-
-        let final_staff_with_service = StaffWithServices {
-            staff: staff_member,
-            services: all_staff_services
-        };
-       
-
-        Ok(final_staff_with_service)
+        PgStaffRepository::new(&db::establish_connection()).find_service(id)
     }
 
-    pub fn add_service(set_staff_id: i32, set_service_id: i32) -> Result<(), ApiError> {
-        let conn = db::establish_connection();
-
-        let new_staff_service = StaffServiceCreate {
-            staff_id: set_staff_id,
-            service_variant_id: set_service_id,
-            is_active: 1
-        };
-
-        diesel::insert_into(staff_service::table)
-            .values(new_staff_service)
-            .execute(&conn);
-
-        Ok(())
+    pub fn add_service(set_staff_id: i32, set_variant_id: i32, actor: &Actor) -> Result<StaffService, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).add_service(set_staff_id, set_variant_id, actor)
     }
 
-    pub fn update_staff_services(current_staff_id: i32, updated_services: Vec<StaffServiceCreate>) -> QueryResult<()> {
-        let conn = db::establish_connection();
-
-        diesel::delete(staff_service::table
-            .filter(staff_service::staff_service_id
-            .eq(current_staff_id)))
-            .execute(&conn)?;
-
-        for current_staff_service in updated_services {
-            diesel::insert_into(staff_service::table)
-                .values(current_staff_service)
-                .execute(&conn);
-        }
-
-        Ok(())
+    /// Replaces every service the staff member performs.
+    pub fn update_staff_services(current_staff_id: i32, updated_services: Vec<StaffServiceAssign>, actor: &Actor) -> Result<StaffWithServices, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).update_staff_services(current_staff_id, updated_services, actor)
     }
 
-    pub fn delete_service(id: i32)-> Result<usize, ApiError> {
-        let conn = db::establish_connection();
-
-        let res = diesel::delete(staff_service::table
-            .filter(staff_service::staff_service_id.eq(id)))
-            .execute(&conn)?;
+    pub fn delete_service(current_staff_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).delete_service(current_staff_id, id, actor)
+    }
 
-        Ok(res)
+    pub fn import_roster(roster: RosterImport, actor: &Actor) -> Result<Vec<RosterResult>, ApiError> {
+        PgStaffRepository::new(&db::establish_connection()).import_roster(roster, actor)
     }
 }
\ No newline at end of file
@@ -0,0 +1,14 @@
+use crate::api_error::ApiError;
+
+/// bcrypt work factor. Each step doubles the time to hash and to guess.
+const COST: u32 = bcrypt::DEFAULT_COST;
+
+pub fn hash(password: &str) -> Result<String, ApiError> {
+    bcrypt::hash(password, COST).map_err(|err| ApiError::new(500, format!("Could not hash password: {}", err)))
+}
+
+/// Passwords stored before hashing was added are plain text. bcrypt hashes
+/// are 60 characters starting with `$2a$`, `$2b$` or `$2y$`.
+pub fn is_hashed(stored: &str) -> bool {
+    stored.len() == 60 && ["$2a$", "$2b$", "$2y$"].iter().any(|prefix| stored.starts_with(prefix))
+}
\ No newline at end of file
@@ -0,0 +1,678 @@
+use crate::api_error::ApiError;
+use crate::audit::{self, Actor};
+use crate::{
+    schema::staff::{self, dsl::*},
+    schema::staff_hours::{self, dsl::*},
+    schema::staff_service::{self, dsl::*},
+    schema::service_variant::{self, dsl::*},
+    schema::service::{self, dsl::*},
+    schema::block_extra_time::{self, dsl::*}
+};
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::webhook;
+use chrono::{NaiveDateTime, Utc};
+use diesel::pg::PgConnection;
+use serde_json::json;
+use tracing::debug_span;
+
+use super::model::*;
+use super::password;
+use super::roster::{RosterImport, RosterResult};
+
+/// Staff members, their weekly hours and the service variants they perform.
+///
+/// Every call runs on the connection the repository was built with, so
+/// callers choose the database and can wrap several calls in one
+/// transaction.
+pub trait StaffRepository {
+    fn find_all(&self, staff_query: StaffQuery, page: &Pagination) -> Result<(Vec<Staff>, i64), ApiError>;
+
+    fn find(&self, id: i32) -> QueryResult<Staff>;
+
+    fn find_basic(&self, id: i32) -> QueryResult<BasicStaffInfo>;
+
+    fn find_all_staff_hours(&self, staff_query: StaffQuery, page: &Pagination) -> Result<(Vec<StaffWithHours>, i64), ApiError>;
+
+    fn find_staff_hours(&self, id: i32) -> QueryResult<StaffWithHours>;
+
+    /// Stores a bcrypt hash of `staff_create.password`, never the password itself.
+    fn create(&self, staff_create: StaffCreate, actor: &Actor) -> Result<Staff, ApiError>;
+
+    /// Changes the staff member's details, hashing a new password only when
+    /// one is sent.
+    fn update(&self, id: i32, staff_update: StaffUpdate, actor: &Actor) -> Result<Staff, ApiError>;
+
+    fn find_by_email(&self, staff_email: &str) -> QueryResult<Staff>;
+
+    fn reset_password(&self, id: i32, new_password: &str, actor: &Actor) -> Result<Staff, ApiError>;
+
+    /// Hashes any password still stored as plain text and returns how many
+    /// were changed.
+    fn hash_plaintext_passwords(&self) -> Result<usize, ApiError>;
+
+    /// Inserts the empty weekday rows missing from `staff_hours` and returns
+    /// how many were added.
+    fn repair_hours(&self) -> QueryResult<usize>;
+
+    fn update_one_hour(&self, id: i32, staff_hour: StaffHoursCreate, actor: &Actor) -> Result<StaffHours, ApiError>;
+
+    fn update_hours(&self, staff_hours_update: Vec<StaffHoursCreate>, actor: &Actor) -> Result<(), ApiError>;
+
+    fn delete(&self, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+
+    fn restore(&self, id: i32, actor: &Actor) -> Result<Staff, ApiError>;
+
+    fn find_staff_with_service(&self, passed_service_id: i32) -> QueryResult<Vec<StaffService>>;
+
+    fn find_service(&self, id: i32) -> QueryResult<StaffWithServices>;
+
+    fn add_service(&self, set_staff_id: i32, set_variant_id: i32, actor: &Actor) -> Result<StaffService, ApiError>;
+
+    /// Replaces every service the staff member performs.
+    fn update_staff_services(&self, current_staff_id: i32, updated_services: Vec<StaffServiceAssign>, actor: &Actor) -> Result<StaffWithServices, ApiError>;
+
+    fn delete_service(&self, current_staff_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+
+    /// Creates every staff member in `roster` with their hours and services,
+    /// or none of them if any row is invalid, repeats an email or phone, or
+    /// names an unknown service.
+    fn import_roster(&self, roster: RosterImport, actor: &Actor) -> Result<Vec<RosterResult>, ApiError>;
+}
+
+pub struct PgStaffRepository<'a> {
+    conn: &'a PgConnection
+}
+
+impl<'a> PgStaffRepository<'a> {
+    pub fn new(conn: &'a PgConnection) -> PgStaffRepository<'a> {
+        PgStaffRepository { conn }
+    }
+}
+
+impl<'a> StaffRepository for PgStaffRepository<'a> {
+    fn find_all(&self, staff_query: StaffQuery, page: &Pagination) -> Result<(Vec<Staff>, i64), ApiError> {
+        let span = debug_span!("Staff::find_all");
+        let _entered = span.enter();
+
+        staff_query.find_page(page, self.conn)
+    }
+
+    fn find(&self, id: i32) -> QueryResult<Staff> {
+        let span = debug_span!("Staff::find");
+        let _entered = span.enter();
+
+        staff.filter(staff::staff_id.eq(id)).first::<Staff>(self.conn)
+    }
+
+    fn find_basic(&self, id: i32) -> QueryResult<BasicStaffInfo> {
+        let span = debug_span!("Staff::find_basic");
+        let _entered = span.enter();
+
+        let staff_data: Staff = staff.filter(staff::staff_id.eq(id)).first::<Staff>(self.conn)?;
+
+        let staff_member = BasicStaffInfo {
+            staff_id: staff_data.staff_id,
+            first_name: staff_data.first_name,
+            last_name: staff_data.last_name,
+            email: staff_data.email,
+            phone: staff_data.phone,
+            calendar_color: staff_data.calendar_color
+        };
+
+        Ok(staff_member)
+    }
+
+    fn find_all_staff_hours(&self, staff_query: StaffQuery, page: &Pagination) -> Result<(Vec<StaffWithHours>, i64), ApiError> {
+        let span = debug_span!("Staff::find_all_staff_hours");
+        let _entered = span.enter();
+
+        let (staff_members, total) = staff_query.find_page(page, self.conn)?;
+        let staff_hours_list = StaffHours::belonging_to(&staff_members)
+            .load::<StaffHours>(self.conn)?
+            .grouped_by(&staff_members);
+
+        let mut staff_final_list: Vec<StaffWithHours> = vec![];
+
+        for (member, member_hours) in staff_members.into_iter().zip(staff_hours_list) {
+            let current_staff = StaffWithHours {
+                staff: member,
+                staff_hours: member_hours,
+            };
+
+            staff_final_list.push(current_staff);
+        }
+        
+        Ok((staff_final_list, total))
+    }
+
+    fn find_staff_hours(&self, id: i32) -> QueryResult<StaffWithHours> {
+        let span = debug_span!("Staff::find_staff_hours");
+        let _entered = span.enter();
+
+        let staff_member = staff.filter(staff::staff_id.eq(id)).first::<Staff>(self.conn)?;
+        let staff_member_hours = staff_hours
+            .filter(staff_hours::staff_id.eq(id))
+            .load::<StaffHours>(self.conn)?;
+
+        let staff_hour = StaffWithHours {
+            staff: staff_member,
+            staff_hours: staff_member_hours,
+        };
+
+        Ok(staff_hour)
+    }
+
+    fn create(&self, staff_create: StaffCreate, actor: &Actor) -> Result<Staff, ApiError> {
+        let span = debug_span!("Staff::create");
+        let _entered = span.enter();
+
+        let staff_create = StaffCreate { password: password::hash(&staff_create.password)?, ..staff_create };
+        let (staff_created, staff_info) = self.conn.transaction::<_, ApiError, _>(|| {
+            let staff_created: Staff = diesel::insert_into(staff::table)
+                .values(staff_create)
+                .get_result(self.conn)?;
+
+            // generate staff hours
+            let mut hours_list: Vec<StaffHoursCreate> = vec![];
+
+            for x in 0..7 {
+                let day_hours = StaffHoursCreate {
+                    staff_id: staff_created.staff_id,
+                    day_of_week: x,
+                    start_time: None,
+                    end_time: None,
+                };
+
+                hours_list.push(day_hours);
+            }
+
+            diesel::insert_into(staff_hours::table).values(hours_list).execute(self.conn)?;
+
+            let staff_info = BasicStaffInfo::from(&staff_created);
+            audit::created(self.conn, actor, "staff", staff_created.staff_id, &staff_info)?;
+
+            Ok((staff_created, staff_info))
+        })?;
+
+        webhook::emit(self.conn, webhook::STAFF_CREATED, json!(staff_info));
+
+        Ok(staff_created)
+    }
+
+    fn update(&self, id: i32, staff_update: StaffUpdate, actor: &Actor) -> Result<Staff, ApiError> {
+        let span = debug_span!("Staff::update");
+        let _entered = span.enter();
+
+        let new_password = match &staff_update.password {
+            Some(new_password) => Some(password::hash(new_password)?),
+            None => None,
+        };
+        let staff_update = StaffUpdate { password: new_password, ..staff_update };
+        let (staff_updated, staff_info) = self.conn.transaction::<_, ApiError, _>(|| {
+            let staff_before = staff.filter(staff::staff_id.eq(id)).first::<Staff>(self.conn)?;
+            let staff_updated: Staff = diesel::update(staff::table)
+                .filter(staff::staff_id.eq(id))
+                .set(staff_update)
+                .get_result(self.conn)?;
+
+            let staff_info = BasicStaffInfo::from(&staff_updated);
+            audit::updated(self.conn, actor, "staff", id, &BasicStaffInfo::from(&staff_before), &staff_info)?;
+
+            Ok((staff_updated, staff_info))
+        })?;
+
+        webhook::emit(self.conn, webhook::STAFF_UPDATED, json!(staff_info));
+
+        Ok(staff_updated)
+    }
+
+    fn find_by_email(&self, staff_email: &str) -> QueryResult<Staff> {
+        let span = debug_span!("Staff::find_by_email");
+        let _entered = span.enter();
+
+        staff
+            .filter(staff::email.eq(staff_email))
+            .filter(staff::deleted_at.is_null())
+            .first::<Staff>(self.conn)
+    }
+
+    fn reset_password(&self, id: i32, new_password: &str, actor: &Actor) -> Result<Staff, ApiError> {
+        let span = debug_span!("Staff::reset_password");
+        let _entered = span.enter();
+
+        let staff_updated = self.conn.transaction::<_, ApiError, _>(|| {
+            let staff_updated: Staff = diesel::update(staff::table)
+                .filter(staff::staff_id.eq(id))
+                .set(staff::password.eq(password::hash(new_password)?))
+                .get_result(self.conn)?;
+
+            audit::password_reset(self.conn, actor, "staff", id)?;
+
+            Ok(staff_updated)
+        })?;
+
+        Ok(staff_updated)
+    }
+
+    fn hash_plaintext_passwords(&self) -> Result<usize, ApiError> {
+        let span = debug_span!("Staff::hash_plaintext_passwords");
+        let _entered = span.enter();
+
+        let stored = staff
+            .select((staff::staff_id, staff::password))
+            .load::<(i32, String)>(self.conn)?;
+
+        let mut hashed = 0;
+
+        for (id, stored_password) in stored {
+            if password::is_hashed(&stored_password) {
+                continue;
+            }
+
+            diesel::update(staff::table)
+                .filter(staff::staff_id.eq(id))
+                .set(staff::password.eq(password::hash(&stored_password)?))
+                .execute(self.conn)?;
+
+            hashed += 1;
+        }
+
+        Ok(hashed)
+    }
+
+    fn repair_hours(&self) -> QueryResult<usize> {
+        let span = debug_span!("Staff::repair_hours");
+        let _entered = span.enter();
+
+        let staff_ids = staff.select(staff::staff_id).load::<i32>(self.conn)?;
+        let existing = staff_hours
+            .select((staff_hours::staff_id, staff_hours::day_of_week))
+            .load::<(i32, i32)>(self.conn)?;
+
+        let mut hours_list: Vec<StaffHoursCreate> = vec![];
+
+        for current_staff_id in staff_ids {
+            for x in 0..7 {
+                if existing.contains(&(current_staff_id, x)) {
+                    continue;
+                }
+
+                hours_list.push(StaffHoursCreate {
+                    staff_id: current_staff_id,
+                    day_of_week: x,
+                    start_time: None,
+                    end_time: None,
+                });
+            }
+        }
+
+        if hours_list.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::insert_into(staff_hours::table).values(hours_list).execute(self.conn)
+    }
+
+    fn update_one_hour(&self, id: i32, staff_hour: StaffHoursCreate, actor: &Actor) -> Result<StaffHours, ApiError> {
+        let span = debug_span!("Staff::update_one_hour");
+        let _entered = span.enter();
+
+        let staff_hour_updated = self.conn.transaction::<_, ApiError, _>(|| {
+            let staff_hour_before = staff_hours
+                .filter(staff_hours::staff_hours_id.eq(id))
+                .filter(staff_hours::staff_id.eq(staff_hour.staff_id))
+                .first::<StaffHours>(self.conn)?;
+            let staff_hour_updated: StaffHours = diesel::update(staff_hours::table)
+                .filter(staff_hours::staff_hours_id.eq(id))
+                .set(staff_hour)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "staff_hours", id, &staff_hour_before, &staff_hour_updated)?;
+
+            Ok(staff_hour_updated)
+        })?;
+
+        webhook::emit(self.conn, webhook::STAFF_HOURS_UPDATED, json!({ "staff_hours": [staff_hour_updated] }));
+
+        Ok(staff_hour_updated)
+    }
+
+    fn update_hours(&self, staff_hours_update: Vec<StaffHoursCreate>, actor: &Actor) -> Result<(), ApiError> {
+        let span = debug_span!("Staff::update_hours");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            for staff_member in &staff_hours_update {
+                let hours_before = staff_hours
+                    .filter(staff_hours::staff_id.eq(staff_member.staff_id))
+                    .filter(staff_hours::day_of_week.eq(staff_member.day_of_week))
+                    .load::<StaffHours>(self.conn)?;
+                let hours_updated = diesel::update(staff_hours::table)
+                    .filter(staff_hours::staff_id.eq(staff_member.staff_id))
+                    .filter(staff_hours::day_of_week.eq(staff_member.day_of_week))
+                    .set(staff_member).get_results::<StaffHours>(self.conn)?;
+
+                for (hour_before, hour_updated) in hours_before.iter().zip(hours_updated.iter()) {
+                    audit::updated(self.conn, actor, "staff_hours", hour_updated.staff_hours_id, hour_before, hour_updated)?;
+                }
+            }
+
+            Ok(())
+        })?;
+
+        webhook::emit(self.conn, webhook::STAFF_HOURS_UPDATED, json!({ "staff_hours": staff_hours_update }));
+
+        Ok(())
+    }
+
+    fn delete(&self, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Staff::delete");
+        let _entered = span.enter();
+
+        let res = self.conn.transaction::<_, ApiError, _>(|| {
+            let staff_before = staff
+                .filter(staff::staff_id.eq(id))
+                .filter(staff::deleted_at.is_null())
+                .first::<Staff>(self.conn)
+                .optional()?;
+
+            // soft delete so past appointments keep their staff, hours and services
+            let res = diesel::update(staff::table)
+                .filter(staff::staff_id.eq(id))
+                .filter(staff::deleted_at.is_null())
+                .set(staff::deleted_at.eq(Utc::now().naive_utc()))
+                .execute(self.conn)?;
+
+            if let Some(staff_before) = staff_before {
+                audit::deleted(self.conn, actor, "staff", id, &BasicStaffInfo::from(&staff_before))?;
+            }
+
+            Ok(res)
+        })?;
+
+        if res > 0 {
+            webhook::emit(self.conn, webhook::STAFF_DELETED, json!({ "staff_id": id }));
+        }
+
+        Ok(res)
+    }
+
+    fn restore(&self, id: i32, actor: &Actor) -> Result<Staff, ApiError> {
+        let span = debug_span!("Staff::restore");
+        let _entered = span.enter();
+
+        let (staff_restored, staff_info) = self.conn.transaction::<_, ApiError, _>(|| {
+            let staff_member = staff.filter(staff::staff_id.eq(id)).for_update().first::<Staff>(self.conn)?;
+
+            if staff_member.deleted_at.is_none() {
+                return Err(ApiError::coded(409, "staff.not_deleted", "Staff member is not deleted".to_string()));
+            }
+
+            let staff_restored: Staff = diesel::update(staff::table)
+                .filter(staff::staff_id.eq(id))
+                .set(staff::deleted_at.eq(None::<NaiveDateTime>))
+                .get_result(self.conn)?;
+
+            let staff_info = BasicStaffInfo::from(&staff_restored);
+            audit::restored(self.conn, actor, "staff", id, &staff_info)?;
+
+            Ok((staff_restored, staff_info))
+        })?;
+
+        webhook::emit(self.conn, webhook::STAFF_RESTORED, json!(staff_info));
+
+        Ok(staff_restored)
+    }
+
+    fn find_staff_with_service(&self, passed_service_id: i32) -> QueryResult<Vec<StaffService>> {
+        let span = debug_span!("Staff::find_staff_with_service");
+        let _entered = span.enter();
+
+        staff_service
+            .inner_join(staff)
+            .filter(staff_service::service_id.eq(passed_service_id))
+            .filter(staff::deleted_at.is_null())
+            .select(staff_service::all_columns)
+            .load::<StaffService>(self.conn)
+    }
+
+    fn find_service(&self, id: i32) -> QueryResult<StaffWithServices> {
+        let span = debug_span!("Staff::find_service");
+        let _entered = span.enter();
+
+        let staff_member = staff.filter(staff::staff_id.eq(id)).first::<Staff>(self.conn)?;
+        let staff_service_join = staff_service
+            .filter(staff_service::staff_id.eq(id))
+            .load::<StaffService>(self.conn)?;
+
+        let mut all_staff_services: Vec<FullService> = vec![];
+
+        for current_service in staff_service_join {
+            let current_variant: ServiceVariant = service_variant
+                .filter(service_variant::service_variant_id
+                .eq(current_service.service_variant_id))
+                .first::<ServiceVariant>(self.conn)?;
+            let current_service: Service = service
+                .filter(service::service_id
+                .eq(current_variant.service_id))
+                .first::<Service>(self.conn)?;
+
+            if current_service.deleted_at.is_some() {
+                continue;
+            }
+
+            let block_extra: BlockExtraTime = block_extra_time
+                .filter(block_extra_time::service_id
+                .eq(current_service.service_id))
+                .first::<BlockExtraTime>(self.conn)?;
+            
+            let complete_staff_service = FullService {
+                service: current_service,
+                blocked_time: block_extra,
+                variants: vec![current_variant]
+            };
+
+            all_staff_services.push(complete_staff_service);
+        }
+
+        let final_staff_with_service = StaffWithServices {
+            staff: staff_member,
+            services: all_staff_services
+        };
+       
+
+        Ok(final_staff_with_service)
+    }
+
+    fn add_service(&self, set_staff_id: i32, set_variant_id: i32, actor: &Actor) -> Result<StaffService, ApiError> {
+        let span = debug_span!("Staff::add_service");
+        let _entered = span.enter();
+
+        let inserted = self.conn.transaction::<_, ApiError, _>(|| {
+            let variant = service_variant
+                .filter(service_variant::service_variant_id.eq(set_variant_id))
+                .first::<ServiceVariant>(self.conn)?;
+
+            let new_staff_service = StaffServiceCreate {
+                staff_id: set_staff_id,
+                service_id: variant.service_id,
+                service_variant_id: variant.service_variant_id,
+                is_active: 1
+            };
+
+            let inserted: StaffService = diesel::insert_into(staff_service::table)
+                .values(new_staff_service)
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "staff_service", inserted.staff_service_id, &inserted)?;
+
+            Ok(inserted)
+        })?;
+
+        Ok(inserted)
+    }
+
+    fn update_staff_services(&self, current_staff_id: i32, updated_services: Vec<StaffServiceAssign>, actor: &Actor) -> Result<StaffWithServices, ApiError> {
+        let span = debug_span!("Staff::update_staff_services");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            staff.filter(staff::staff_id.eq(current_staff_id)).first::<Staff>(self.conn)?;
+
+            let services_deleted = diesel::delete(staff_service::table
+                .filter(staff_service::staff_id
+                .eq(current_staff_id)))
+                .get_results::<StaffService>(self.conn)?;
+
+            for row in &services_deleted {
+                audit::deleted(self.conn, actor, "staff_service", row.staff_service_id, row)?;
+            }
+
+            for assignment in &updated_services {
+                let variant = service_variant
+                    .filter(service_variant::service_variant_id.eq(assignment.service_variant_id))
+                    .first::<ServiceVariant>(self.conn)?;
+
+                let row: StaffService = diesel::insert_into(staff_service::table)
+                    .values(StaffServiceCreate {
+                        staff_id: current_staff_id,
+                        service_id: variant.service_id,
+                        service_variant_id: variant.service_variant_id,
+                        is_active: assignment.is_active
+                    })
+                    .get_result(self.conn)?;
+
+                audit::created(self.conn, actor, "staff_service", row.staff_service_id, &row)?;
+            }
+
+            Ok(())
+        })?;
+
+        webhook::emit(self.conn, webhook::STAFF_SERVICES_UPDATED, json!({
+            "staff_id": current_staff_id,
+            "services": updated_services
+        }));
+
+        Ok(self.find_service(current_staff_id)?)
+    }
+
+    fn delete_service(&self, current_staff_id: i32, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Staff::delete_service");
+        let _entered = span.enter();
+
+        let services_deleted = self.conn.transaction::<_, ApiError, _>(|| {
+            let services_deleted = diesel::delete(staff_service::table
+                .filter(staff_service::staff_id.eq(current_staff_id))
+                .filter(staff_service::staff_service_id.eq(id)))
+                .get_results::<StaffService>(self.conn)?;
+
+            for row in &services_deleted {
+                audit::deleted(self.conn, actor, "staff_service", row.staff_service_id, row)?;
+            }
+
+            Ok(services_deleted)
+        })?;
+
+        Ok(services_deleted.len())
+    }
+
+    fn import_roster(&self, mut roster: RosterImport, actor: &Actor) -> Result<Vec<RosterResult>, ApiError> {
+        let span = debug_span!("Staff::import_roster");
+        let _entered = span.enter();
+
+        roster.validate();
+
+        let taken = staff
+            .filter(staff::deleted_at.is_null())
+            .select((staff::email, staff::phone))
+            .load::<(String, Option<String>)>(self.conn)?;
+        let mut emails: Vec<String> = taken.iter().map(|(taken_email, _)| taken_email.clone()).collect();
+        let mut phones: Vec<String> = taken.into_iter().filter_map(|(_, taken_phone)| taken_phone).collect();
+
+        let service_names: Vec<&String> = roster.entries.iter().flat_map(|entry| entry.staff.services.iter()).collect();
+        let named_services = service::table
+            .filter(service::name.eq_any(service_names))
+            .filter(service::deleted_at.is_null())
+            .select((service::service_id, service::name))
+            .load::<(i32, String)>(self.conn)?;
+        let variants: Vec<(String, i32)> = service_variant::table
+            .filter(service_variant::service_id.eq_any(named_services.iter().map(|(named_id, _)| *named_id)))
+            .order(service_variant::service_variant_id.asc())
+            .select((service_variant::service_id, service_variant::service_variant_id))
+            .load::<(i32, i32)>(self.conn)?
+            .into_iter()
+            .filter_map(|(variant_service_id, variant_id)| {
+                named_services.iter()
+                    .find(|(named_id, _)| *named_id == variant_service_id)
+                    .map(|(_, named)| (named.clone(), variant_id))
+            })
+            .collect();
+
+        for entry in &roster.entries {
+            if emails.contains(&entry.staff.email) {
+                roster.errors.add(&format!("{}.email", entry.row), "is already taken");
+            }
+
+            if phones.contains(&entry.staff.phone) {
+                roster.errors.add(&format!("{}.phone", entry.row), "is already taken");
+            }
+
+            for service_name in &entry.staff.services {
+                if !variants.iter().any(|(variant_service, _)| variant_service == service_name) {
+                    roster.errors.add(&format!("{}.services", entry.row), &format!("unknown service: {}", service_name));
+                }
+            }
+
+            emails.push(entry.staff.email.clone());
+            phones.push(entry.staff.phone.clone());
+        }
+
+        let RosterImport { entries, errors } = roster;
+        errors.into_result()?;
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let mut results = vec![];
+
+            for entry in entries {
+                let staff_created = self.create(entry.staff.staff_create(), actor)?;
+
+                let hours_update: Vec<StaffHoursCreate> = entry.staff.hours
+                    .iter()
+                    .map(|day| StaffHoursCreate {
+                        staff_id: staff_created.staff_id,
+                        day_of_week: day.day_of_week,
+                        start_time: day.start_time,
+                        end_time: day.end_time
+                    })
+                    .collect();
+                let working_days = hours_update.iter().filter(|day| day.start_time.is_some()).count();
+
+                if !hours_update.is_empty() {
+                    self.update_hours(hours_update, actor)?;
+                }
+
+                let assigned: Vec<StaffServiceAssign> = variants
+                    .iter()
+                    .filter(|(variant_service, _)| entry.staff.services.contains(variant_service))
+                    .map(|(_, variant_id)| StaffServiceAssign { service_variant_id: *variant_id, is_active: 1 })
+                    .collect();
+                let assigned_count = assigned.len();
+
+                if !assigned.is_empty() {
+                    self.update_staff_services(staff_created.staff_id, assigned, actor)?;
+                }
+
+                results.push(RosterResult {
+                    row: entry.row,
+                    staff_id: staff_created.staff_id,
+                    email: staff_created.email,
+                    hours: working_days,
+                    services: assigned_count
+                });
+            }
+
+            Ok(results)
+        })
+    }
+}
\ No newline at end of file
@@ -0,0 +1,225 @@
+use crate::api_error::ApiError;
+use crate::validation::{FieldErrors, Validate};
+use chrono::NaiveTime;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::model::StaffCreate;
+
+const DAYS: [&str; 7] = ["sunday", "monday", "tuesday", "wednesday", "thursday", "friday", "saturday"];
+
+/// One staff member in a roster import, with their weekly hours and the
+/// names of the services they perform. Every variant of a named service is
+/// assigned. Days left out of `hours` stay empty.
+#[derive(Deserialize, ToSchema)]
+pub struct RosterStaff {
+    pub first_name: String,
+    pub last_name: String,
+    pub password: String,
+    pub email: String,
+    pub phone: String,
+    pub access: String,
+    pub calendar_color: String,
+    #[serde(default)]
+    pub hours: Vec<RosterHours>,
+    #[serde(default)]
+    pub services: Vec<String>
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct RosterHours {
+    pub day_of_week: i32,
+    pub start_time: Option<NaiveTime>,
+    pub end_time: Option<NaiveTime>
+}
+
+/// Columns of a roster CSV, one staff member per line. Each day column holds
+/// `HH:MM-HH:MM` or is left empty for a day off, and `services` separates
+/// service names with `;`.
+#[derive(Deserialize)]
+struct RosterRow {
+    first_name: String,
+    last_name: String,
+    password: String,
+    email: String,
+    phone: String,
+    access: String,
+    calendar_color: String,
+    sunday: Option<String>,
+    monday: Option<String>,
+    tuesday: Option<String>,
+    wednesday: Option<String>,
+    thursday: Option<String>,
+    friday: Option<String>,
+    saturday: Option<String>,
+    services: Option<String>
+}
+
+/// A roster member keyed by where it came from: `rows[3]` is a CSV line, or
+/// the index of a JSON array entry.
+pub struct RosterEntry {
+    pub row: String,
+    pub staff: RosterStaff
+}
+
+/// Parsed roster plus the errors found so far. Rows that could not be read
+/// are left out of `entries` and reported in `errors` instead.
+#[derive(Default)]
+pub struct RosterImport {
+    pub entries: Vec<RosterEntry>,
+    pub errors: FieldErrors
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct RosterResult {
+    pub row: String,
+    pub staff_id: i32,
+    pub email: String,
+    /// Days with working hours
+    pub hours: usize,
+    /// Service variants assigned
+    pub services: usize
+}
+
+impl RosterStaff {
+    pub fn staff_create(&self) -> StaffCreate {
+        StaffCreate {
+            first_name: self.first_name.clone(),
+            last_name: self.last_name.clone(),
+            password: self.password.clone(),
+            email: self.email.clone(),
+            phone: self.phone.clone(),
+            access: self.access.clone(),
+            calendar_color: self.calendar_color.clone()
+        }
+    }
+}
+
+impl Validate for RosterStaff {
+    fn validate(&self, errors: &mut FieldErrors) {
+        self.staff_create().validate(errors);
+
+        for (index, day) in self.hours.iter().enumerate() {
+            errors.nested(&format!("hours[{}]", index), day);
+
+            if self.hours[..index].iter().any(|earlier| earlier.day_of_week == day.day_of_week) {
+                errors.add(&format!("hours[{}].day_of_week", index), "is listed more than once");
+            }
+        }
+    }
+}
+
+impl Validate for RosterHours {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.day_of_week("day_of_week", self.day_of_week);
+        errors.time_range(self.start_time, self.end_time);
+    }
+}
+
+impl RosterRow {
+    fn into_staff(self, key: &str, errors: &mut FieldErrors) -> RosterStaff {
+        let days = [&self.sunday, &self.monday, &self.tuesday, &self.wednesday, &self.thursday, &self.friday, &self.saturday];
+        let mut hours = vec![];
+
+        for (day, cell) in (0..).zip(days.iter()) {
+            let cell = match cell {
+                Some(cell) => cell,
+                None => continue,
+            };
+
+            match parse_hours(cell) {
+                Some((start_time, end_time)) => hours.push(RosterHours { day_of_week: day, start_time: Some(start_time), end_time: Some(end_time) }),
+                None => errors.add(&format!("{}.{}", key, DAYS[day as usize]), "must look like 09:00-17:00"),
+            }
+        }
+
+        let services = self.services
+            .unwrap_or_default()
+            .split(';')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(str::to_string)
+            .collect();
+
+        RosterStaff {
+            first_name: self.first_name,
+            last_name: self.last_name,
+            password: self.password,
+            email: self.email,
+            phone: self.phone,
+            access: self.access,
+            calendar_color: self.calendar_color,
+            hours,
+            services
+        }
+    }
+}
+
+fn parse_hours(cell: &str) -> Option<(NaiveTime, NaiveTime)> {
+    let mut parts = cell.splitn(2, '-');
+    let start_time = NaiveTime::parse_from_str(parts.next()?.trim(), "%H:%M").ok()?;
+    let end_time = NaiveTime::parse_from_str(parts.next()?.trim(), "%H:%M").ok()?;
+
+    Some((start_time, end_time))
+}
+
+impl RosterImport {
+    pub fn from_json(body: &[u8]) -> Result<RosterImport, ApiError> {
+        let values: Vec<serde_json::Value> = serde_json::from_slice(body)
+            .map_err(|err| ApiError::coded(400, "request.malformed_json", err.to_string()))?;
+
+        let mut import = RosterImport::default();
+
+        for (index, value) in values.into_iter().enumerate() {
+            let key = format!("rows[{}]", index);
+
+            match serde_json::from_value::<RosterStaff>(value) {
+                Ok(staff) => import.entries.push(RosterEntry { row: key, staff }),
+                Err(err) => import.errors.add(&key, &err.to_string()),
+            }
+        }
+
+        Ok(import)
+    }
+
+    pub fn from_csv(body: &[u8]) -> Result<RosterImport, ApiError> {
+        let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::All).from_reader(body);
+        let headers = reader.headers()
+            .map_err(|err| ApiError::coded(400, "request.malformed_csv", err.to_string()))?
+            .clone();
+
+        let mut import = RosterImport::default();
+        let mut record = csv::StringRecord::new();
+
+        loop {
+            let row = match reader.read_record(&mut record) {
+                Ok(false) => break,
+                Ok(true) => record.deserialize::<RosterRow>(Some(&headers)),
+                Err(err) => Err(err),
+            };
+
+            let line = record.position().map(|position| position.line()).unwrap_or_default();
+            let key = format!("rows[{}]", line);
+
+            match row {
+                Ok(row) => {
+                    let staff = row.into_staff(&key, &mut import.errors);
+                    import.entries.push(RosterEntry { row: key, staff });
+                },
+                Err(err) => import.errors.add(&key, &err.to_string()),
+            }
+        }
+
+        Ok(import)
+    }
+
+    pub fn validate(&mut self) {
+        if self.entries.is_empty() && self.errors.is_empty() {
+            self.errors.add("rows", "must contain at least one staff member");
+        }
+
+        for entry in &self.entries {
+            self.errors.nested(&entry.row, &entry.staff);
+        }
+    }
+}
\ No newline at end of file
@@ -1,79 +1,242 @@
 use crate::api_error::ApiError;
-use crate::staff::{Staff, StaffCreate, StaffId, StaffHourId, StaffServiceId, StaffHoursCreate, StaffServiceCreate};
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::staff::{Staff, StaffCreate, StaffUpdate, StaffId, StaffQuery, StaffHourId, StaffServiceId, StaffHoursCreate, StaffServiceAssign};
 use crate::service::{ServiceId};
+use crate::validation::Valid;
 use actix_web::{get, post, put, delete, web, HttpResponse};
 use serde_json::json;
 
+#[utoipa::path(
+    get,
+    path = "/staff",
+    tag = "staff",
+    operation_id = "legacy_staff_find_all",
+    params(
+        StaffQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Staff members", body = [Staff], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
 #[get("/staff")]
-async fn find_all() -> Result<HttpResponse, ApiError> {
-    let staff = Staff::find_all()?;
-    Ok(HttpResponse::Ok().json(staff))
+async fn find_all(query: web::Query<StaffQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (staff, total) = Staff::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, staff))
 }
 
+#[utoipa::path(
+    get,
+    path = "/staff_services/{staff_service_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_find_all_services",
+    params(("staff_service_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Staff member with the services they perform", body = StaffWithServices)
+    )
+)]
 #[get("/staff_services/{staff_service_id}")]
 async fn find_all_services(id: web::Path<StaffServiceId>) -> Result<HttpResponse, ApiError> {
     let staff_services = Staff::find_service(id.staff_service_id)?;
     Ok(HttpResponse::Ok().json(staff_services))
 }
 
+#[utoipa::path(
+    get,
+    path = "/staff_with_service/{service_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_find_staff_with_service",
+    params(("service_id" = i32, Path, description = "Service id")),
+    responses(
+        (status = 200, description = "Staff assignments for the service", body = [StaffService])
+    )
+)]
 #[get("/staff_with_service/{service_id}")]
 async fn find_staff_with_service(id: web::Path<ServiceId>) -> Result<HttpResponse, ApiError> {
     let staff_services = Staff::find_staff_with_service(id.service_id)?;
     Ok(HttpResponse::Ok().json(staff_services))
 }
 
+#[utoipa::path(
+    get,
+    path = "/staff_hours",
+    tag = "staff",
+    operation_id = "legacy_staff_find_all_staff_hours",
+    params(
+        StaffQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Staff members with their weekly hours", body = [StaffWithHours], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
 #[get("/staff_hours")]
-async fn find_all_staff_hours() -> Result<HttpResponse, ApiError> {
-    let staff = Staff::find_all_staff_hours()?;
-    Ok(HttpResponse::Ok().json(staff))
+async fn find_all_staff_hours(query: web::Query<StaffQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (staff, total) = Staff::find_all_staff_hours(query.into_inner(), &page)?;
+    Ok(page.respond(total, staff))
 }
 
+#[utoipa::path(
+    get,
+    path = "/staff_hours/{staff_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_find_staff_hours",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Staff member with their weekly hours", body = StaffWithHours)
+    )
+)]
 #[get("/staff_hours/{staff_id}")]
 async fn find_staff_hours(id: web::Path<StaffId>) -> Result<HttpResponse, ApiError> {
     let staff = Staff::find_staff_hours(id.staff_id)?;
     Ok(HttpResponse::Ok().json(staff))
 }
 
+#[utoipa::path(
+    get,
+    path = "/staff/{staff_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_find",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Staff member", body = Staff)
+    )
+)]
 #[get("/staff/{staff_id}")]
 async fn find(id: web::Path<StaffId>) -> Result<HttpResponse, ApiError> {
     let staff = Staff::find(id.staff_id)?;
     Ok(HttpResponse::Ok().json(staff))
 }
 
+#[utoipa::path(
+    post,
+    path = "/staff",
+    tag = "staff",
+    operation_id = "legacy_staff_create",
+    request_body = StaffCreate,
+    responses(
+        (status = 200, description = "Created staff member", body = Staff)
+    ),
+    security(("staff_id" = []))
+)]
 #[post("/staff")]
-async fn create(staff: web::Json<StaffCreate>) -> Result<HttpResponse, ApiError> {
-    let staff = Staff::create(staff.into_inner())?;
+async fn create(staff: Valid<StaffCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::create(staff.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(staff))
 }
 
+#[utoipa::path(
+    put,
+    path = "/staff/{staff_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_update",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    request_body = StaffUpdate,
+    responses(
+        (status = 200, description = "Updated staff member", body = Staff)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/staff/{staff_id}")]
-async fn update(id: web::Path<StaffId>, staff: web::Json<StaffCreate>) -> Result<HttpResponse, ApiError> {
-    let staff = Staff::update(id.staff_id, staff.into_inner())?;
+async fn update(id: web::Path<StaffId>, staff: Valid<StaffUpdate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::update(id.staff_id, staff.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(staff))
 }
 
+#[utoipa::path(
+    put,
+    path = "/staff_hours",
+    tag = "staff",
+    operation_id = "legacy_staff_update_hours",
+    request_body = [StaffHoursCreate],
+    responses(
+        (status = 200, description = "Hours updated")
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/staff_hours")]
-async fn update_hours(staff_hours: web::Json<Vec<StaffHoursCreate>>) -> Result<HttpResponse, ApiError> {
-    let staff = Staff::update_hours(staff_hours.into_inner())?;
+async fn update_hours(staff_hours: Valid<Vec<StaffHoursCreate>>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::update_hours(staff_hours.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(staff))
 }
 
+#[utoipa::path(
+    put,
+    path = "/staff_hours/{staff_hour_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_update_one_hour",
+    params(("staff_hour_id" = i32, Path, description = "Staff hours row id")),
+    request_body = StaffHoursCreate,
+    responses(
+        (status = 200, description = "Updated hours row", body = StaffHours)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/staff_hours/{staff_hour_id}")]
-async fn update_one_hour(id: web::Path<StaffHourId>, staff_hours: web::Json<StaffHoursCreate>) -> Result<HttpResponse, ApiError> {
-    let staff = Staff::update_one_hour(id.staff_hour_id, staff_hours.into_inner())?;
+async fn update_one_hour(id: web::Path<StaffHourId>, staff_hours: Valid<StaffHoursCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::update_one_hour(id.staff_hour_id, staff_hours.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(staff))
 }
 
+#[utoipa::path(
+    put,
+    path = "/staff_services/{staff_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_update_staff_services",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    request_body = [StaffServiceAssign],
+    responses(
+        (status = 200, description = "Staff member with the services they perform", body = StaffWithServices)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/staff_services/{staff_id}")]
-async fn update_staff_services(id: web::Path<StaffId>, staff_services: web::Json<Vec<StaffServiceCreate>>) -> Result<HttpResponse, ApiError> {
-    let staff_services = Staff::update_staff_services(id.staff_id, staff_services.into_inner())?;
+async fn update_staff_services(id: web::Path<StaffId>, staff_services: Valid<Vec<StaffServiceAssign>>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff_services = Staff::update_staff_services(id.staff_id, staff_services.into_inner(), &actor)?;
 
     Ok(HttpResponse::Ok().json(staff_services))
 }
 
+#[utoipa::path(
+    post,
+    path = "/staff/{staff_id}/restore",
+    tag = "staff",
+    operation_id = "legacy_staff_restore",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Restored staff member", body = Staff),
+        (status = 404, description = "No such staff member", body = ErrorBody),
+        (status = 409, description = "The staff member is not deleted, or their email or phone now belongs to someone else", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/staff/{staff_id}/restore")]
+async fn restore(id: web::Path<StaffId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::restore(id.staff_id, &actor)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/staff/{staff_id}",
+    tag = "staff",
+    operation_id = "legacy_staff_delete",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Number of staff members deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
 #[delete("/staff/{staff_id}")]
-async fn delete(id: web::Path<StaffId>) -> Result<HttpResponse, ApiError> {
-    let staff_deleted = Staff::delete(id.staff_id)?;
+async fn delete(id: web::Path<StaffId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff_deleted = Staff::delete(id.staff_id, &actor)?;
     Ok(HttpResponse::Ok().json(json!({ "deleted": staff_deleted })))
 }
 
@@ -89,5 +252,6 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(update_one_hour);
     cfg.service(update_hours);
     cfg.service(update_staff_services);
+    cfg.service(restore);
     cfg.service(delete);
 }
\ No newline at end of file
@@ -0,0 +1,305 @@
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::staff::{Staff, StaffCreate, StaffUpdate, StaffId, StaffQuery, StaffHourPath, StaffServicePath, StaffHoursCreate, StaffServiceAssign};
+use crate::staff::roster::RosterImport;
+use crate::validation::Valid;
+use actix_web::{get, post, put, delete, web, HttpMessage, HttpRequest, HttpResponse};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/staff",
+    tag = "staff",
+    operation_id = "staff_find_all",
+    params(
+        StaffQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Staff members", body = [Staff], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/staff")]
+async fn find_all(query: web::Query<StaffQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (staff, total) = Staff::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, staff))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/staff",
+    tag = "staff",
+    operation_id = "staff_create",
+    request_body = StaffCreate,
+    responses(
+        (status = 201, description = "Created staff member", body = Staff)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/staff")]
+async fn create(staff: Valid<StaffCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::create(staff.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(staff))
+}
+
+/// Creates staff with their weekly hours and services from a CSV
+/// (`text/csv`) or JSON (`application/json`) body. CSV files have one line
+/// per staff member with the columns
+/// `first_name,last_name,password,email,phone,access,calendar_color,sunday,...,saturday,services`,
+/// where each day is `HH:MM-HH:MM` or empty and `services` is a `;` separated
+/// list of service names.
+#[utoipa::path(
+    post,
+    path = "/api/v1/staff/import",
+    tag = "staff",
+    operation_id = "staff_import",
+    request_body(content = [RosterStaff], description = "JSON array of staff, or the same as CSV with one line per staff member", content_type = "application/json"),
+    responses(
+        (status = 201, description = "Every staff member was created", body = [RosterResult]),
+        (status = 415, description = "Body is neither CSV nor JSON", body = ErrorBody),
+        (status = 422, description = "Rows failed validation and nothing was created", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/staff/import")]
+async fn import(req: HttpRequest, body: web::Bytes, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let roster = match req.content_type() {
+        "text/csv" => RosterImport::from_csv(&body)?,
+        "application/json" => RosterImport::from_json(&body)?,
+        _ => return Err(ApiError::coded(415, "request.content_type", "Expected a text/csv or application/json body".to_string())),
+    };
+
+    let results = Staff::import_roster(roster, &actor)?;
+    Ok(HttpResponse::Created().json(results))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/staff/hours",
+    tag = "staff",
+    operation_id = "staff_find_all_hours",
+    params(
+        StaffQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Staff members with their weekly hours", body = [StaffWithHours], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/staff/hours")]
+async fn find_all_hours(query: web::Query<StaffQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (staff, total) = Staff::find_all_staff_hours(query.into_inner(), &page)?;
+    Ok(page.respond(total, staff))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/staff/{staff_id}",
+    tag = "staff",
+    operation_id = "staff_find",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Staff member", body = Staff)
+    )
+)]
+#[get("/staff/{staff_id}")]
+async fn find(id: web::Path<StaffId>) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::find(id.staff_id)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/staff/{staff_id}",
+    tag = "staff",
+    operation_id = "staff_update",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    request_body = StaffUpdate,
+    responses(
+        (status = 200, description = "Updated staff member", body = Staff)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/staff/{staff_id}")]
+async fn update(id: web::Path<StaffId>, staff: Valid<StaffUpdate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::update(id.staff_id, staff.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/staff/{staff_id}",
+    tag = "staff",
+    operation_id = "staff_delete",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Number of staff members deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/staff/{staff_id}")]
+async fn delete(id: web::Path<StaffId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff_deleted = Staff::delete(id.staff_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": staff_deleted })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/staff/{staff_id}/restore",
+    tag = "staff",
+    operation_id = "staff_restore",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Restored staff member", body = Staff),
+        (status = 404, description = "No such staff member", body = ErrorBody),
+        (status = 409, description = "The staff member is not deleted, or their email or phone now belongs to someone else", body = ErrorBody)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/staff/{staff_id}/restore")]
+async fn restore(id: web::Path<StaffId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::restore(id.staff_id, &actor)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/staff/{staff_id}/hours",
+    tag = "staff",
+    operation_id = "staff_find_hours",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Staff member with their weekly hours", body = StaffWithHours)
+    )
+)]
+#[get("/staff/{staff_id}/hours")]
+async fn find_hours(id: web::Path<StaffId>) -> Result<HttpResponse, ApiError> {
+    let staff = Staff::find_staff_hours(id.staff_id)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/staff/{staff_id}/hours",
+    tag = "staff",
+    operation_id = "staff_update_hours",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    request_body = [StaffHoursCreate],
+    responses(
+        (status = 200, description = "Staff member with their updated weekly hours", body = StaffWithHours)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/staff/{staff_id}/hours")]
+async fn update_hours(id: web::Path<StaffId>, staff_hours: Valid<Vec<StaffHoursCreate>>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let mut staff_hours = staff_hours.into_inner();
+    staff_hours.iter_mut().for_each(|hour| hour.staff_id = id.staff_id);
+
+    Staff::update_hours(staff_hours, &actor)?;
+
+    let staff = Staff::find_staff_hours(id.staff_id)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/staff/{staff_id}/hours/{staff_hour_id}",
+    tag = "staff",
+    operation_id = "staff_update_one_hour",
+    params(
+        ("staff_id" = i32, Path, description = "Staff member id"),
+        ("staff_hour_id" = i32, Path, description = "Staff hours row id")
+    ),
+    request_body = StaffHoursCreate,
+    responses(
+        (status = 200, description = "Updated hours row", body = StaffHours)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/staff/{staff_id}/hours/{staff_hour_id}")]
+async fn update_one_hour(path: web::Path<StaffHourPath>, staff_hours: Valid<StaffHoursCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let mut staff_hours = staff_hours.into_inner();
+    staff_hours.staff_id = path.staff_id;
+
+    let staff = Staff::update_one_hour(path.staff_hour_id, staff_hours, &actor)?;
+    Ok(HttpResponse::Ok().json(staff))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/staff/{staff_id}/services",
+    tag = "staff",
+    operation_id = "staff_find_services",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    responses(
+        (status = 200, description = "Staff member with the services they perform", body = StaffWithServices)
+    )
+)]
+#[get("/staff/{staff_id}/services")]
+async fn find_services(id: web::Path<StaffId>) -> Result<HttpResponse, ApiError> {
+    let staff_services = Staff::find_service(id.staff_id)?;
+    Ok(HttpResponse::Ok().json(staff_services))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/staff/{staff_id}/services",
+    tag = "staff",
+    operation_id = "staff_update_services",
+    params(("staff_id" = i32, Path, description = "Staff member id")),
+    request_body = [StaffServiceAssign],
+    responses(
+        (status = 200, description = "Staff member with the services they perform", body = StaffWithServices)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/staff/{staff_id}/services")]
+async fn update_services(id: web::Path<StaffId>, staff_services: Valid<Vec<StaffServiceAssign>>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let staff_services = Staff::update_staff_services(id.staff_id, staff_services.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(staff_services))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/staff/{staff_id}/services/{staff_service_id}",
+    tag = "staff",
+    operation_id = "staff_delete_service",
+    params(
+        ("staff_id" = i32, Path, description = "Staff member id"),
+        ("staff_service_id" = i32, Path, description = "Staff service assignment id")
+    ),
+    responses(
+        (status = 200, description = "Number of assignments deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/staff/{staff_id}/services/{staff_service_id}")]
+async fn delete_service(path: web::Path<StaffServicePath>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let services_deleted = Staff::delete_service(path.staff_id, path.staff_service_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": services_deleted })))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(create);
+    cfg.service(import);
+    // registered ahead of /staff/{staff_id} so "hours" is not parsed as an id
+    cfg.service(find_all_hours);
+    cfg.service(find);
+    cfg.service(update);
+    cfg.service(delete);
+    cfg.service(restore);
+    cfg.service(find_hours);
+    cfg.service(update_hours);
+    cfg.service(update_one_hour);
+    cfg.service(find_services);
+    cfg.service(update_services);
+    cfg.service(delete_service);
+}
\ No newline at end of file
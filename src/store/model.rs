@@ -1,32 +1,85 @@
 use crate::db;
 use crate::api_error::ApiError;
-use crate::{schema::store::{self, dsl::*}, schema::store_hours::{self, dsl::*}, schema::store_address::{self, dsl::*}};
+use crate::audit::Actor;
+use crate::{schema::store, schema::store_hours, schema::store_address};
 use chrono::{NaiveTime};
 use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use crate::validation::{FieldErrors, Validate};
+use diesel::pg::{Pg, PgConnection};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use super::repository::{PgStoreRepository, StoreRepository};
 
 #[derive(Deserialize)]
 pub struct StoreId {
     pub store_id: i32
 }
 
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StoreQuery {
+    pub name: Option<String>
+}
+
+const STORE_SORT_FIELDS: [&str; 2] = ["store_id", "name"];
+
+impl StoreQuery {
+    fn filtered(&self) -> store::BoxedQuery<'static, Pg> {
+        let mut query = store::table.into_boxed();
+
+        if let Some(search) = &self.name {
+            query = query.filter(store::name.ilike(format!("%{}%", search)));
+        }
+
+        query
+    }
+
+    pub(super) fn find_page(&self, page: &Pagination, conn: &PgConnection) -> Result<(Vec<Store>, i64), ApiError> {
+        let sort = page.sort(&STORE_SORT_FIELDS)?;
+        let total = self.filtered().count().get_result::<i64>(conn)?;
+
+        let query = match (sort.field.as_str(), sort.descending) {
+            ("name", false) => self.filtered().order(store::name.asc()),
+            ("name", true) => self.filtered().order(store::name.desc()),
+            (_, true) => self.filtered().order(store::store_id.desc()),
+            (_, false) => self.filtered().order(store::store_id.asc()),
+        };
+
+        let stores = query
+            .then_order_by(store::store_id.asc())
+            .limit(page.limit)
+            .offset(page.offset)
+            .load::<Store>(conn)?;
+
+        Ok((stores, total))
+    }
+}
+
 #[derive(Deserialize)]
 pub struct StoreHourId {
     pub store_hour_id: i32
 }
 
+#[derive(Deserialize)]
+pub struct StoreHourPath {
+    pub store_id: i32,
+    pub store_hour_id: i32
+}
+
 #[derive(Deserialize)]
 pub struct StoreAddressId {
     pub store_address_id: i32
 }
 
-#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
 #[table_name = "store"]
 pub struct StoreCreate {
     pub name: String
 }
 
-#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable)]
+#[derive(Eq, PartialEq, Identifiable, Serialize, Deserialize, Queryable, ToSchema)]
 #[primary_key(store_id)]
 #[table_name = "store"]
 pub struct Store {
@@ -34,26 +87,26 @@ pub struct Store {
     pub name: String
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct FullStore {
     pub name: String,
     pub address: StoreAddress,
     pub hours: Vec<StoreHours>
 }
 
-#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
 #[table_name = "store_address"]
 pub struct StoreAddressCreate {
     pub store_id: i32,
     pub street_address: String,
     pub city: String,
     pub state: String,
-    pub zip: i32,
+    pub zip: String,
     pub phone: String,
     pub email: String
 }
 
-#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug)]
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, ToSchema)]
 #[belongs_to(Store)]
 #[primary_key(store_address_id)]
 #[table_name = "store_address"]
@@ -63,12 +116,12 @@ pub struct StoreAddress {
     pub street_address: String,
     pub city: String,
     pub state: String,
-    pub zip: i32,
+    pub zip: String,
     pub phone: String,
     pub email: String
 }
 
-#[derive(Serialize, Deserialize, AsChangeset, Insertable)]
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
 #[table_name = "store_hours"]
 pub struct StoreHoursCreate {
     pub store_id: i32,
@@ -77,7 +130,7 @@ pub struct StoreHoursCreate {
     pub end_time: Option<NaiveTime>
 }
 
-#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Copy, Clone)]
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, AsChangeset, Debug, Copy, Clone, ToSchema)]
 #[belongs_to(Store)]
 #[primary_key(store_hours_id)]
 #[table_name = "store_hours"]
@@ -89,204 +142,87 @@ pub struct StoreHours {
     pub end_time: Option<NaiveTime>
 }
 
-#[derive(Serialize, Deserialize, Queryable)]
+#[derive(Serialize, Deserialize, Queryable, ToSchema)]
 pub struct StoreWithHours {
     pub store: Store,
     pub store_hours: Vec<StoreHours>
 }
 
+impl Validate for StoreCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("name", &self.name);
+    }
+}
+
+impl Validate for StoreAddressCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.required("street_address", &self.street_address);
+        errors.required("city", &self.city);
+        errors.required("state", &self.state);
+        errors.zip("zip", &self.zip);
+        errors.phone("phone", &self.phone);
+        errors.email("email", &self.email);
+    }
+}
+
+impl Validate for StoreHoursCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.day_of_week("day_of_week", self.day_of_week);
+        errors.time_range(self.start_time, self.end_time);
+    }
+}
+
+/// Shortcuts that run `StoreRepository` on a connection from the server pool.
 impl Store {
-    pub fn find_all() -> QueryResult<Vec<Self>> {
-        let conn = db::establish_connection();
-        store.order(store::store_id.asc()).load::<Self>(&conn)
+    pub fn find_all(store_query: StoreQuery, page: &Pagination) -> Result<(Vec<Self>, i64), ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).find_all(store_query, page)
     }
 
     pub fn find_all_data(id: i32) -> QueryResult<FullStore> {
-        let conn = db::establish_connection();
-
-        let store_name = store
-            .filter(store::store_id
-            .eq(id))
-            .first::<Self>(&conn)?;
-        let store_address_data = store_address
-            .filter(store_address::store_id
-            .eq(id))
-            .first::<StoreAddress>(&conn)?;
-        let store_hours_data = store_hours
-            .filter(store_hours::store_id
-            .eq(id))
-            .load::<StoreHours>(&conn)?;
-
-        let full_store_data = FullStore {
-            name: store_name.name,
-            address: store_address_data,
-            hours: store_hours_data
-        };
-
-        Ok(full_store_data)
+        PgStoreRepository::new(&db::establish_connection()).find_all_data(id)
     }
 
     pub fn find(id: i32) -> QueryResult<Self> {
-        let conn = db::establish_connection();
-    
-        store.filter(store::store_id.eq(id)).first::<Self>(&conn)
+        PgStoreRepository::new(&db::establish_connection()).find(id)
     }
 
-    pub fn find_all_store_hours() -> QueryResult<Vec<StoreWithHours>> {
-        let conn = db::establish_connection();
-
-        let store_list = store::table.load::<Store>(&conn)?;
-        let store_hours_list = StoreHours::belonging_to(&store_list)
-            .load::<StoreHours>(&conn)?
-            .grouped_by(&store_list);
-        
-        let mut store_final_list: Vec<StoreWithHours> = vec![];
-
-        let mut temp_iter = 0;
-        for store_info in store_list {
-            let current_store = StoreWithHours {
-                store: store_info,
-                store_hours: store_hours_list[temp_iter].clone()
-            };
-
-            store_final_list.push(current_store);
-            temp_iter += 1;
-        }
-
-        Ok(store_final_list)
+    pub fn find_all_store_hours(store_query: StoreQuery, page: &Pagination) -> Result<(Vec<StoreWithHours>, i64), ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).find_all_store_hours(store_query, page)
     }
 
     pub fn find_store_hours(id: i32) -> QueryResult<StoreWithHours> {
-        let conn = db::establish_connection();
-
-        let store_member = store
-            .filter(store::store_id
-            .eq(id))
-            .first::<Self>(&conn)?;
-        let store_member_hours = store_hours
-            .filter(store_hours::store_id.eq(id))
-            .load::<StoreHours>(&conn)?;
-
-        let store_hour = StoreWithHours {
-            store: store_member,
-            store_hours: store_member_hours,
-        };
-
-        Ok(store_hour)
+        PgStoreRepository::new(&db::establish_connection()).find_store_hours(id)
     }
 
     pub fn find_address(id: i32) -> QueryResult<StoreAddress> {
-        let conn = db::establish_connection();
-
-        let store_address_details = store_address.filter(store_address::store_id.eq(id)).first::<StoreAddress>(&conn)?;
-
-        Ok(store_address_details)
+        PgStoreRepository::new(&db::establish_connection()).find_address(id)
     }
 
-    pub fn create(store_create: StoreCreate) -> QueryResult<Self> {
-        let conn = db::establish_connection();
-
-        let store_created: Self = diesel::insert_into(store::table)
-            .values(store_create)
-            .get_result(&conn)?;
-
-        // generate store hours
-        let mut hours_list: Vec<StoreHoursCreate> = vec![];
-
-        for x in 0..7 {
-            let day_hours = StoreHoursCreate {
-                store_id: store_created.store_id,
-                day_of_week: x,
-                start_time: None,
-                end_time: None,
-            };
-
-            hours_list.push(day_hours);
-        }
-
-        diesel::insert_into(store_hours::table)
-            .values(hours_list)
-            .execute(&conn)?;
-
-        Ok(store_created)
+    pub fn create(store_create: StoreCreate, actor: &Actor) -> QueryResult<Self> {
+        PgStoreRepository::new(&db::establish_connection()).create(store_create, actor)
     }
 
-    pub fn create_address(store_address_create: StoreAddressCreate) -> QueryResult<StoreAddress> {
-        let conn = db::establish_connection();
-
-        let store_address_created: StoreAddress = diesel::insert_into(store_address::table)
-            .values(store_address_create)
-            .get_result(&conn)?;
-        
-        Ok(store_address_created)
+    pub fn create_address(store_address_create: StoreAddressCreate, actor: &Actor) -> QueryResult<StoreAddress> {
+        PgStoreRepository::new(&db::establish_connection()).create_address(store_address_create, actor)
     }
 
-    pub fn update(id: i32, store_update: StoreCreate) -> Result<Self, ApiError> {
-        let conn = db::establish_connection();
-
-        let store_updated = diesel::update(store::table)
-            .filter(store::store_id.eq(id))
-            .set(store_update)
-            .get_result(&conn)?;
-
-        Ok(store_updated)
+    pub fn update(id: i32, store_update: StoreCreate, actor: &Actor) -> Result<Self, ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).update(id, store_update, actor)
     }
 
-    pub fn update_address(id: i32, store_address_update: StoreAddressCreate) -> Result<StoreAddress, ApiError> {
-        let conn = db::establish_connection();
-
-        let store_updated = diesel::update(store_address::table)
-            .filter(store_address::store_id.eq(id))
-            .set(store_address_update)
-            .get_result(&conn)?;
-
-        Ok(store_updated)
+    pub fn update_address(id: i32, store_address_update: StoreAddressCreate, actor: &Actor) -> Result<StoreAddress, ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).update_address(id, store_address_update, actor)
     }
 
-    pub fn update_one_hour(id: i32, store_hour: StoreHoursCreate) -> Result<StoreHours, ApiError> {
-        let conn = db::establish_connection();
-
-        let store_hour_updated = diesel::update(store_hours::table)
-            .filter(store_hours::store_hours_id.eq(id))
-            .set(store_hour)
-            .get_result(&conn)?;
-
-        Ok(store_hour_updated)
+    pub fn update_one_hour(id: i32, store_hour: StoreHoursCreate, actor: &Actor) -> Result<StoreHours, ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).update_one_hour(id, store_hour, actor)
     }
 
-    pub fn update_hours(store_hours_update: Vec<StoreHoursCreate>) -> Result<(), ApiError> {
-        let conn = db::establish_connection();
-
-        for store_member in store_hours_update {
-            diesel::update(store_hours::table)
-                .filter(store_hours::store_id.eq(store_member.store_id))
-                .filter(store_hours::day_of_week.eq(store_member.day_of_week))
-                .set(store_member).execute(&conn);
-        }
-
-        Ok(())
+    pub fn update_hours(store_hours_update: Vec<StoreHoursCreate>, actor: &Actor) -> Result<(), ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).update_hours(store_hours_update, actor)
     }
 
-    pub fn delete(id: i32) -> Result<usize, ApiError> {
-        let conn = db::establish_connection();
-
-        // also make sure to delete other store data
-        diesel::delete(store_hours::table)
-            .filter(store_hours::store_id
-            .eq(id))
-            .execute(&conn)?;
-        diesel::delete(store_address::table)
-            .filter(store_address::store_id
-            .eq(id))
-            .execute(&conn)?;
-
-        let res = diesel::delete(
-                store::table
-                    .filter(store::store_id
-                    .eq(id))
-            )
-            .execute(&conn)?;
-
-        Ok(res)
+    pub fn delete(id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        PgStoreRepository::new(&db::establish_connection()).delete(id, actor)
     }
 }
\ No newline at end of file
@@ -0,0 +1,349 @@
+use crate::api_error::ApiError;
+use crate::audit::{self, Actor};
+use crate::{schema::store::{self, dsl::*}, schema::store_hours::{self, dsl::*}, schema::store_address::{self, dsl::*}};
+use crate::diesel::prelude::*;
+use crate::pagination::Pagination;
+use diesel::pg::PgConnection;
+use serde_json::json;
+use tracing::debug_span;
+
+use super::model::*;
+
+/// Stores, their addresses and weekly hours.
+///
+/// Every call runs on the connection the repository was built with, so
+/// callers choose the database and can wrap several calls in one
+/// transaction.
+pub trait StoreRepository {
+    fn find_all(&self, store_query: StoreQuery, page: &Pagination) -> Result<(Vec<Store>, i64), ApiError>;
+
+    fn find_all_data(&self, id: i32) -> QueryResult<FullStore>;
+
+    fn find(&self, id: i32) -> QueryResult<Store>;
+
+    fn find_all_store_hours(&self, store_query: StoreQuery, page: &Pagination) -> Result<(Vec<StoreWithHours>, i64), ApiError>;
+
+    fn find_store_hours(&self, id: i32) -> QueryResult<StoreWithHours>;
+
+    fn find_address(&self, id: i32) -> QueryResult<StoreAddress>;
+
+    fn create(&self, store_create: StoreCreate, actor: &Actor) -> QueryResult<Store>;
+
+    fn create_address(&self, store_address_create: StoreAddressCreate, actor: &Actor) -> QueryResult<StoreAddress>;
+
+    fn update(&self, id: i32, store_update: StoreCreate, actor: &Actor) -> Result<Store, ApiError>;
+
+    fn update_address(&self, id: i32, store_address_update: StoreAddressCreate, actor: &Actor) -> Result<StoreAddress, ApiError>;
+
+    fn update_one_hour(&self, id: i32, store_hour: StoreHoursCreate, actor: &Actor) -> Result<StoreHours, ApiError>;
+
+    fn update_hours(&self, store_hours_update: Vec<StoreHoursCreate>, actor: &Actor) -> Result<(), ApiError>;
+
+    fn delete(&self, id: i32, actor: &Actor) -> Result<usize, ApiError>;
+
+    /// Inserts the empty weekday rows missing from `store_hours` and returns
+    /// how many were added.
+    fn repair_hours(&self) -> QueryResult<usize>;
+}
+
+pub struct PgStoreRepository<'a> {
+    conn: &'a PgConnection
+}
+
+impl<'a> PgStoreRepository<'a> {
+    pub fn new(conn: &'a PgConnection) -> PgStoreRepository<'a> {
+        PgStoreRepository { conn }
+    }
+}
+
+impl<'a> StoreRepository for PgStoreRepository<'a> {
+    fn find_all(&self, store_query: StoreQuery, page: &Pagination) -> Result<(Vec<Store>, i64), ApiError> {
+        let span = debug_span!("Store::find_all");
+        let _entered = span.enter();
+
+        store_query.find_page(page, self.conn)
+    }
+
+    fn find_all_data(&self, id: i32) -> QueryResult<FullStore> {
+        let span = debug_span!("Store::find_all_data");
+        let _entered = span.enter();
+
+        let store_name = store
+            .filter(store::store_id
+            .eq(id))
+            .first::<Store>(self.conn)?;
+        let store_address_data = store_address
+            .filter(store_address::store_id
+            .eq(id))
+            .first::<StoreAddress>(self.conn)?;
+        let store_hours_data = store_hours
+            .filter(store_hours::store_id
+            .eq(id))
+            .load::<StoreHours>(self.conn)?;
+
+        let full_store_data = FullStore {
+            name: store_name.name,
+            address: store_address_data,
+            hours: store_hours_data
+        };
+
+        Ok(full_store_data)
+    }
+
+    fn find(&self, id: i32) -> QueryResult<Store> {
+        let span = debug_span!("Store::find");
+        let _entered = span.enter();
+
+    
+        store.filter(store::store_id.eq(id)).first::<Store>(self.conn)
+    }
+
+    fn find_all_store_hours(&self, store_query: StoreQuery, page: &Pagination) -> Result<(Vec<StoreWithHours>, i64), ApiError> {
+        let span = debug_span!("Store::find_all_store_hours");
+        let _entered = span.enter();
+
+        let (store_list, total) = store_query.find_page(page, self.conn)?;
+        let store_hours_list = StoreHours::belonging_to(&store_list)
+            .load::<StoreHours>(self.conn)?
+            .grouped_by(&store_list);
+        
+        let mut store_final_list: Vec<StoreWithHours> = vec![];
+
+        for (store_info, store_info_hours) in store_list.into_iter().zip(store_hours_list) {
+            let current_store = StoreWithHours {
+                store: store_info,
+                store_hours: store_info_hours
+            };
+
+            store_final_list.push(current_store);
+        }
+
+        Ok((store_final_list, total))
+    }
+
+    fn find_store_hours(&self, id: i32) -> QueryResult<StoreWithHours> {
+        let span = debug_span!("Store::find_store_hours");
+        let _entered = span.enter();
+
+        let store_member = store
+            .filter(store::store_id
+            .eq(id))
+            .first::<Store>(self.conn)?;
+        let store_member_hours = store_hours
+            .filter(store_hours::store_id.eq(id))
+            .load::<StoreHours>(self.conn)?;
+
+        let store_hour = StoreWithHours {
+            store: store_member,
+            store_hours: store_member_hours,
+        };
+
+        Ok(store_hour)
+    }
+
+    fn find_address(&self, id: i32) -> QueryResult<StoreAddress> {
+        let span = debug_span!("Store::find_address");
+        let _entered = span.enter();
+
+        let store_address_details = store_address.filter(store_address::store_id.eq(id)).first::<StoreAddress>(self.conn)?;
+
+        Ok(store_address_details)
+    }
+
+    fn create(&self, store_create: StoreCreate, actor: &Actor) -> QueryResult<Store> {
+        let span = debug_span!("Store::create");
+        let _entered = span.enter();
+
+        self.conn.transaction(|| {
+            let store_created: Store = diesel::insert_into(store::table)
+                .values(store_create)
+                .get_result(self.conn)?;
+
+            // generate store hours
+            let mut hours_list: Vec<StoreHoursCreate> = vec![];
+
+            for x in 0..7 {
+                let day_hours = StoreHoursCreate {
+                    store_id: store_created.store_id,
+                    day_of_week: x,
+                    start_time: None,
+                    end_time: None,
+                };
+
+                hours_list.push(day_hours);
+            }
+
+            diesel::insert_into(store_hours::table)
+                .values(hours_list)
+                .execute(self.conn)?;
+
+            audit::created(self.conn, actor, "store", store_created.store_id, &store_created)?;
+
+            Ok(store_created)
+        })
+    }
+
+    fn create_address(&self, store_address_create: StoreAddressCreate, actor: &Actor) -> QueryResult<StoreAddress> {
+        let span = debug_span!("Store::create_address");
+        let _entered = span.enter();
+
+        self.conn.transaction(|| {
+            let store_address_created: StoreAddress = diesel::insert_into(store_address::table)
+                .values(store_address_create)
+                .get_result(self.conn)?;
+
+            audit::created(self.conn, actor, "store_address", store_address_created.store_address_id, &store_address_created)?;
+
+            Ok(store_address_created)
+        })
+    }
+
+    fn update(&self, id: i32, store_update: StoreCreate, actor: &Actor) -> Result<Store, ApiError> {
+        let span = debug_span!("Store::update");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let store_before = store.filter(store::store_id.eq(id)).first::<Store>(self.conn)?;
+            let store_updated: Store = diesel::update(store::table)
+                .filter(store::store_id.eq(id))
+                .set(store_update)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "store", id, &store_before, &store_updated)?;
+
+            Ok(store_updated)
+        })
+    }
+
+    fn update_address(&self, id: i32, store_address_update: StoreAddressCreate, actor: &Actor) -> Result<StoreAddress, ApiError> {
+        let span = debug_span!("Store::update_address");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let address_before = store_address
+                .filter(store_address::store_id.eq(id))
+                .first::<StoreAddress>(self.conn)?;
+            let store_updated: StoreAddress = diesel::update(store_address::table)
+                .filter(store_address::store_id.eq(id))
+                .set(store_address_update)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "store_address", store_updated.store_address_id, &address_before, &store_updated)?;
+
+            Ok(store_updated)
+        })
+    }
+
+    fn update_one_hour(&self, id: i32, store_hour: StoreHoursCreate, actor: &Actor) -> Result<StoreHours, ApiError> {
+        let span = debug_span!("Store::update_one_hour");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let store_hour_before = store_hours
+                .filter(store_hours::store_hours_id.eq(id))
+                .filter(store_hours::store_id.eq(store_hour.store_id))
+                .first::<StoreHours>(self.conn)?;
+            let store_hour_updated: StoreHours = diesel::update(store_hours::table)
+                .filter(store_hours::store_hours_id.eq(id))
+                .set(store_hour)
+                .get_result(self.conn)?;
+
+            audit::updated(self.conn, actor, "store_hours", id, &store_hour_before, &store_hour_updated)?;
+
+            Ok(store_hour_updated)
+        })
+    }
+
+    fn update_hours(&self, store_hours_update: Vec<StoreHoursCreate>, actor: &Actor) -> Result<(), ApiError> {
+        let span = debug_span!("Store::update_hours");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            for store_member in store_hours_update {
+                let hours_before = store_hours
+                    .filter(store_hours::store_id.eq(store_member.store_id))
+                    .filter(store_hours::day_of_week.eq(store_member.day_of_week))
+                    .load::<StoreHours>(self.conn)?;
+                let hours_updated = diesel::update(store_hours::table)
+                    .filter(store_hours::store_id.eq(store_member.store_id))
+                    .filter(store_hours::day_of_week.eq(store_member.day_of_week))
+                    .set(store_member).get_results::<StoreHours>(self.conn)?;
+
+                for (hour_before, hour_updated) in hours_before.iter().zip(hours_updated.iter()) {
+                    audit::updated(self.conn, actor, "store_hours", hour_updated.store_hours_id, hour_before, hour_updated)?;
+                }
+            }
+
+            Ok(())
+        })
+    }
+
+    fn delete(&self, id: i32, actor: &Actor) -> Result<usize, ApiError> {
+        let span = debug_span!("Store::delete");
+        let _entered = span.enter();
+
+        self.conn.transaction::<_, ApiError, _>(|| {
+            let store_before = store.filter(store::store_id.eq(id)).first::<Store>(self.conn).optional()?;
+
+            // also make sure to delete other store data
+            let hours_deleted = diesel::delete(store_hours::table)
+                .filter(store_hours::store_id
+                .eq(id))
+                .get_results::<StoreHours>(self.conn)?;
+            let addresses_deleted = diesel::delete(store_address::table)
+                .filter(store_address::store_id
+                .eq(id))
+                .get_results::<StoreAddress>(self.conn)?;
+
+            let res = diesel::delete(
+                    store::table
+                        .filter(store::store_id
+                        .eq(id))
+                )
+                .execute(self.conn)?;
+
+            if let Some(store_before) = store_before {
+                audit::deleted(self.conn, actor, "store", id, &json!({
+                    "store": store_before,
+                    "address": addresses_deleted,
+                    "hours": hours_deleted
+                }))?;
+            }
+
+            Ok(res)
+        })
+    }
+
+    fn repair_hours(&self) -> QueryResult<usize> {
+        let span = debug_span!("Store::repair_hours");
+        let _entered = span.enter();
+
+        let store_ids = store.select(store::store_id).load::<i32>(self.conn)?;
+        let existing = store_hours
+            .select((store_hours::store_id, store_hours::day_of_week))
+            .load::<(i32, i32)>(self.conn)?;
+
+        let mut hours_list: Vec<StoreHoursCreate> = vec![];
+
+        for current_store_id in store_ids {
+            for x in 0..7 {
+                if existing.contains(&(current_store_id, x)) {
+                    continue;
+                }
+
+                hours_list.push(StoreHoursCreate {
+                    store_id: current_store_id,
+                    day_of_week: x,
+                    start_time: None,
+                    end_time: None,
+                });
+            }
+        }
+
+        if hours_list.is_empty() {
+            return Ok(0);
+        }
+
+        diesel::insert_into(store_hours::table).values(hours_list).execute(self.conn)
+    }
+}
\ No newline at end of file
@@ -1,83 +1,238 @@
 use crate::api_error::ApiError;
-use crate::store::{Store, StoreCreate, StoreId, StoreHourId ,StoreHoursCreate, StoreAddressCreate};
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::store::{Store, StoreCreate, StoreId, StoreQuery, StoreHourId, StoreHoursCreate, StoreAddressCreate};
+use crate::validation::Valid;
 use actix_web::{get, post, put, delete, web, HttpResponse};
 use serde_json::json;
 
+#[utoipa::path(
+    get,
+    path = "/store",
+    tag = "store",
+    operation_id = "legacy_store_find_all",
+    params(
+        StoreQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Stores", body = [Store], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
 #[get("/store")]
-async fn find_all() -> Result<HttpResponse, ApiError> {
-    let store = Store::find_all()?;
-    Ok(HttpResponse::Ok().json(store))
+async fn find_all(query: web::Query<StoreQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (store, total) = Store::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, store))
 }
 
+#[utoipa::path(
+    get,
+    path = "/store_info/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_find_all_info",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store with its address and hours", body = FullStore)
+    )
+)]
 #[get("/store_info/{store_id}")]
 async fn find_all_info(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
     let store_info = Store::find_all_data(id.store_id)?;
     Ok(HttpResponse::Ok().json(store_info))
 }
 
+#[utoipa::path(
+    get,
+    path = "/store_hours",
+    tag = "store",
+    operation_id = "legacy_store_find_all_store_hours",
+    params(
+        StoreQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Stores with their weekly hours", body = [StoreWithHours], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
 #[get("/store_hours")]
-async fn find_all_store_hours() -> Result<HttpResponse, ApiError> {
-    let store = Store::find_all_store_hours()?;
-    Ok(HttpResponse::Ok().json(store))
+async fn find_all_store_hours(query: web::Query<StoreQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (store, total) = Store::find_all_store_hours(query.into_inner(), &page)?;
+    Ok(page.respond(total, store))
 }
 
+#[utoipa::path(
+    get,
+    path = "/store_hours/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_find_store_hours",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store with its weekly hours", body = StoreWithHours)
+    )
+)]
 #[get("/store_hours/{store_id}")]
 async fn find_store_hours(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
     let store = Store::find_store_hours(id.store_id)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    get,
+    path = "/store/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_find",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store", body = Store)
+    )
+)]
 #[get("/store/{store_id}")]
 async fn find(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
     let store = Store::find(id.store_id)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    get,
+    path = "/store_address/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_find_address",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store address", body = StoreAddress)
+    )
+)]
 #[get("/store_address/{store_id}")]
 async fn find_address(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
     let store = Store::find_address(id.store_id)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    post,
+    path = "/store",
+    tag = "store",
+    operation_id = "legacy_store_create",
+    request_body = StoreCreate,
+    responses(
+        (status = 200, description = "Created store", body = Store)
+    ),
+    security(("staff_id" = []))
+)]
 #[post("/store")]
-async fn create(store: web::Json<StoreCreate>) -> Result<HttpResponse, ApiError> {
-    let store = Store::create(store.into_inner())?;
+async fn create(store: Valid<StoreCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store = Store::create(store.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    post,
+    path = "/store_address",
+    tag = "store",
+    operation_id = "legacy_store_create_address",
+    request_body = StoreAddressCreate,
+    responses(
+        (status = 200, description = "Created store address", body = StoreAddress)
+    ),
+    security(("staff_id" = []))
+)]
 #[post("/store_address")]
-async fn create_address(store_address: web::Json<StoreAddressCreate>) -> Result<HttpResponse, ApiError> {
-    let store_address = Store::create_address(store_address.into_inner())?;
+async fn create_address(store_address: Valid<StoreAddressCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store_address = Store::create_address(store_address.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(store_address))
 }
 
+#[utoipa::path(
+    put,
+    path = "/store/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_update",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = StoreCreate,
+    responses(
+        (status = 200, description = "Updated store", body = Store)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/store/{store_id}")]
-async fn update(id: web::Path<StoreId>, store: web::Json<StoreCreate>) -> Result<HttpResponse, ApiError> {
-    let store = Store::update(id.store_id, store.into_inner())?;
+async fn update(id: web::Path<StoreId>, store: Valid<StoreCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store = Store::update(id.store_id, store.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    put,
+    path = "/store_address/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_update_address",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = StoreAddressCreate,
+    responses(
+        (status = 200, description = "Updated store address", body = StoreAddress)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/store_address/{store_id}")]
-async fn update_address(id: web::Path<StoreId>, store_address: web::Json<StoreAddressCreate>) -> Result<HttpResponse, ApiError> {
-    let store_address = Store::update_address(id.store_id, store_address.into_inner())?;
+async fn update_address(id: web::Path<StoreId>, store_address: Valid<StoreAddressCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store_address = Store::update_address(id.store_id, store_address.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(store_address))
 }
 
+#[utoipa::path(
+    put,
+    path = "/store_hours",
+    tag = "store",
+    operation_id = "legacy_store_update_hours",
+    request_body = [StoreHoursCreate],
+    responses(
+        (status = 200, description = "Hours updated")
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/store_hours")]
-async fn update_hours(store_hours: web::Json<Vec<StoreHoursCreate>>) -> Result<HttpResponse, ApiError> {
-    let store = Store::update_hours(store_hours.into_inner())?;
+async fn update_hours(store_hours: Valid<Vec<StoreHoursCreate>>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store = Store::update_hours(store_hours.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    put,
+    path = "/store_hours/{store_hour_id}",
+    tag = "store",
+    operation_id = "legacy_store_update_one_hour",
+    params(("store_hour_id" = i32, Path, description = "Store hours row id")),
+    request_body = StoreHoursCreate,
+    responses(
+        (status = 200, description = "Updated hours row", body = StoreHours)
+    ),
+    security(("staff_id" = []))
+)]
 #[put("/store_hours/{store_hour_id}")]
-async fn update_one_hour(id: web::Path<StoreHourId>, store_hours: web::Json<StoreHoursCreate>) -> Result<HttpResponse, ApiError> {
-    let store = Store::update_one_hour(id.store_hour_id, store_hours.into_inner())?;
+async fn update_one_hour(id: web::Path<StoreHourId>, store_hours: Valid<StoreHoursCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store = Store::update_one_hour(id.store_hour_id, store_hours.into_inner(), &actor)?;
     Ok(HttpResponse::Ok().json(store))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/store/{store_id}",
+    tag = "store",
+    operation_id = "legacy_store_delete",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Number of stores deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
 #[delete("/store/{store_id}")]
-async fn delete(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
-    let store_deleted = Store::delete(id.store_id)?;
+async fn delete(id: web::Path<StoreId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store_deleted = Store::delete(id.store_id, &actor)?;
     Ok(HttpResponse::Ok().json(json!({ "deleted": store_deleted })))
 }
 
@@ -0,0 +1,273 @@
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::pagination::{PageParams, Pagination};
+use crate::store::{Store, StoreCreate, StoreId, StoreQuery, StoreHourPath, StoreHoursCreate, StoreAddressCreate};
+use crate::validation::Valid;
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores",
+    tag = "store",
+    operation_id = "store_find_all",
+    params(
+        StoreQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Stores", body = [Store], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/stores")]
+async fn find_all(query: web::Query<StoreQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (store, total) = Store::find_all(query.into_inner(), &page)?;
+    Ok(page.respond(total, store))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/stores",
+    tag = "store",
+    operation_id = "store_create",
+    request_body = StoreCreate,
+    responses(
+        (status = 201, description = "Created store", body = Store)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/stores")]
+async fn create(store: Valid<StoreCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store = Store::create(store.into_inner(), &actor)?;
+    Ok(HttpResponse::Created().json(store))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores/hours",
+    tag = "store",
+    operation_id = "store_find_all_hours",
+    params(
+        StoreQuery,
+        PageParams
+    ),
+    responses(
+        (status = 200, description = "Stores with their weekly hours", body = [StoreWithHours], headers(
+            ("X-Total-Count" = i64, description = "Total matching rows"),
+            ("Link" = String, description = "first, prev, next and last page links")
+        ))
+    )
+)]
+#[get("/stores/hours")]
+async fn find_all_hours(query: web::Query<StoreQuery>, page: Pagination) -> Result<HttpResponse, ApiError> {
+    let (store, total) = Store::find_all_store_hours(query.into_inner(), &page)?;
+    Ok(page.respond(total, store))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores/{store_id}",
+    tag = "store",
+    operation_id = "store_find",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store", body = Store)
+    )
+)]
+#[get("/stores/{store_id}")]
+async fn find(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
+    let store = Store::find(id.store_id)?;
+    Ok(HttpResponse::Ok().json(store))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/stores/{store_id}",
+    tag = "store",
+    operation_id = "store_update",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = StoreCreate,
+    responses(
+        (status = 200, description = "Updated store", body = Store)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/stores/{store_id}")]
+async fn update(id: web::Path<StoreId>, store: Valid<StoreCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store = Store::update(id.store_id, store.into_inner(), &actor)?;
+    Ok(HttpResponse::Ok().json(store))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/stores/{store_id}",
+    tag = "store",
+    operation_id = "store_delete",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Number of stores deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/stores/{store_id}")]
+async fn delete(id: web::Path<StoreId>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let store_deleted = Store::delete(id.store_id, &actor)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": store_deleted })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores/{store_id}/details",
+    tag = "store",
+    operation_id = "store_find_details",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store with its address and hours", body = FullStore)
+    )
+)]
+#[get("/stores/{store_id}/details")]
+async fn find_details(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
+    let store_info = Store::find_all_data(id.store_id)?;
+    Ok(HttpResponse::Ok().json(store_info))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores/{store_id}/address",
+    tag = "store",
+    operation_id = "store_find_address",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store address", body = StoreAddress)
+    )
+)]
+#[get("/stores/{store_id}/address")]
+async fn find_address(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
+    let store_address = Store::find_address(id.store_id)?;
+    Ok(HttpResponse::Ok().json(store_address))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/stores/{store_id}/address",
+    tag = "store",
+    operation_id = "store_create_address",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = StoreAddressCreate,
+    responses(
+        (status = 201, description = "Created store address", body = StoreAddress)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/stores/{store_id}/address")]
+async fn create_address(id: web::Path<StoreId>, store_address: Valid<StoreAddressCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let mut store_address = store_address.into_inner();
+    store_address.store_id = id.store_id;
+
+    let store_address = Store::create_address(store_address, &actor)?;
+    Ok(HttpResponse::Created().json(store_address))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/stores/{store_id}/address",
+    tag = "store",
+    operation_id = "store_update_address",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = StoreAddressCreate,
+    responses(
+        (status = 200, description = "Updated store address", body = StoreAddress)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/stores/{store_id}/address")]
+async fn update_address(id: web::Path<StoreId>, store_address: Valid<StoreAddressCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let mut store_address = store_address.into_inner();
+    store_address.store_id = id.store_id;
+
+    let store_address = Store::update_address(id.store_id, store_address, &actor)?;
+    Ok(HttpResponse::Ok().json(store_address))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/stores/{store_id}/hours",
+    tag = "store",
+    operation_id = "store_find_hours",
+    params(("store_id" = i32, Path, description = "Store id")),
+    responses(
+        (status = 200, description = "Store with its weekly hours", body = StoreWithHours)
+    )
+)]
+#[get("/stores/{store_id}/hours")]
+async fn find_hours(id: web::Path<StoreId>) -> Result<HttpResponse, ApiError> {
+    let store = Store::find_store_hours(id.store_id)?;
+    Ok(HttpResponse::Ok().json(store))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/stores/{store_id}/hours",
+    tag = "store",
+    operation_id = "store_update_hours",
+    params(("store_id" = i32, Path, description = "Store id")),
+    request_body = [StoreHoursCreate],
+    responses(
+        (status = 200, description = "Store with its updated weekly hours", body = StoreWithHours)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/stores/{store_id}/hours")]
+async fn update_hours(id: web::Path<StoreId>, store_hours: Valid<Vec<StoreHoursCreate>>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let mut store_hours = store_hours.into_inner();
+    store_hours.iter_mut().for_each(|hour| hour.store_id = id.store_id);
+
+    Store::update_hours(store_hours, &actor)?;
+
+    let store = Store::find_store_hours(id.store_id)?;
+    Ok(HttpResponse::Ok().json(store))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/stores/{store_id}/hours/{store_hour_id}",
+    tag = "store",
+    operation_id = "store_update_one_hour",
+    params(
+        ("store_id" = i32, Path, description = "Store id"),
+        ("store_hour_id" = i32, Path, description = "Store hours row id")
+    ),
+    request_body = StoreHoursCreate,
+    responses(
+        (status = 200, description = "Updated hours row", body = StoreHours)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/stores/{store_id}/hours/{store_hour_id}")]
+async fn update_one_hour(path: web::Path<StoreHourPath>, store_hours: Valid<StoreHoursCreate>, actor: Actor) -> Result<HttpResponse, ApiError> {
+    let mut store_hours = store_hours.into_inner();
+    store_hours.store_id = path.store_id;
+
+    let store = Store::update_one_hour(path.store_hour_id, store_hours, &actor)?;
+    Ok(HttpResponse::Ok().json(store))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(create);
+    // registered ahead of /stores/{store_id} so "hours" is not parsed as an id
+    cfg.service(find_all_hours);
+    cfg.service(find);
+    cfg.service(update);
+    cfg.service(delete);
+    cfg.service(find_details);
+    cfg.service(find_address);
+    cfg.service(create_address);
+    cfg.service(update_address);
+    cfg.service(find_hours);
+    cfg.service(update_hours);
+    cfg.service(update_one_hour);
+}
\ No newline at end of file
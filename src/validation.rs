@@ -0,0 +1,200 @@
+use crate::api_error::ApiError;
+use actix_web::{dev, web, Error, FromRequest, HttpRequest};
+use chrono::NaiveTime;
+use futures::future::{FutureExt, LocalBoxFuture};
+use serde::de::DeserializeOwned;
+use std::collections::BTreeMap;
+
+/// Request bodies that can check their own fields before reaching a model.
+pub trait Validate {
+    fn validate(&self, errors: &mut FieldErrors);
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self, errors: &mut FieldErrors) {
+        for (index, item) in self.iter().enumerate() {
+            errors.nested(&format!("[{}]", index), item);
+        }
+    }
+}
+
+/// Error messages keyed by field path, e.g. `variants[0].price`.
+#[derive(Default)]
+pub struct FieldErrors {
+    errors: BTreeMap<String, Vec<String>>
+}
+
+impl FieldErrors {
+    pub fn add(&mut self, field: &str, message: &str) {
+        self.errors
+            .entry(field.to_string())
+            .or_default()
+            .push(message.to_string());
+    }
+
+    pub fn nested<T: Validate>(&mut self, prefix: &str, value: &T) {
+        let mut inner = FieldErrors::default();
+        value.validate(&mut inner);
+
+        for (field, messages) in inner.errors {
+            let path = match field.starts_with('[') {
+                true => format!("{}{}", prefix, field),
+                false => format!("{}.{}", prefix, field),
+            };
+
+            self.errors.entry(path).or_default().extend(messages);
+        }
+    }
+
+    pub fn required(&mut self, field: &str, value: &str) {
+        if value.trim().is_empty() {
+            self.add(field, "is required");
+        }
+    }
+
+    pub fn email(&mut self, field: &str, value: &str) {
+        let mut parts = value.splitn(2, '@');
+        let local = parts.next().unwrap_or_default();
+        let domain = parts.next().unwrap_or_default();
+
+        let valid = !local.is_empty()
+            && domain.contains('.')
+            && !domain.starts_with('.')
+            && !domain.ends_with('.')
+            && !domain.contains('@')
+            && !value.chars().any(char::is_whitespace);
+
+        if !valid {
+            self.add(field, "must be a valid email address");
+        }
+    }
+
+    pub fn phone(&mut self, field: &str, value: &str) {
+        let digits = value.chars().filter(char::is_ascii_digit).count();
+        let allowed = value.chars().all(|c| c.is_ascii_digit() || " +-().".contains(c));
+
+        if !allowed || !(7..=15).contains(&digits) {
+            self.add(field, "must be a phone number");
+        }
+    }
+
+    pub fn zip(&mut self, field: &str, value: &str) {
+        let bytes = value.as_bytes();
+        let digits = |part: &[u8]| part.iter().all(u8::is_ascii_digit);
+
+        let valid = match bytes.len() {
+            5 => digits(bytes),
+            10 => digits(&bytes[..5]) && bytes[5] == b'-' && digits(&bytes[6..]),
+            _ => false,
+        };
+
+        if !valid {
+            self.add(field, "must be a 5 digit ZIP code or ZIP+4");
+        }
+    }
+
+    /// An absolute `http://` or `https://` URL with a host.
+    pub fn http_url(&mut self, field: &str, value: &str) {
+        let host = value.strip_prefix("https://").or_else(|| value.strip_prefix("http://")).unwrap_or_default();
+        let valid = !host.is_empty() && !host.starts_with('/') && !value.chars().any(char::is_whitespace);
+
+        if !valid {
+            self.add(field, "must be an http:// or https:// URL");
+        }
+    }
+
+    pub fn flag(&mut self, field: &str, value: i32) {
+        if value != 0 && value != 1 {
+            self.add(field, "must be 0 or 1");
+        }
+    }
+
+    pub fn non_negative(&mut self, field: &str, value: f64) {
+        if !value.is_finite() || value < 0.0 {
+            self.add(field, "must not be negative");
+        }
+    }
+
+    /// Customers per slot, 1 for one-to-one services.
+    pub fn capacity(&mut self, field: &str, value: i32) {
+        if !(1..=500).contains(&value) {
+            self.add(field, "must be between 1 and 500");
+        }
+    }
+
+    /// A resource type such as `treatment_room`, matched exactly between
+    /// resources and the services that need them.
+    pub fn resource_type(&mut self, field: &str, value: &str) {
+        let valid = !value.is_empty() && value.bytes().all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_');
+
+        if !valid {
+            self.add(field, "must be lowercase letters, digits and underscores, e.g. treatment_room");
+        }
+    }
+
+    pub fn day_of_week(&mut self, field: &str, value: i32) {
+        if !(0..=6).contains(&value) {
+            self.add(field, "must be between 0 (Sunday) and 6 (Saturday)");
+        }
+    }
+
+    /// Both times set with `end` after `start`, or both left empty for a day off.
+    pub fn time_range(&mut self, start: Option<NaiveTime>, end: Option<NaiveTime>) {
+        match (start, end) {
+            (Some(start_time), Some(end_time)) if end_time <= start_time => {
+                self.add("end_time", "must be after start_time");
+            },
+            (Some(_), None) => self.add("end_time", "is required when start_time is set"),
+            (None, Some(_)) => self.add("start_time", "is required when end_time is set"),
+            _ => {},
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn into_map(self) -> BTreeMap<String, Vec<String>> {
+        self.errors
+    }
+
+    pub fn into_result(self) -> Result<(), ApiError> {
+        match self.errors.is_empty() {
+            true => Ok(()),
+            false => Err(ApiError::validation(self.errors)),
+        }
+    }
+}
+
+/// JSON body extractor that rejects invalid payloads with a 422 before the
+/// handler runs.
+pub struct Valid<T>(pub T);
+
+impl<T> Valid<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> FromRequest for Valid<T>
+where
+    T: DeserializeOwned + Validate + 'static,
+{
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Error>>;
+    type Config = web::JsonConfig;
+
+    fn from_request(req: &HttpRequest, payload: &mut dev::Payload) -> Self::Future {
+        web::Json::<T>::from_request(req, payload)
+            .map(|body| {
+                let body = body?.into_inner();
+
+                let mut errors = FieldErrors::default();
+                body.validate(&mut errors);
+                errors.into_result()?;
+
+                Ok(Valid(body))
+            })
+            .boxed_local()
+    }
+}
\ No newline at end of file
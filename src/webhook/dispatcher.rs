@@ -0,0 +1,121 @@
+use crate::config::CONFIG;
+use crate::webhook::{WebhookDelivery, WebhookSubscription};
+use chrono::Duration;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc};
+use std::thread;
+use std::time;
+
+type HmacSha256 = Hmac<Sha256>;
+
+const BATCH_SIZE: i64 = 50;
+
+/// How long a claimed batch is kept from other dispatchers, enough for every
+/// delivery in it to hit the connect and read timeouts.
+const CLAIM_LEASE_MINUTES: i64 = 15;
+
+/// Hex encoded HMAC-SHA256 of `body`, sent as `X-Webhook-Signature: sha256=<hex>`.
+pub fn sign(secret: &str, body: &str) -> String {
+    let mut mac = HmacSha256::new_varkey(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body.as_bytes());
+
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Handle to the delivery thread, used to stop it when the server shuts down.
+pub struct Dispatcher {
+    stopping: Arc<AtomicBool>,
+    thread: thread::Thread,
+    done: mpsc::Receiver<()>
+}
+
+impl Dispatcher {
+    /// Lets the delivery in flight finish, leaves the rest queued for the
+    /// next start and waits up to `timeout` for the thread to exit. Returns
+    /// false if it was still busy when the timeout ran out.
+    pub fn stop(self, timeout: time::Duration) -> bool {
+        self.stopping.store(true, Ordering::SeqCst);
+        self.thread.unpark();
+
+        !matches!(self.done.recv_timeout(timeout), Err(mpsc::RecvTimeoutError::Timeout))
+    }
+}
+
+/// Spawns the background thread that sends queued deliveries.
+pub fn start() -> Dispatcher {
+    let poll_seconds = CONFIG.webhook.poll_seconds;
+    let stopping = Arc::new(AtomicBool::new(false));
+    let (done_tx, done) = mpsc::channel();
+
+    let thread_stopping = stopping.clone();
+    let handle = thread::spawn(move || {
+        while !thread_stopping.load(Ordering::SeqCst) {
+            if let Err(err) = deliver_due(&thread_stopping) {
+                error!("Webhook dispatch failed: {}", err);
+            }
+
+            // woken early by Dispatcher::stop
+            thread::park_timeout(time::Duration::from_secs(poll_seconds));
+        }
+
+        let _ = done_tx.send(());
+    });
+
+    Dispatcher { stopping, thread: handle.thread().clone(), done }
+}
+
+pub fn deliver_due(stopping: &AtomicBool) -> diesel::QueryResult<usize> {
+    let max_attempts = CONFIG.webhook.max_attempts;
+    let base_delay = Duration::seconds(CONFIG.webhook.retry_base_seconds);
+
+    let due = WebhookDelivery::claim_due(BATCH_SIZE, Duration::minutes(CLAIM_LEASE_MINUTES))?;
+    let mut delivered = 0;
+
+    for (index, (delivery, subscription)) in due.iter().enumerate() {
+        if stopping.load(Ordering::SeqCst) {
+            let unsent: Vec<i32> = due[index..].iter().map(|(delivery, _)| delivery.webhook_delivery_id).collect();
+            WebhookDelivery::release(&unsent)?;
+            break;
+        }
+
+        match send(delivery, subscription) {
+            Ok(status_code) => {
+                WebhookDelivery::record_success(delivery.webhook_delivery_id, status_code)?;
+            },
+            Err((status_code, failure)) => {
+                warn!("Webhook delivery {} failed: {}", delivery.webhook_delivery_id, failure);
+                delivery.record_failure(status_code, failure, max_attempts, base_delay)?;
+            }
+        }
+
+        delivered += 1;
+    }
+
+    Ok(delivered)
+}
+
+fn send(delivery: &WebhookDelivery, subscription: &WebhookSubscription) -> Result<i32, (Option<i32>, String)> {
+    let body = delivery.payload.to_string();
+
+    let response = ureq::post(&subscription.url)
+        .set("Content-Type", "application/json")
+        .set("X-Webhook-Event", &delivery.event_type)
+        .set("X-Webhook-Delivery", &delivery.webhook_delivery_id.to_string())
+        .set("X-Webhook-Signature", &format!("sha256={}", sign(&subscription.secret, &body)))
+        .timeout_connect(5_000)
+        .timeout_read(10_000)
+        .send_string(&body);
+
+    if let Some(err) = response.synthetic_error() {
+        return Err((None, err.to_string()));
+    }
+
+    let status_code = i32::from(response.status());
+    match response.ok() {
+        true => Ok(status_code),
+        false => Err((Some(status_code), format!("Endpoint returned {}", status_code))),
+    }
+}
\ No newline at end of file
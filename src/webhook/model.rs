@@ -0,0 +1,417 @@
+use crate::db;
+use crate::api_error::ApiError;
+use crate::{
+    schema::webhook_subscription::{self, dsl::*},
+    schema::webhook_delivery::{self, dsl::*}
+};
+use chrono::{Duration, NaiveDateTime, Utc};
+use crate::diesel::prelude::*;
+use crate::validation::{FieldErrors, Validate};
+use diesel::pg::PgConnection;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use tracing::debug_span;
+
+pub const APPOINTMENT_CREATED: &str = "appointment.created";
+pub const APPOINTMENT_RESCHEDULED: &str = "appointment.rescheduled";
+pub const APPOINTMENT_CANCELLED: &str = "appointment.cancelled";
+pub const APPOINTMENT_COMPLETED: &str = "appointment.completed";
+pub const STAFF_CREATED: &str = "staff.created";
+pub const STAFF_UPDATED: &str = "staff.updated";
+pub const STAFF_DELETED: &str = "staff.deleted";
+pub const STAFF_RESTORED: &str = "staff.restored";
+pub const STAFF_HOURS_UPDATED: &str = "staff.hours_updated";
+pub const STAFF_SERVICES_UPDATED: &str = "staff.services_updated";
+pub const SERVICE_CREATED: &str = "service.created";
+pub const SERVICE_UPDATED: &str = "service.updated";
+pub const SERVICE_DELETED: &str = "service.deleted";
+pub const SERVICE_RESTORED: &str = "service.restored";
+
+pub const EVENT_TYPES: [&str; 14] = [
+    APPOINTMENT_CREATED,
+    APPOINTMENT_RESCHEDULED,
+    APPOINTMENT_CANCELLED,
+    APPOINTMENT_COMPLETED,
+    STAFF_CREATED,
+    STAFF_UPDATED,
+    STAFF_DELETED,
+    STAFF_RESTORED,
+    STAFF_HOURS_UPDATED,
+    STAFF_SERVICES_UPDATED,
+    SERVICE_CREATED,
+    SERVICE_UPDATED,
+    SERVICE_DELETED,
+    SERVICE_RESTORED
+];
+
+pub const DELIVERY_PENDING: &str = "pending";
+pub const DELIVERY_DELIVERED: &str = "delivered";
+pub const DELIVERY_FAILED: &str = "failed";
+
+#[derive(Deserialize)]
+pub struct WebhookSubscriptionId {
+    pub webhook_subscription_id: i32
+}
+
+#[derive(Deserialize)]
+pub struct WebhookDeliveryId {
+    pub webhook_delivery_id: i32
+}
+
+#[derive(Serialize, Deserialize, AsChangeset, Insertable, ToSchema)]
+#[table_name = "webhook_subscription"]
+pub struct WebhookSubscriptionCreate {
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub is_active: i32
+}
+
+#[derive(Identifiable, Serialize, Deserialize, Queryable, Clone, Debug, ToSchema)]
+#[primary_key(webhook_subscription_id)]
+#[table_name = "webhook_subscription"]
+pub struct WebhookSubscription {
+    pub webhook_subscription_id: i32,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub is_active: i32,
+    pub created_at: NaiveDateTime
+}
+
+#[derive(Insertable)]
+#[table_name = "webhook_delivery"]
+pub struct WebhookDeliveryCreate {
+    pub webhook_subscription_id: i32,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: String,
+    pub next_attempt_at: Option<NaiveDateTime>
+}
+
+#[derive(Identifiable, Associations, Serialize, Deserialize, Queryable, Debug, ToSchema)]
+#[belongs_to(WebhookSubscription)]
+#[primary_key(webhook_delivery_id)]
+#[table_name = "webhook_delivery"]
+pub struct WebhookDelivery {
+    pub webhook_delivery_id: i32,
+    pub webhook_subscription_id: i32,
+    pub event_type: String,
+    pub payload: Value,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: Option<NaiveDateTime>,
+    pub last_status_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime
+}
+
+/// Queues `event` for every active subscription listening to it.
+///
+/// Webhooks are a side effect of the change that triggered them, so a
+/// failure to queue is logged rather than failing the caller.
+pub fn emit(conn: &PgConnection, event: &str, data: Value) {
+    if let Err(err) = WebhookDelivery::queue(conn, event, data) {
+        error!("Failed to queue {} webhooks: {}", event, err);
+    }
+}
+
+impl Validate for WebhookSubscriptionCreate {
+    fn validate(&self, errors: &mut FieldErrors) {
+        errors.http_url("url", &self.url);
+        errors.required("secret", &self.secret);
+        errors.flag("is_active", self.is_active);
+    }
+}
+
+fn check_event_types(types: &[String]) -> Result<(), ApiError> {
+    for event in types {
+        if !EVENT_TYPES.contains(&event.as_str()) {
+            return Err(ApiError::coded(400, "webhook.unknown_event_type", format!("Unknown event type: {}", event)));
+        }
+    }
+
+    Ok(())
+}
+
+impl WebhookSubscription {
+    pub fn find_all() -> QueryResult<Vec<Self>> {
+        let span = debug_span!("WebhookSubscription::find_all");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        webhook_subscription
+            .order(webhook_subscription::webhook_subscription_id.asc())
+            .load::<Self>(&conn)
+    }
+
+    pub fn find(id: i32) -> QueryResult<Self> {
+        let span = debug_span!("WebhookSubscription::find");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        webhook_subscription
+            .filter(webhook_subscription::webhook_subscription_id.eq(id))
+            .first::<Self>(&conn)
+    }
+
+    pub fn create(subscription_create: WebhookSubscriptionCreate) -> Result<Self, ApiError> {
+        let span = debug_span!("WebhookSubscription::create");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        check_event_types(&subscription_create.event_types)?;
+
+        let subscription_created = diesel::insert_into(webhook_subscription::table)
+            .values(subscription_create)
+            .get_result(&conn)?;
+
+        Ok(subscription_created)
+    }
+
+    pub fn update(id: i32, subscription_update: WebhookSubscriptionCreate) -> Result<Self, ApiError> {
+        let span = debug_span!("WebhookSubscription::update");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        check_event_types(&subscription_update.event_types)?;
+
+        let subscription_updated = diesel::update(webhook_subscription::table)
+            .filter(webhook_subscription::webhook_subscription_id.eq(id))
+            .set(subscription_update)
+            .get_result(&conn)?;
+
+        Ok(subscription_updated)
+    }
+
+    pub fn delete(id: i32) -> Result<usize, ApiError> {
+        let span = debug_span!("WebhookSubscription::delete");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        // also make sure to delete the delivery log
+        diesel::delete(webhook_delivery::table)
+            .filter(webhook_delivery::webhook_subscription_id.eq(id))
+            .execute(&conn)?;
+
+        let res = diesel::delete(
+                webhook_subscription::table
+                    .filter(webhook_subscription::webhook_subscription_id.eq(id))
+            )
+            .execute(&conn)?;
+
+        Ok(res)
+    }
+}
+
+impl WebhookDelivery {
+    pub fn find_by_subscription(id: i32) -> QueryResult<Vec<Self>> {
+        let span = debug_span!("WebhookDelivery::find_by_subscription");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        webhook_delivery
+            .filter(webhook_delivery::webhook_subscription_id.eq(id))
+            .order(webhook_delivery::webhook_delivery_id.desc())
+            .load::<Self>(&conn)
+    }
+
+    pub fn queue(conn: &PgConnection, event: &str, data: Value) -> QueryResult<usize> {
+        let span = debug_span!("WebhookDelivery::queue");
+        let _entered = span.enter();
+
+        let subscriptions = webhook_subscription
+            .filter(webhook_subscription::is_active.eq(1))
+            .filter(webhook_subscription::event_types.contains(vec![event]))
+            .load::<WebhookSubscription>(conn)?;
+
+        let now = Utc::now().naive_utc();
+        let payload_body = json!({
+            "event": event,
+            "created_at": now,
+            "data": data
+        });
+
+        let deliveries: Vec<WebhookDeliveryCreate> = subscriptions
+            .iter()
+            .map(|subscription| WebhookDeliveryCreate {
+                webhook_subscription_id: subscription.webhook_subscription_id,
+                event_type: event.to_string(),
+                payload: payload_body.clone(),
+                status: DELIVERY_PENDING.to_string(),
+                next_attempt_at: Some(now)
+            })
+            .collect();
+
+        diesel::insert_into(webhook_delivery::table)
+            .values(deliveries)
+            .execute(conn)
+    }
+
+    /// Claims up to `limit` due deliveries by pushing their next attempt out
+    /// by `lease`, so another dispatcher skips them while they are sent. A
+    /// claim that is never recorded, because the process died mid-send,
+    /// simply comes due again once the lease runs out.
+    pub fn claim_due(limit: i64, lease: Duration) -> QueryResult<Vec<(Self, WebhookSubscription)>> {
+        let span = debug_span!("WebhookDelivery::claim_due");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let claimed = conn.transaction::<_, diesel::result::Error, _>(|| {
+            let now = Utc::now().naive_utc();
+            let due = webhook_delivery
+                .filter(webhook_delivery::status.eq(DELIVERY_PENDING))
+                .filter(webhook_delivery::next_attempt_at.le(now))
+                .order(webhook_delivery::next_attempt_at.asc())
+                .limit(limit)
+                .for_update()
+                .skip_locked()
+                .load::<Self>(&conn)?;
+
+            let due_ids: Vec<i32> = due.iter().map(|delivery| delivery.webhook_delivery_id).collect();
+            diesel::update(webhook_delivery::table)
+                .filter(webhook_delivery::webhook_delivery_id.eq_any(&due_ids))
+                .set(webhook_delivery::next_attempt_at.eq(now + lease))
+                .execute(&conn)?;
+
+            Ok(due)
+        })?;
+
+        let subscription_ids: Vec<i32> = claimed.iter().map(|delivery| delivery.webhook_subscription_id).collect();
+        let subscriptions: HashMap<i32, WebhookSubscription> = webhook_subscription
+            .filter(webhook_subscription::webhook_subscription_id.eq_any(subscription_ids))
+            .load::<WebhookSubscription>(&conn)?
+            .into_iter()
+            .map(|subscription| (subscription.webhook_subscription_id, subscription))
+            .collect();
+
+        Ok(claimed
+            .into_iter()
+            .filter_map(|delivery| {
+                let subscription = subscriptions.get(&delivery.webhook_subscription_id)?;
+                Some((delivery, subscription.clone()))
+            })
+            .collect())
+    }
+
+    /// Hands claimed deliveries that were not sent back to the queue.
+    pub fn release(ids: &[i32]) -> QueryResult<usize> {
+        let span = debug_span!("WebhookDelivery::release");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        diesel::update(webhook_delivery::table)
+            .filter(webhook_delivery::webhook_delivery_id.eq_any(ids))
+            .filter(webhook_delivery::status.eq(DELIVERY_PENDING))
+            .set(webhook_delivery::next_attempt_at.eq(Utc::now().naive_utc()))
+            .execute(&conn)
+    }
+
+    pub fn record_success(id: i32, status_code: i32) -> QueryResult<Self> {
+        let span = debug_span!("WebhookDelivery::record_success");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        diesel::update(webhook_delivery::table)
+            .filter(webhook_delivery::webhook_delivery_id.eq(id))
+            .set((
+                webhook_delivery::status.eq(DELIVERY_DELIVERED),
+                webhook_delivery::attempts.eq(webhook_delivery::attempts + 1),
+                webhook_delivery::next_attempt_at.eq(None::<NaiveDateTime>),
+                webhook_delivery::last_status_code.eq(status_code),
+                webhook_delivery::last_error.eq(None::<String>),
+                webhook_delivery::updated_at.eq(Utc::now().naive_utc())
+            ))
+            .get_result(&conn)
+    }
+
+    /// Schedules the next attempt with exponential backoff, or gives up
+    /// once `max_attempts` have been made.
+    pub fn record_failure(&self, status_code: Option<i32>, failure: String, max_attempts: i32, base_delay: Duration) -> QueryResult<Self> {
+        let span = debug_span!("WebhookDelivery::record_failure");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let attempts_made = self.attempts + 1;
+        let now = Utc::now().naive_utc();
+        let (next_status, next_attempt) = if attempts_made >= max_attempts {
+            (DELIVERY_FAILED, None)
+        } else {
+            let backoff = base_delay * 2i32.pow((attempts_made - 1).min(10) as u32);
+            (DELIVERY_PENDING, Some(now + backoff.min(Duration::hours(6))))
+        };
+
+        diesel::update(webhook_delivery::table)
+            .filter(webhook_delivery::webhook_delivery_id.eq(self.webhook_delivery_id))
+            .set((
+                webhook_delivery::status.eq(next_status),
+                webhook_delivery::attempts.eq(attempts_made),
+                webhook_delivery::next_attempt_at.eq(next_attempt),
+                webhook_delivery::last_status_code.eq(status_code),
+                webhook_delivery::last_error.eq(failure),
+                webhook_delivery::updated_at.eq(now)
+            ))
+            .get_result(&conn)
+    }
+
+    pub fn replay(id: i32) -> Result<Self, ApiError> {
+        let span = debug_span!("WebhookDelivery::replay");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let delivery = webhook_delivery
+            .filter(webhook_delivery::webhook_delivery_id.eq(id))
+            .first::<Self>(&conn)?;
+
+        if delivery.status != DELIVERY_FAILED {
+            return Err(ApiError::coded(409, "webhook_delivery.not_replayable", "Only failed deliveries can be replayed".to_string()));
+        }
+
+        let delivery_replayed = diesel::update(webhook_delivery::table)
+            .filter(webhook_delivery::webhook_delivery_id.eq(id))
+            .set((
+                webhook_delivery::status.eq(DELIVERY_PENDING),
+                webhook_delivery::attempts.eq(0),
+                webhook_delivery::next_attempt_at.eq(Utc::now().naive_utc()),
+                webhook_delivery::updated_at.eq(Utc::now().naive_utc())
+            ))
+            .get_result(&conn)?;
+
+        Ok(delivery_replayed)
+    }
+
+    pub fn replay_failed(subscription_id: i32) -> Result<usize, ApiError> {
+        let span = debug_span!("WebhookDelivery::replay_failed");
+        let _entered = span.enter();
+
+        let conn = db::establish_connection();
+
+        let res = diesel::update(webhook_delivery::table)
+            .filter(webhook_delivery::webhook_subscription_id.eq(subscription_id))
+            .filter(webhook_delivery::status.eq(DELIVERY_FAILED))
+            .set((
+                webhook_delivery::status.eq(DELIVERY_PENDING),
+                webhook_delivery::attempts.eq(0),
+                webhook_delivery::next_attempt_at.eq(Utc::now().naive_utc()),
+                webhook_delivery::updated_at.eq(Utc::now().naive_utc())
+            ))
+            .execute(&conn)?;
+
+        Ok(res)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,169 @@
+use crate::api_error::ApiError;
+use crate::audit::Actor;
+use crate::validation::Valid;
+use crate::webhook::{WebhookSubscription, WebhookSubscriptionCreate, WebhookSubscriptionId, WebhookDelivery, WebhookDeliveryId};
+use actix_web::{get, post, put, delete, web, HttpResponse};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhook",
+    operation_id = "webhook_find_all",
+    responses(
+        (status = 200, description = "Webhook subscriptions", body = [WebhookSubscription])
+    ),
+    security(("staff_id" = []))
+)]
+#[get("/webhooks")]
+async fn find_all(actor: Actor) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let subscriptions = WebhookSubscription::find_all()?;
+    Ok(HttpResponse::Ok().json(subscriptions))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhook",
+    operation_id = "webhook_create",
+    request_body = WebhookSubscriptionCreate,
+    responses(
+        (status = 201, description = "Created webhook subscription", body = WebhookSubscription)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/webhooks")]
+async fn create(actor: Actor, subscription: Valid<WebhookSubscriptionCreate>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let subscription = WebhookSubscription::create(subscription.into_inner())?;
+    Ok(HttpResponse::Created().json(subscription))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{webhook_subscription_id}",
+    tag = "webhook",
+    operation_id = "webhook_find",
+    params(("webhook_subscription_id" = i32, Path, description = "Webhook subscription id")),
+    responses(
+        (status = 200, description = "Webhook subscription", body = WebhookSubscription)
+    ),
+    security(("staff_id" = []))
+)]
+#[get("/webhooks/{webhook_subscription_id}")]
+async fn find(actor: Actor, id: web::Path<WebhookSubscriptionId>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let subscription = WebhookSubscription::find(id.webhook_subscription_id)?;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/webhooks/{webhook_subscription_id}",
+    tag = "webhook",
+    operation_id = "webhook_update",
+    params(("webhook_subscription_id" = i32, Path, description = "Webhook subscription id")),
+    request_body = WebhookSubscriptionCreate,
+    responses(
+        (status = 200, description = "Updated webhook subscription", body = WebhookSubscription)
+    ),
+    security(("staff_id" = []))
+)]
+#[put("/webhooks/{webhook_subscription_id}")]
+async fn update(actor: Actor, id: web::Path<WebhookSubscriptionId>, subscription: Valid<WebhookSubscriptionCreate>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let subscription = WebhookSubscription::update(id.webhook_subscription_id, subscription.into_inner())?;
+    Ok(HttpResponse::Ok().json(subscription))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{webhook_subscription_id}",
+    tag = "webhook",
+    operation_id = "webhook_delete",
+    params(("webhook_subscription_id" = i32, Path, description = "Webhook subscription id")),
+    responses(
+        (status = 200, description = "Number of subscriptions deleted", body = Deleted)
+    ),
+    security(("staff_id" = []))
+)]
+#[delete("/webhooks/{webhook_subscription_id}")]
+async fn delete(actor: Actor, id: web::Path<WebhookSubscriptionId>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let subscription_deleted = WebhookSubscription::delete(id.webhook_subscription_id)?;
+    Ok(HttpResponse::Ok().json(json!({ "deleted": subscription_deleted })))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{webhook_subscription_id}/deliveries",
+    tag = "webhook",
+    operation_id = "webhook_find_deliveries",
+    params(("webhook_subscription_id" = i32, Path, description = "Webhook subscription id")),
+    responses(
+        (status = 200, description = "Deliveries for the subscription", body = [WebhookDelivery])
+    ),
+    security(("staff_id" = []))
+)]
+#[get("/webhooks/{webhook_subscription_id}/deliveries")]
+async fn find_deliveries(actor: Actor, id: web::Path<WebhookSubscriptionId>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let deliveries = WebhookDelivery::find_by_subscription(id.webhook_subscription_id)?;
+    Ok(HttpResponse::Ok().json(deliveries))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{webhook_subscription_id}/deliveries/replay",
+    tag = "webhook",
+    operation_id = "webhook_replay_failed",
+    params(("webhook_subscription_id" = i32, Path, description = "Webhook subscription id")),
+    responses(
+        (status = 200, description = "Number of failed deliveries queued again", body = Replayed)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/webhooks/{webhook_subscription_id}/deliveries/replay")]
+async fn replay_failed(actor: Actor, id: web::Path<WebhookSubscriptionId>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let replayed = WebhookDelivery::replay_failed(id.webhook_subscription_id)?;
+    Ok(HttpResponse::Ok().json(json!({ "replayed": replayed })))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhook_deliveries/{webhook_delivery_id}/replay",
+    tag = "webhook",
+    operation_id = "webhook_replay",
+    params(("webhook_delivery_id" = i32, Path, description = "Webhook delivery id")),
+    responses(
+        (status = 200, description = "Delivery queued again", body = WebhookDelivery)
+    ),
+    security(("staff_id" = []))
+)]
+#[post("/webhook_deliveries/{webhook_delivery_id}/replay")]
+async fn replay(actor: Actor, id: web::Path<WebhookDeliveryId>) -> Result<HttpResponse, ApiError> {
+    actor.require_manager()?;
+
+    let delivery = WebhookDelivery::replay(id.webhook_delivery_id)?;
+    Ok(HttpResponse::Ok().json(delivery))
+}
+
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(find_all);
+    cfg.service(create);
+    cfg.service(find);
+    cfg.service(update);
+    cfg.service(delete);
+    cfg.service(find_deliveries);
+    cfg.service(replay_failed);
+    cfg.service(replay);
+}
\ No newline at end of file
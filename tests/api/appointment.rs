@@ -0,0 +1,375 @@
+use super::{connection, fixtures, get, post, put, send, setup};
+use actix_web::http::StatusCode;
+use chrono::{Duration, NaiveDateTime, Utc};
+use rust_scheduler::appointment::{AppointmentRepository, PgAppointmentRepository};
+use rust_scheduler::audit::Actor;
+use rust_scheduler::service::{GenerateServiceAddOn, Service};
+use rust_scheduler::webhook::{self, WebhookDelivery, WebhookSubscription, WebhookSubscriptionCreate};
+use serde_json::{json, Value};
+
+/// The only variant of a new 30 minute service with 10 minutes after it.
+fn variant(name: &str) -> i32 {
+    let service = fixtures::service(name);
+    Service::find_variants(service.service_id).unwrap()[0].service_variant_id
+}
+
+fn booking(customer_id: i32, start_time: &str, segments: Value) -> Value {
+    json!({ "customer_id": customer_id, "start_time": start_time, "segments": segments })
+}
+
+fn series(customer_id: i32, start_time: &str, rrule: &str, segments: Value) -> Value {
+    json!({ "customer_id": customer_id, "start_time": start_time, "rrule": rrule, "segments": segments })
+}
+
+/// 10:00 on the day `days` from today, as the API formats it.
+fn days_ahead(days: i64) -> String {
+    let day = Utc::now().naive_utc().date() + Duration::days(days);
+    day.and_hms(10, 0, 0).format("%Y-%m-%dT%H:%M:%S").to_string()
+}
+
+fn booked(appointments: &Value) -> Vec<&Value> {
+    appointments.as_array().unwrap().iter().filter(|appointment| appointment["status"] == "booked").collect()
+}
+
+#[actix_rt::test]
+async fn book_chains_segments_with_different_staff() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let color = variant("Color");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let grace = fixtures::bookable_staff("grace@example.com", &[color]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let conditioning = Service::create_add_on(1, GenerateServiceAddOn {
+        name: "Deep conditioning".to_string(),
+        price: 15.0,
+        extra_duration: Some(chrono::NaiveTime::from_hms(0, 20, 0)),
+        is_active: 1
+    }, &Actor::Anonymous).unwrap();
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", json!([
+        { "service_variant_id": cut, "staff_id": ada.staff_id, "add_on_ids": [conditioning.service_add_on_id] },
+        { "service_variant_id": color, "staff_id": grace.staff_id }
+    ])))).await;
+
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["appointment"]["price"], 65.0);
+    assert_eq!(res.body["appointment"]["end_time"], "2020-11-03T11:20:00");
+    assert_eq!(res.body["segments"][0]["segment"]["end_time"], "2020-11-03T10:50:00");
+    assert_eq!(res.body["segments"][0]["add_ons"][0]["price"], 15.0);
+    assert_eq!(res.body["segments"][1]["segment"]["start_time"], "2020-11-03T10:50:00");
+    assert_eq!(res.body["segments"][1]["segment"]["blocked_until"], "2020-11-03T11:30:00");
+
+    let res = send(get(&format!("/api/v1/appointments?staff_id={}", grace.staff_id))).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+}
+
+#[actix_rt::test]
+async fn book_waits_for_padding_when_staff_repeats() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let color = variant("Color");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut, color]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", json!([
+        { "service_variant_id": cut, "staff_id": ada.staff_id },
+        { "service_variant_id": color, "staff_id": ada.staff_id }
+    ])))).await;
+
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["segments"][1]["segment"]["start_time"], "2020-11-03T10:40:00");
+    assert_eq!(res.body["appointment"]["end_time"], "2020-11-03T11:10:00");
+}
+
+#[actix_rt::test]
+async fn book_is_all_or_nothing() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let color = variant("Color");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let grace = fixtures::bookable_staff("grace@example.com", &[color]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", json!([
+        { "service_variant_id": cut, "staff_id": ada.staff_id }
+    ])))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    // grace is free at 10:00, but ada's cut would follow at 10:30
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", json!([
+        { "service_variant_id": color, "staff_id": grace.staff_id },
+        { "service_variant_id": cut, "staff_id": ada.staff_id }
+    ])))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "appointment.conflict");
+    assert!(res.body["errors"]["segments[0]"].is_null());
+    assert!(res.body["errors"]["segments[1]"].is_array());
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T16:45:00", json!([
+        { "service_variant_id": color, "staff_id": grace.staff_id }
+    ])))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["errors"]["segments[0]"][0], "is outside the staff member's hours");
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T12:00:00", json!([
+        { "service_variant_id": color, "staff_id": ada.staff_id, "add_on_ids": [99] }
+    ])))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["segments[0].staff_id"].is_array());
+    assert!(res.body["errors"]["segments[0].add_on_ids[0]"].is_array());
+
+    let res = send(get("/api/v1/appointments")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+}
+
+#[actix_rt::test]
+async fn availability_skips_booked_time_and_cancel_frees_it() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+
+    let booked = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", segments.clone()))).await;
+    let appointment_id = booked.body["appointment"]["appointment_id"].clone();
+
+    let res = send(post("/api/v1/appointments/availability", json!({
+        "date": "2020-11-03", "segments": segments, "interval_minutes": 30
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    let start_times = res.body.as_array().unwrap();
+    // 9:00, then 11:00 to 16:00; 16:30 would run past 17:00 with padding
+    assert_eq!(start_times.len(), 12);
+    assert_eq!(start_times[0], "2020-11-03T09:00:00");
+    assert_eq!(start_times[1], "2020-11-03T11:00:00");
+    assert_eq!(start_times[11], "2020-11-03T16:00:00");
+
+    let res = send(post(&format!("/api/v1/appointments/{}/cancel", appointment_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["appointment"]["status"], "cancelled");
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", segments))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = send(get("/metrics")).await;
+    assert!(res.text.contains("appointments_booked_total{source=\"appointment\"}"));
+    assert!(res.text.contains("appointments_cancelled_total{source=\"appointment\"}"));
+}
+
+#[actix_rt::test]
+async fn complete_keeps_the_time_booked_and_emits_a_webhook() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+    let subscription = WebhookSubscription::create(WebhookSubscriptionCreate {
+        url: "http://127.0.0.1:9/hooks".to_string(),
+        secret: "secret".to_string(),
+        event_types: vec![webhook::APPOINTMENT_COMPLETED.to_string()],
+        is_active: 1
+    }).unwrap();
+
+    let booked = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", segments.clone()))).await;
+    let appointment_id = booked.body["appointment"]["appointment_id"].clone();
+
+    let res = send(post(&format!("/api/v1/appointments/{}/complete", appointment_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["appointment"]["status"], "completed");
+
+    let deliveries = WebhookDelivery::find_by_subscription(subscription.webhook_subscription_id).unwrap();
+    assert_eq!(deliveries.len(), 1);
+    assert_eq!(deliveries[0].event_type, "appointment.completed");
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-11-03T10:00:00", segments))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+
+    let res = send(post(&format!("/api/v1/appointments/{}/cancel", appointment_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "appointment.completed");
+}
+
+#[actix_rt::test]
+async fn missing_series_is_not_found() {
+    let _db = setup();
+
+    let res = send(get("/api/v1/appointment_series/999")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "appointment_series.not_found");
+}
+
+#[actix_rt::test]
+async fn series_books_every_occurrence_or_none() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+
+    let one_off = send(post("/api/v1/appointments", booking(customer.customer_id, "2020-12-01T10:15:00", segments.clone()))).await;
+
+    let res = send(post("/api/v1/appointment_series", series(customer.customer_id, "2020-11-03T10:00:00", "FREQ=WEEKLY;INTERVAL=4;BYDAY=TU;COUNT=3", segments.clone()))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "appointment_series.conflict");
+    assert!(res.body["errors"]["occurrences[2020-12-01T10:00:00].segments[0]"].is_array());
+    assert_eq!(res.body["errors"].as_object().unwrap().len(), 1);
+
+    let res = send(get("/api/v1/appointments")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+
+    send(post(&format!("/api/v1/appointments/{}/cancel", one_off.body["appointment"]["appointment_id"]), json!({}))).await;
+
+    let res = send(post("/api/v1/appointment_series", series(customer.customer_id, "2020-11-03T10:00:00", "RRULE:FREQ=WEEKLY;INTERVAL=4;BYDAY=TU;COUNT=3", segments.clone()))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["series"]["rrule"], "FREQ=WEEKLY;INTERVAL=4;BYDAY=TU;COUNT=3");
+
+    let appointments = res.body["appointments"].as_array().unwrap();
+    assert_eq!(appointments.len(), 3);
+    assert_eq!(appointments[1]["start_time"], "2020-12-01T10:00:00");
+    assert_eq!(appointments[2]["occurrence_time"], "2020-12-29T10:00:00");
+    assert_eq!(appointments[2]["appointment_series_id"], res.body["series"]["appointment_series_id"]);
+
+    let res = send(post("/api/v1/appointment_series", series(customer.customer_id, "2020-11-03T10:00:00", "FREQ=YEARLY", segments))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["rrule"].is_array());
+}
+
+#[actix_rt::test]
+async fn series_edits_this_and_following_occurrences() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+
+    let created = send(post("/api/v1/appointment_series", series(customer.customer_id, "2020-11-03T10:00:00", "FREQ=WEEKLY;BYDAY=TU;COUNT=4", segments.clone()))).await;
+    let series_id = created.body["series"]["appointment_series_id"].clone();
+
+    let res = send(put(
+        &format!("/api/v1/appointment_series/{}/occurrences/2020-11-10T10:00:00?scope=this", series_id),
+        json!({ "start_time": "2020-11-11T14:00:00", "segments": segments })
+    )).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let booked_now = booked(&res.body["appointments"]);
+    assert_eq!(booked_now.len(), 4);
+    let moved = booked_now.iter().find(|appointment| appointment["occurrence_time"] == "2020-11-10T10:00:00").unwrap();
+    assert_eq!(moved["start_time"], "2020-11-11T14:00:00");
+
+    // moving the rest of the series to Thursdays keeps the remaining count
+    let res = send(put(
+        &format!("/api/v1/appointment_series/{}/occurrences/2020-11-17T10:00:00?scope=following", series_id),
+        json!({ "start_time": "2020-11-19T09:00:00", "segments": segments })
+    )).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_ne!(res.body["series"]["appointment_series_id"], series_id);
+    assert_eq!(res.body["series"]["rrule"], "FREQ=WEEKLY;BYDAY=TH;COUNT=2");
+    assert_eq!(res.body["appointments"][1]["start_time"], "2020-11-26T09:00:00");
+
+    let res = send(get(&format!("/api/v1/appointment_series/{}", series_id))).await;
+    assert_eq!(res.body["series"]["rrule"], "FREQ=WEEKLY;BYDAY=TU;UNTIL=20201117T095959");
+    assert_eq!(booked(&res.body["appointments"]).len(), 2);
+
+    let res = send(put(
+        &format!("/api/v1/appointment_series/{}/occurrences/2020-11-24T10:00:00?scope=this", series_id),
+        json!({ "start_time": "2020-11-24T12:00:00", "segments": segments })
+    )).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "appointment_series.occurrence_not_found");
+
+    let res = send(put(
+        &format!("/api/v1/appointment_series/{}/occurrences/2020-11-03T10:00:00?scope=sometimes", series_id),
+        json!({ "start_time": "2020-11-03T12:00:00", "segments": segments })
+    )).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+async fn series_edit_all_moves_only_upcoming_occurrences() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+    let first = days_ahead(7);
+
+    let created = send(post("/api/v1/appointment_series", series(customer.customer_id, &first, "FREQ=DAILY;INTERVAL=2;COUNT=3", segments.clone()))).await;
+    let series_id = created.body["series"]["appointment_series_id"].clone();
+    assert_eq!(booked(&created.body["appointments"]).len(), 3);
+
+    let res = send(put(
+        &format!("/api/v1/appointment_series/{}/occurrences/{}?scope=all", series_id, first),
+        json!({ "start_time": first.replace("T10:00", "T14:00"), "segments": segments })
+    )).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["series"]["appointment_series_id"], series_id);
+
+    let booked_now = booked(&res.body["appointments"]);
+    assert_eq!(booked_now.len(), 3);
+    assert!(booked_now.iter().all(|appointment| appointment["start_time"].as_str().unwrap().ends_with("T14:00:00")));
+    assert_eq!(res.body["appointments"].as_array().unwrap().len(), 6);
+}
+
+#[actix_rt::test]
+async fn series_cancel_scopes() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+
+    let created = send(post("/api/v1/appointment_series", series(customer.customer_id, "2020-11-03T10:00:00", "FREQ=WEEKLY;COUNT=3", segments))).await;
+    let series_id = created.body["series"]["appointment_series_id"].clone();
+
+    let res = send(post(&format!("/api/v1/appointment_series/{}/occurrences/2020-11-10T10:00:00/cancel", series_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["series"]["excluded_times"][0], "2020-11-10T10:00:00");
+    assert_eq!(booked(&res.body["appointments"]).len(), 2);
+
+    let res = send(post(&format!("/api/v1/appointment_series/{}/occurrences/2020-11-10T10:00:00/cancel", series_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = send(post(&format!("/api/v1/appointment_series/{}/occurrences/2020-11-03T10:00:00/cancel?scope=following", series_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["series"]["status"], "cancelled");
+    assert!(booked(&res.body["appointments"]).is_empty());
+
+    let res = send(post(&format!("/api/v1/appointment_series/{}/occurrences/2020-11-17T10:00:00/cancel", series_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "appointment_series.cancelled");
+}
+
+#[actix_rt::test]
+async fn materialize_books_new_occurrences_and_reports_conflicts() {
+    let _db = setup();
+    let cut = variant("Cut");
+    let ada = fixtures::bookable_staff("ada@example.com", &[cut]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+    let segments = json!([{ "service_variant_id": cut, "staff_id": ada.staff_id }]);
+    // beyond the horizon, so nothing is booked yet
+    let first = days_ahead(400);
+    let second = days_ahead(407);
+
+    let created = send(post("/api/v1/appointment_series", series(customer.customer_id, &first, "FREQ=WEEKLY;COUNT=3", segments.clone()))).await;
+    assert_eq!(created.status, StatusCode::CREATED);
+    assert!(created.body["appointments"].as_array().unwrap().is_empty());
+
+    send(post("/api/v1/appointments", booking(customer.customer_id, &second, segments))).await;
+
+    let conn = connection();
+    let through = NaiveDateTime::parse_from_str(&first, "%Y-%m-%dT%H:%M:%S").unwrap() + Duration::days(30);
+    let report = PgAppointmentRepository::new(&conn).materialize_series(through, &Actor::System).unwrap();
+
+    assert_eq!(report.series, 1);
+    assert_eq!(report.booked, 2);
+    assert_eq!(report.skipped.len(), 1);
+    assert_eq!(report.skipped[0].occurrence_time.format("%Y-%m-%dT%H:%M:%S").to_string(), second);
+
+    // already materialized through that time, so a second run books nothing
+    let report = PgAppointmentRepository::new(&conn).materialize_series(through, &Actor::System).unwrap();
+    assert_eq!(report.series, 0);
+
+    let res = send(post("/api/v1/appointment_series/materialize", json!({}))).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+}
\ No newline at end of file
@@ -0,0 +1,151 @@
+use super::{fixtures, get, post, send, setup};
+use actix_web::http::StatusCode;
+use rust_scheduler::service::Service;
+use serde_json::json;
+
+/// The only variant of a new 30 minute group class with 10 minutes after it.
+fn class_variant(name: &str, capacity: i32) -> i32 {
+    let service = fixtures::group_service(name, capacity);
+    Service::find_variants(service.service_id).unwrap()[0].service_variant_id
+}
+
+#[actix_rt::test]
+async fn enrollment_stops_at_capacity() {
+    let _db = setup();
+    let yoga = class_variant("Yoga", 2);
+    let ada = fixtures::bookable_staff("ada@example.com", &[yoga]);
+    let lin = fixtures::customer("Lin", "Park", "lin@example.com");
+    let sam = fixtures::customer("Sam", "Reed", "sam@example.com");
+    let kim = fixtures::customer("Kim", "Cho", "kim@example.com");
+
+    let res = send(post("/api/v1/class_sessions", json!({
+        "service_variant_id": yoga, "staff_id": ada.staff_id, "start_time": "2020-11-03T10:00:00"
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["session"]["capacity"], 2);
+    assert_eq!(res.body["session"]["end_time"], "2020-11-03T10:30:00");
+    assert_eq!(res.body["spots_left"], 2);
+
+    let res = send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": lin.customer_id }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["price"], 25.0);
+
+    let res = send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": lin.customer_id }))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "class_session.already_enrolled");
+
+    let res = send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": sam.customer_id }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": kim.customer_id }))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "class_session.full");
+
+    // a cancelled enrollment frees its spot
+    let res = send(post("/api/v1/class_sessions/1/enrollments/1/cancel", json!({}))).await;
+    assert_eq!(res.body["status"], "cancelled");
+
+    let res = send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": kim.customer_id }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = send(get("/api/v1/class_sessions/1")).await;
+    assert_eq!(res.body["enrolled"], 2);
+    assert_eq!(res.body["spots_left"], 0);
+}
+
+#[actix_rt::test]
+async fn roster_lists_enrolled_customers() {
+    let _db = setup();
+    let yoga = class_variant("Yoga", 10);
+    let ada = fixtures::bookable_staff("ada@example.com", &[yoga]);
+    let lin = fixtures::customer("Lin", "Park", "lin@example.com");
+    let sam = fixtures::customer("Sam", "Reed", "sam@example.com");
+
+    send(post("/api/v1/class_sessions", json!({
+        "service_variant_id": yoga, "staff_id": ada.staff_id, "start_time": "2020-11-03T10:00:00", "capacity": 4
+    }))).await;
+    send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": sam.customer_id }))).await;
+    send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": lin.customer_id }))).await;
+
+    let res = send(get("/api/v1/class_sessions/1/roster")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body[0]["customer"]["first_name"], "Sam");
+    assert_eq!(res.body[1]["customer"]["first_name"], "Lin");
+
+    let res = send(get("/api/v1/class_sessions/2/roster")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    // cancelling the session cancels its enrollments
+    let res = send(post("/api/v1/class_sessions/1/cancel", json!({}))).await;
+    assert_eq!(res.body["session"]["status"], "cancelled");
+    assert_eq!(res.body["enrolled"], 0);
+
+    let res = send(get("/api/v1/class_sessions/1/roster")).await;
+    assert_eq!(res.body, json!([]));
+
+    let res = send(post("/api/v1/class_sessions/1/enrollments", json!({ "customer_id": lin.customer_id }))).await;
+    assert_eq!(res.code(), "class_session.cancelled");
+}
+
+#[actix_rt::test]
+async fn group_classes_and_appointments_stay_apart() {
+    let _db = setup();
+    let yoga = class_variant("Yoga", 8);
+    let cut = class_variant("Cut", 1);
+    let ada = fixtures::bookable_staff("ada@example.com", &[yoga, cut]);
+    let lin = fixtures::customer("Lin", "Park", "lin@example.com");
+
+    let res = send(post("/api/v1/appointments", json!({
+        "customer_id": lin.customer_id,
+        "start_time": "2020-11-03T10:00:00",
+        "segments": [{ "service_variant_id": yoga, "staff_id": ada.staff_id }]
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["segments[0].service_variant_id"].is_array());
+
+    let res = send(post("/api/v1/class_sessions", json!({
+        "service_variant_id": cut, "staff_id": ada.staff_id, "start_time": "2020-11-03T10:00:00"
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["service_variant_id"].is_array());
+}
+
+#[actix_rt::test]
+async fn sessions_block_the_staff_calendar() {
+    let _db = setup();
+    let yoga = class_variant("Yoga", 8);
+    let cut = class_variant("Cut", 1);
+    let ada = fixtures::bookable_staff("ada@example.com", &[yoga, cut]);
+    let lin = fixtures::customer("Lin", "Park", "lin@example.com");
+
+    let res = send(post("/api/v1/class_sessions", json!({
+        "service_variant_id": yoga, "staff_id": ada.staff_id, "start_time": "2020-11-03T10:00:00"
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = send(post("/api/v1/appointments", json!({
+        "customer_id": lin.customer_id,
+        "start_time": "2020-11-03T10:30:00",
+        "segments": [{ "service_variant_id": cut, "staff_id": ada.staff_id }]
+    }))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "appointment.conflict");
+
+    let res = send(post("/api/v1/class_sessions", json!({
+        "service_variant_id": yoga, "staff_id": ada.staff_id, "start_time": "2020-11-03T10:15:00"
+    }))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "class_session.conflict");
+
+    // the padding after the session ends at 10:40
+    let res = send(post("/api/v1/appointments", json!({
+        "customer_id": lin.customer_id,
+        "start_time": "2020-11-03T10:40:00",
+        "segments": [{ "service_variant_id": cut, "staff_id": ada.staff_id }]
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    let res = send(get(&format!("/api/v1/class_sessions?staff_id={}&status=scheduled", ada.staff_id))).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+}
\ No newline at end of file
@@ -0,0 +1,127 @@
+//! Rows created through the models, so they get the same hours, blocked
+//! time and audit entries as rows created over HTTP.
+
+use rust_scheduler::audit::Actor;
+use rust_scheduler::customer::{Customer, CustomerCreate};
+use rust_scheduler::service::{GenerateService, GenerateServiceVariant, Service, ServiceCategory, ServiceCategoryCreate};
+use rust_scheduler::staff::{Staff, StaffCreate, StaffHoursCreate};
+use rust_scheduler::store::{Store, StoreAddress, StoreAddressCreate, StoreCreate};
+use chrono::NaiveTime;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+static PHONES: AtomicU32 = AtomicU32::new(0);
+
+/// Staff phone numbers are unique, so each fixture gets its own.
+fn phone() -> String {
+    format!("217555{:04}", PHONES.fetch_add(1, Ordering::Relaxed))
+}
+
+pub fn store(name: &str) -> Store {
+    Store::create(StoreCreate { name: name.to_string() }, &Actor::Anonymous).expect("create store")
+}
+
+pub fn store_address(store_id: i32) -> StoreAddress {
+    Store::create_address(StoreAddressCreate {
+        store_id,
+        street_address: "1 Main St".to_string(),
+        city: "Springfield".to_string(),
+        state: "IL".to_string(),
+        zip: "62701".to_string(),
+        phone: "2175550100".to_string(),
+        email: "front@example.com".to_string()
+    }, &Actor::Anonymous).expect("create store address")
+}
+
+pub fn staff(email: &str) -> Staff {
+    Staff::create(StaffCreate {
+        first_name: "Ada".to_string(),
+        last_name: "Lovelace".to_string(),
+        password: "secret".to_string(),
+        email: email.to_string(),
+        phone: phone(),
+        access: "staff".to_string(),
+        calendar_color: "#336699".to_string()
+    }, &Actor::Anonymous).expect("create staff")
+}
+
+/// A staff member working 9 to 5 every day who performs `variant_ids`.
+pub fn bookable_staff(email: &str, variant_ids: &[i32]) -> Staff {
+    let member = staff(email);
+
+    Staff::update_hours((0..7).map(|day| StaffHoursCreate {
+        staff_id: member.staff_id,
+        day_of_week: day,
+        start_time: Some(NaiveTime::from_hms(9, 0, 0)),
+        end_time: Some(NaiveTime::from_hms(17, 0, 0))
+    }).collect(), &Actor::Anonymous).expect("update staff hours");
+
+    for variant_id in variant_ids {
+        Staff::add_service(member.staff_id, *variant_id, &Actor::Anonymous).expect("add staff service");
+    }
+
+    member
+}
+
+pub fn customer(first_name: &str, last_name: &str, email: &str) -> Customer {
+    Customer::create(CustomerCreate {
+        first_name: first_name.to_string(),
+        last_name: last_name.to_string(),
+        email: Some(email.to_string()),
+        phone: Some("3125550199".to_string())
+    }).expect("create customer")
+}
+
+/// A service with a single 30 minute variant.
+pub fn service(name: &str) -> Service {
+    group_service(name, 1)
+}
+
+/// A service with a single 30 minute variant taking `capacity` customers.
+pub fn group_service(name: &str, capacity: i32) -> Service {
+    Service::create(GenerateService {
+        name: name.to_string(),
+        description: Some("A service".to_string()),
+        is_active: 1,
+        service_category_id: None,
+        capacity,
+        before_time: None,
+        after_time: Some(NaiveTime::from_hms(0, 10, 0)),
+        variants: vec![GenerateServiceVariant { price: 25.0, duration: Some(NaiveTime::from_hms(0, 30, 0)), capacity: None }]
+    }, &Actor::Anonymous).expect("create service")
+}
+
+pub fn service_category(name: &str, display_order: i32) -> ServiceCategory {
+    Service::create_category(ServiceCategoryCreate {
+        name: name.to_string(),
+        description: None,
+        display_order,
+        is_active: 1
+    }, &Actor::Anonymous).expect("create service category")
+}
+
+/// Request body for creating or updating a staff member.
+pub fn staff_body(email: &str) -> Value {
+    json!({
+        "first_name": "Grace",
+        "last_name": "Hopper",
+        "password": "secret",
+        "email": email,
+        "phone": phone(),
+        "access": "manager",
+        "calendar_color": "#993366"
+    })
+}
+
+/// Request body for creating or updating a store address.
+pub fn address_body(store_id: i32, city: &str) -> Value {
+    json!({
+        "store_id": store_id,
+        "street_address": "2 Elm St",
+        "city": city,
+        "state": "IL",
+        "zip": "62702-1234",
+        "phone": "2175550103",
+        "email": "hello@example.com"
+    })
+}
\ No newline at end of file
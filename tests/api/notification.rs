@@ -0,0 +1,30 @@
+use super::{fixtures, post, put, send, setup};
+use actix_web::http::StatusCode;
+use rust_scheduler::notification::sms::SMS_SECRET_HEADER;
+use serde_json::json;
+
+pub const SMS_STATUS_SECRET: &str = "test-sms-status-secret";
+
+#[actix_rt::test]
+async fn status_callbacks_need_the_shared_secret() {
+    let _db = setup();
+    let customer = fixtures::customer("Ada", "Lovelace", "ada@example.com");
+    let uri = format!("/notification_preference/{}", customer.customer_id);
+    send(put(&uri, json!({ "email_enabled": 1, "sms_enabled": 1 }))).await;
+
+    let uri = format!("/api/v1/customers/{}/sms_messages", customer.customer_id);
+    let message = send(post(&uri, json!({ "body": "See you at 3pm" }))).await;
+    assert_eq!(message.status, StatusCode::CREATED);
+    let body = json!({ "provider_message_id": message.body["provider_message_id"], "status": "delivered" });
+
+    let res = send(put("/api/v1/sms_messages/status", body.clone())).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+    assert_eq!(res.code(), "sms.callback_unauthorized");
+
+    let res = send(put("/sms_status", body.clone()).header(SMS_SECRET_HEADER, "not-the-secret")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = send(put("/api/v1/sms_messages/status", body).header(SMS_SECRET_HEADER, SMS_STATUS_SECRET)).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["status"], "delivered");
+}
\ No newline at end of file
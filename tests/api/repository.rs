@@ -0,0 +1,133 @@
+use super::{connection, fixtures, setup};
+use diesel::connection::Connection;
+use diesel::prelude::*;
+use rust_scheduler::audit::Actor;
+use rust_scheduler::schema::staff_hours;
+use rust_scheduler::service::{GenerateService, GenerateServiceVariant, PgServiceRepository, ServiceRepository};
+use rust_scheduler::staff::{password, PgStaffRepository, Staff, StaffCreate, StaffRepository, StaffServiceAssign, StaffUpdate};
+
+fn staff_create(email: &str) -> StaffCreate {
+    StaffCreate {
+        first_name: "Ada".to_string(),
+        last_name: "Lovelace".to_string(),
+        password: "secret".to_string(),
+        email: email.to_string(),
+        phone: "2175559999".to_string(),
+        access: "staff".to_string(),
+        calendar_color: "#336699".to_string()
+    }
+}
+
+#[test]
+fn writes_stay_on_the_callers_connection() {
+    let _db = setup();
+    let conn = connection();
+    conn.begin_test_transaction().unwrap();
+
+    let staff = PgStaffRepository::new(&conn);
+    let created = staff.create(staff_create("ada@example.com"), &Actor::Anonymous).unwrap();
+
+    assert_eq!(staff.find_staff_hours(created.staff_id).unwrap().staff_hours.len(), 7);
+    // the pool cannot see the uncommitted row
+    assert!(Staff::find(created.staff_id).is_err());
+}
+
+#[test]
+fn repositories_share_a_connection() {
+    let _db = setup();
+    let conn = connection();
+    conn.begin_test_transaction().unwrap();
+
+    let staff = PgStaffRepository::new(&conn);
+    let services = PgServiceRepository::new(&conn);
+
+    let member = staff.create(staff_create("ada@example.com"), &Actor::Anonymous).unwrap();
+    let service = services.create(GenerateService {
+        name: "Cut".to_string(),
+        description: None,
+        is_active: 1,
+        service_category_id: None,
+        capacity: 1,
+        before_time: None,
+        after_time: None,
+        variants: vec![GenerateServiceVariant { price: 20.0, duration: None, capacity: None }]
+    }, &Actor::Anonymous).unwrap();
+
+    let variant = services.find_variants(service.service_id).unwrap().remove(0);
+    staff.update_staff_services(member.staff_id, vec![StaffServiceAssign { service_variant_id: variant.service_variant_id, is_active: 1 }], &Actor::Anonymous).unwrap();
+
+    let found = services.find(service.service_id).unwrap();
+    assert_eq!(found.staff.len(), 1);
+    assert_eq!(found.staff[0].staff_id, member.staff_id);
+}
+
+#[test]
+fn shortcuts_use_the_pool() {
+    let _db = setup();
+    let member = fixtures::staff("ada@example.com");
+
+    let conn = connection();
+    assert_eq!(PgStaffRepository::new(&conn).find(member.staff_id).unwrap().email, "ada@example.com");
+}
+
+#[test]
+fn passwords_are_stored_hashed() {
+    let _db = setup();
+    let conn = connection();
+    conn.begin_test_transaction().unwrap();
+
+    let staff = PgStaffRepository::new(&conn);
+    let created = staff.create(staff_create("ada@example.com"), &Actor::Anonymous).unwrap();
+    assert!(password::is_hashed(&created.password));
+    assert!(bcrypt::verify("secret", &created.password).unwrap());
+
+    let reset = staff.reset_password(created.staff_id, "new secret", &Actor::System).unwrap();
+    assert!(bcrypt::verify("new secret", &reset.password).unwrap());
+    assert_eq!(staff.find_by_email("ada@example.com").unwrap().staff_id, created.staff_id);
+}
+
+#[test]
+fn updates_keep_the_password_unless_one_is_sent() {
+    let _db = setup();
+    let conn = connection();
+    conn.begin_test_transaction().unwrap();
+
+    let staff = PgStaffRepository::new(&conn);
+    let created = staff.create(staff_create("ada@example.com"), &Actor::Anonymous).unwrap();
+    let staff_update = |new_password: Option<&str>| StaffUpdate {
+        first_name: "Ada".to_string(),
+        last_name: "King".to_string(),
+        password: new_password.map(String::from),
+        email: "ada@example.com".to_string(),
+        phone: "2175559999".to_string(),
+        access: "staff".to_string(),
+        calendar_color: "#336699".to_string()
+    };
+
+    let updated = staff.update(created.staff_id, staff_update(None), &Actor::Anonymous).unwrap();
+    assert_eq!(updated.last_name, "King");
+    assert_eq!(updated.password, created.password);
+
+    let updated = staff.update(created.staff_id, staff_update(Some("new secret")), &Actor::Anonymous).unwrap();
+    assert!(bcrypt::verify("new secret", &updated.password).unwrap());
+}
+
+#[test]
+fn repair_hours_restores_missing_weekdays() {
+    let _db = setup();
+    let conn = connection();
+    conn.begin_test_transaction().unwrap();
+
+    let staff = PgStaffRepository::new(&conn);
+    let created = staff.create(staff_create("ada@example.com"), &Actor::Anonymous).unwrap();
+
+    diesel::delete(staff_hours::table
+        .filter(staff_hours::staff_id.eq(created.staff_id))
+        .filter(staff_hours::day_of_week.ge(5)))
+        .execute(&conn)
+        .unwrap();
+
+    assert_eq!(staff.repair_hours().unwrap(), 2);
+    assert_eq!(staff.repair_hours().unwrap(), 0);
+    assert_eq!(staff.find_staff_hours(created.staff_id).unwrap().staff_hours.len(), 7);
+}
\ No newline at end of file
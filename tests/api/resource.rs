@@ -0,0 +1,150 @@
+use super::{delete, fixtures, get, post, put, send, setup};
+use actix_web::http::StatusCode;
+use rust_scheduler::service::Service;
+use serde_json::{json, Value};
+
+/// A 30 minute massage with 10 minutes after it that needs a treatment room.
+async fn massage() -> i32 {
+    let service = fixtures::service("Massage");
+    let res = send(put(&format!("/api/v1/services/{}/resource_types", service.service_id), json!({
+        "resource_types": ["treatment_room"]
+    }))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    Service::find_variants(service.service_id).unwrap()[0].service_variant_id
+}
+
+async fn room(store_id: i32, name: &str) -> Value {
+    let res = send(post(&format!("/api/v1/stores/{}/resources", store_id), json!({
+        "name": name, "resource_type": "treatment_room", "is_active": 1
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+
+    res.body
+}
+
+fn booking(customer_id: i32, store_id: i32, staff_id: i32, service_variant_id: i32) -> Value {
+    json!({
+        "customer_id": customer_id,
+        "store_id": store_id,
+        "start_time": "2020-11-03T10:00:00",
+        "segments": [{ "service_variant_id": service_variant_id, "staff_id": staff_id }]
+    })
+}
+
+#[actix_rt::test]
+async fn resources_belong_to_a_store() {
+    let _db = setup();
+    let downtown = fixtures::store("Downtown");
+    let uptown = fixtures::store("Uptown");
+    let first = room(downtown.store_id, "Room 1").await;
+    room(uptown.store_id, "Room A").await;
+
+    let res = send(post(&format!("/api/v1/stores/{}/resources", downtown.store_id), json!({
+        "name": "", "resource_type": "Treatment Room", "is_active": 1
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["name"].is_array());
+    assert!(res.body["errors"]["resource_type"].is_array());
+
+    let uri = format!("/api/v1/stores/{}/resources/{}", downtown.store_id, first["resource_id"]);
+    let res = send(put(&uri, json!({ "name": "Room 1", "resource_type": "treatment_room", "is_active": 0 }))).await;
+    assert_eq!(res.body["is_active"], 0);
+
+    // another store's resource is not found through this one
+    let res = send(put(&format!("/api/v1/stores/{}/resources/{}", uptown.store_id, first["resource_id"]), json!({
+        "name": "Room 1", "resource_type": "treatment_room", "is_active": 1
+    }))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = send(get(&format!("/api/v1/stores/{}/resources?resource_type=treatment_room", downtown.store_id))).await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+
+    let res = send(delete(&uri)).await;
+    assert_eq!(res.body["deleted"], 1);
+
+    let res = send(get(&format!("/api/v1/stores/{}/resources", downtown.store_id))).await;
+    assert_eq!(res.body, json!([]));
+
+    let res = send(put("/api/v1/services/1/resource_types", json!({ "resource_types": ["chair"] }))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let service = fixtures::service("Pedicure");
+    let uri = format!("/api/v1/services/{}/resource_types", service.service_id);
+    let res = send(put(&uri, json!({ "resource_types": ["chair", "chair"] }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["resource_types[1]"].is_array());
+
+    send(put(&uri, json!({ "resource_types": ["foot_spa", "chair"] }))).await;
+    let res = send(get(&uri)).await;
+    assert_eq!(res.body["resource_types"], json!(["chair", "foot_spa"]));
+}
+
+#[actix_rt::test]
+async fn booking_holds_a_free_resource() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+    let massage = massage().await;
+    let first = room(store.store_id, "Room 1").await;
+    let second = room(store.store_id, "Room 2").await;
+    let ada = fixtures::bookable_staff("ada@example.com", &[massage]);
+    let grace = fixtures::bookable_staff("grace@example.com", &[massage]);
+    let alan = fixtures::bookable_staff("alan@example.com", &[massage]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+
+    let res = send(post("/api/v1/appointments", json!({
+        "customer_id": customer.customer_id,
+        "start_time": "2020-11-03T10:00:00",
+        "segments": [{ "service_variant_id": massage, "staff_id": ada.staff_id }]
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["store_id"].is_array());
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, store.store_id, ada.staff_id, massage))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["appointment"]["store_id"], store.store_id);
+    assert_eq!(res.body["segments"][0]["resources"][0]["resource_id"], first["resource_id"]);
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, store.store_id, grace.staff_id, massage))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["segments"][0]["resources"][0]["resource_id"], second["resource_id"]);
+    let appointment_id = res.body["appointment"]["appointment_id"].clone();
+
+    // a free therapist is not enough without a free room
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, store.store_id, alan.staff_id, massage))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.body["errors"]["segments[0]"], json!(["needs a free treatment_room at the store"]));
+
+    send(post(&format!("/api/v1/appointments/{}/cancel", appointment_id), json!({}))).await;
+
+    let res = send(post("/api/v1/appointments", booking(customer.customer_id, store.store_id, alan.staff_id, massage))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["segments"][0]["resources"][0]["resource_id"], second["resource_id"]);
+}
+
+#[actix_rt::test]
+async fn availability_needs_a_free_resource() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+    let massage = massage().await;
+    room(store.store_id, "Room 1").await;
+    let ada = fixtures::bookable_staff("ada@example.com", &[massage]);
+    let grace = fixtures::bookable_staff("grace@example.com", &[massage]);
+    let customer = fixtures::customer("Lin", "Park", "lin@example.com");
+
+    send(post("/api/v1/appointments", booking(customer.customer_id, store.store_id, ada.staff_id, massage))).await;
+
+    let res = send(post("/api/v1/appointments/availability", json!({
+        "date": "2020-11-03",
+        "store_id": store.store_id,
+        "segments": [{ "service_variant_id": massage, "staff_id": grace.staff_id }],
+        "interval_minutes": 30
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    let start_times = res.body.as_array().unwrap();
+    // the only room is held from 10:00 to 10:40
+    assert_eq!(start_times.len(), 12);
+    assert_eq!(start_times[0], "2020-11-03T09:00:00");
+    assert_eq!(start_times[1], "2020-11-03T11:00:00");
+}
\ No newline at end of file
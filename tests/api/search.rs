@@ -0,0 +1,88 @@
+use super::{delete, fixtures, get, send, setup};
+use actix_web::http::StatusCode;
+
+#[actix_rt::test]
+async fn search_finds_every_type() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    let customer = fixtures::customer("Ada", "Byron", "byron@example.com");
+    fixtures::service("Cut");
+
+    let res = send(get("/api/v1/search?q=ada")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "2");
+
+    let kinds: Vec<(&str, i64)> = res.body.as_array().unwrap().iter()
+        .map(|result| (result["kind"].as_str().unwrap(), result["id"].as_i64().unwrap()))
+        .collect();
+    assert!(kinds.contains(&("staff", staff.staff_id as i64)));
+    assert!(kinds.contains(&("customer", customer.customer_id as i64)));
+}
+
+#[actix_rt::test]
+async fn search_matches_partial_phone_and_misspellings() {
+    let _db = setup();
+    fixtures::customer("Grace", "Hopper", "grace@example.com");
+    fixtures::service("Balayage");
+
+    let res = send(get("/api/v1/search?q=555019")).await;
+    assert_eq!(res.body[0]["title"], "Grace Hopper");
+    assert_eq!(res.body[0]["subtitle"], "grace@example.com");
+
+    let res = send(get("/api/v1/search?q=balyage")).await;
+    assert_eq!(res.body[0]["kind"], "service");
+    assert_eq!(res.body[0]["title"], "Balayage");
+}
+
+#[actix_rt::test]
+async fn search_ranks_exact_words_first() {
+    let _db = setup();
+    fixtures::service("Cut");
+    fixtures::service("Cuticle care");
+
+    let res = send(get("/api/v1/search?q=cut&types=service")).await;
+
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "2");
+    assert_eq!(res.body[0]["title"], "Cut");
+    assert_eq!(res.body[1]["title"], "Cuticle care");
+}
+
+#[actix_rt::test]
+async fn search_filters_types_and_skips_deleted() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    fixtures::customer("Ada", "Byron", "byron@example.com");
+
+    let res = send(get("/api/v1/search?q=ada&types=customer")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+    assert_eq!(res.body[0]["kind"], "customer");
+
+    send(delete(&format!("/api/v1/staff/{}", staff.staff_id))).await;
+    let res = send(get("/api/v1/search?q=ada&types=staff")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "0");
+}
+
+#[actix_rt::test]
+async fn search_rejects_bad_queries() {
+    let _db = setup();
+
+    let res = send(get("/api/v1/search?q=%20")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.code(), "search.query_required");
+
+    let res = send(get("/api/v1/search?q=ada&types=store")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.code(), "search.unknown_type");
+}
+
+#[actix_rt::test]
+async fn search_treats_wildcards_literally() {
+    let _db = setup();
+    fixtures::service("Cut");
+
+    let res = send(get("/api/v1/search?q=%25")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "0");
+}
\ No newline at end of file
@@ -0,0 +1,447 @@
+use super::{delete, fixtures, get, post, put, send, setup};
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use rust_scheduler::service::Service;
+use serde_json::{json, Value};
+
+fn service_body(name: &str) -> Value {
+    json!({
+        "name": name,
+        "description": "Wash and cut",
+        "is_active": 1,
+        "service_category_id": null,
+        "before_time": "00:05:00",
+        "after_time": null,
+        "variants": [
+            { "price": 30.0, "duration": "00:30:00" },
+            { "price": 45.0, "duration": "00:45:00" }
+        ]
+    })
+}
+
+#[actix_rt::test]
+async fn find_all_includes_variants_and_staff() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    fixtures::service("Cut");
+
+    send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 1, "is_active": 1 }
+    ]))).await;
+
+    let res = send(get("/service?group_by=none")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+    assert_eq!(res.body[0]["service"]["name"], "Cut");
+    assert_eq!(res.body[0]["variants"].as_array().unwrap().len(), 1);
+    assert_eq!(res.body[0]["blocked_time"]["after_time"], "00:10:00");
+    assert_eq!(res.body[0]["staff"][0]["staff_id"], staff.staff_id);
+}
+
+#[actix_rt::test]
+async fn find_all_filters() {
+    let _db = setup();
+    fixtures::service("Cut");
+    let spa = fixtures::service_category("Spa", 1);
+    send(post("/service", json!({
+        "name": "Massage", "description": null, "is_active": 0, "service_category_id": spa.service_category_id,
+        "before_time": null, "after_time": null, "variants": [{ "price": 60.0, "duration": "01:00:00" }]
+    }))).await;
+
+    let res = send(get(&format!("/service?group_by=none&service_category_id={}", spa.service_category_id))).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+    assert_eq!(res.body[0]["service"]["name"], "Massage");
+
+    let res = send(get("/service?group_by=none&is_active=1")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+    assert_eq!(res.body[0]["service"]["name"], "Cut");
+
+    let res = send(get("/service?group_by=none&sort=name:desc")).await;
+    assert_eq!(res.body[0]["service"]["name"], "Massage");
+
+    let res = send(get("/service?group_by=none&sort=price:asc")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
+
+#[actix_rt::test]
+async fn find_all_groups_by_category_in_display_order() {
+    let _db = setup();
+    let nails = fixtures::service_category("Nails", 2);
+    let hair = fixtures::service_category("Hair", 1);
+    fixtures::service("Consultation");
+
+    for (name, category) in [("Manicure", &nails), ("Color", &hair), ("Cut", &hair)].iter() {
+        let mut body = service_body(name);
+        body["service_category_id"] = json!(category.service_category_id);
+        send(post("/api/v1/services", body)).await;
+    }
+
+    let res = send(get("/api/v1/services?group_by=category")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "4");
+    assert_eq!(res.body[0]["category"]["name"], "Hair");
+    assert_eq!(res.body[0]["services"][0]["service"]["name"], "Color");
+    assert_eq!(res.body[0]["services"][1]["service"]["name"], "Cut");
+    assert_eq!(res.body[1]["category"]["name"], "Nails");
+    assert!(res.body[2]["category"].is_null());
+    assert_eq!(res.body[2]["services"][0]["service"]["name"], "Consultation");
+
+    let res = send(get("/api/v1/services")).await;
+    assert_eq!(res.body[0]["service"]["name"], "Consultation");
+
+    let res = send(get("/api/v1/services?group_by=staff")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.code(), "service.invalid_group_by");
+}
+
+#[actix_rt::test]
+async fn legacy_find_all_groups_and_hides_inactive_categories() {
+    let _db = setup();
+    let hair = fixtures::service_category("Hair", 2);
+    let retired = fixtures::service_category("Retired", 1);
+    fixtures::service("Consultation");
+
+    for (name, category) in [("Cut", &hair), ("Perm", &retired)].iter() {
+        let mut body = service_body(name);
+        body["service_category_id"] = json!(category.service_category_id);
+        send(post("/api/v1/services", body)).await;
+    }
+    send(put(&format!("/api/v1/service_categories/{}", retired.service_category_id), json!({
+        "name": "Retired", "description": null, "display_order": 1, "is_active": 0
+    }))).await;
+
+    let res = send(get("/service")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "2");
+    assert_eq!(res.body.as_array().unwrap().len(), 2);
+    assert_eq!(res.body[0]["category"]["name"], "Hair");
+    assert_eq!(res.body[0]["services"][0]["service"]["name"], "Cut");
+    assert!(res.body[1]["category"].is_null());
+
+    let res = send(get("/service?group_by=none")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "3");
+}
+
+#[actix_rt::test]
+async fn service_categories_crud() {
+    let _db = setup();
+
+    let res = send(post("/api/v1/service_categories", json!({
+        "name": " Hair ", "description": "Cuts and color", "display_order": 2, "is_active": 1
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["name"], "Hair");
+    let hair_id = res.body["service_category_id"].as_i64().unwrap();
+
+    let res = send(post("/api/v1/service_categories", json!({
+        "name": "hair", "description": null, "display_order": 1, "is_active": 1
+    }))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "service_category.name_taken");
+
+    fixtures::service_category("Nails", 1);
+    let res = send(get("/api/v1/service_categories")).await;
+    assert_eq!(res.body[0]["name"], "Nails");
+    assert_eq!(res.body[1]["name"], "Hair");
+
+    let res = send(put(&format!("/api/v1/service_categories/{}", hair_id), json!({
+        "name": "Hair", "description": null, "display_order": 0, "is_active": 0
+    }))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["is_active"], 0);
+
+    let mut body = service_body("Cut");
+    body["service_category_id"] = json!(hair_id);
+    let service_id = send(post("/api/v1/services", body)).await.body["service_id"].clone();
+
+    let res = send(delete(&format!("/api/v1/service_categories/{}", hair_id))).await;
+    assert_eq!(res.body["deleted"], 1);
+
+    let res = send(get(&format!("/api/v1/services/{}", service_id))).await;
+    assert!(res.body["service"]["service_category_id"].is_null());
+
+    let res = send(get(&format!("/api/v1/service_categories/{}", hair_id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn find_returns_service() {
+    let _db = setup();
+    let service = fixtures::service("Cut");
+
+    let res = send(get(&format!("/service/{}", service.service_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["service"]["name"], "Cut");
+    assert_eq!(res.body["variants"][0]["price"], 25.0);
+
+    let res = send(get("/service/99")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "service.not_found");
+}
+
+#[actix_rt::test]
+async fn create_adds_variants_and_blocked_time() {
+    let _db = setup();
+
+    let res = send(post("/service", service_body("Cut"))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["service_id"], 1);
+
+    let res = send(get("/service/1")).await;
+    assert_eq!(res.body["variants"].as_array().unwrap().len(), 2);
+    assert_eq!(res.body["blocked_time"]["before_time"], "00:05:00");
+}
+
+#[actix_rt::test]
+async fn create_rejects_invalid_service() {
+    let _db = setup();
+    let mut body = service_body("");
+    body["is_active"] = json!(2);
+    body["variants"] = json!([{ "price": -1.0, "duration": null }]);
+
+    let res = send(post("/service", body)).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["name"].is_array());
+    assert!(res.body["errors"]["is_active"].is_array());
+    assert!(res.body["errors"]["variants[0].price"].is_array());
+
+    let mut body = service_body("Cut");
+    body["variants"] = json!([]);
+
+    let res = send(post("/service", body)).await;
+    assert!(res.body["errors"]["variants"].is_array());
+}
+
+#[actix_rt::test]
+async fn update_changes_service_only() {
+    let _db = setup();
+    let service = fixtures::service("Cut");
+
+    let res = send(put(&format!("/service/{}", service.service_id), json!({
+        "name": "Trim", "description": null, "is_active": 0, "service_category_id": null
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["name"], "Trim");
+    assert_eq!(res.body["is_active"], 0);
+
+    let res = send(put("/service/99", json!({
+        "name": "Trim", "description": null, "is_active": 0, "service_category_id": null
+    }))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn update_all_changes_variants_and_blocked_time() {
+    let _db = setup();
+    let service = fixtures::service("Cut");
+
+    let res = send(put(&format!("/full_service/{}", service.service_id), json!({
+        "name": "Long cut",
+        "description": null,
+        "is_active": 1,
+        "service_category_id": null,
+        "before_time": "00:15:00",
+        "after_time": null,
+        "variants": [{ "service_variant_id": 1, "service_id": service.service_id, "price": 40.0, "duration": "01:00:00" }]
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["name"], "Long cut");
+
+    let res = send(get(&format!("/service/{}", service.service_id))).await;
+    assert_eq!(res.body["variants"][0]["price"], 40.0);
+    assert_eq!(res.body["variants"][0]["duration"], "01:00:00");
+    assert_eq!(res.body["blocked_time"]["before_time"], "00:15:00");
+}
+
+#[actix_rt::test]
+async fn delete_and_restore() {
+    let _db = setup();
+    let service = fixtures::service("Cut");
+
+    let res = send(delete(&format!("/service/{}", service.service_id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, json!({ "deleted": 1 }));
+
+    let res = send(get("/service")).await;
+    assert_eq!(res.body, json!([]));
+
+    let res = send(get("/service?include_deleted=true")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+
+    let res = send(post(&format!("/service/{}/restore", service.service_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.body["deleted_at"].is_null());
+
+    let res = send(post(&format!("/service/{}/restore", service.service_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "service.not_deleted");
+
+    let res = send(get("/service")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+}
+
+#[actix_rt::test]
+async fn unknown_route_is_not_found() {
+    let _db = setup();
+
+    let res = send(get("/services_typo")).await;
+
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "route.not_found");
+}
+
+const CATALOG_CSV: &str = "\
+name,description,category,is_active,before_time,after_time,price,duration
+Cut,Wash and cut,hair,1,00:05:00,,30.0,00:30:00
+Cut,Wash and cut,hair,1,00:05:00,,45.0,00:45:00
+Manicure,,nails,1,,00:10:00,25.0,00:40:00
+";
+
+fn csv_import(uri: &str, csv: &str) -> TestRequest {
+    TestRequest::post()
+        .uri(uri)
+        .header("content-type", "text/csv")
+        .set_payload(csv.to_string())
+}
+
+#[actix_rt::test]
+async fn csv_import_creates_services_with_variants() {
+    let _db = setup();
+
+    let res = send(csv_import("/api/v1/services/import", CATALOG_CSV)).await;
+
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body["services"], 2);
+    assert_eq!(res.body["variants"], 3);
+    assert_eq!(res.body["created"].as_array().unwrap().len(), 2);
+
+    let res = send(get("/api/v1/services?sort=name")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "2");
+    assert_eq!(res.body[0]["service"]["name"], "Cut");
+    assert_eq!(res.body[0]["variants"].as_array().unwrap().len(), 2);
+    assert_eq!(res.body[0]["blocked_time"]["before_time"], "00:05:00");
+    assert!(res.body[1]["service"]["description"].is_null());
+}
+
+#[actix_rt::test]
+async fn csv_import_dry_run_reports_row_errors() {
+    let _db = setup();
+    let csv = format!("{}Color,,hair,1,,,-10,01:00:00\nGloss,,hair,1,,,cheap,00:30:00\n", CATALOG_CSV);
+
+    let res = send(csv_import("/api/v1/services/import?dry_run=true", &csv)).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["dry_run"], true);
+    assert_eq!(res.body["services"], 3);
+    assert!(res.body["errors"]["rows[5].variants[0].price"].is_array());
+    assert!(res.body["errors"]["rows[6]"].is_array());
+    assert!(res.body["created"].as_array().unwrap().is_empty());
+
+    let res = send(get("/api/v1/services")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "0");
+}
+
+#[actix_rt::test]
+async fn import_writes_nothing_when_a_row_is_invalid() {
+    let _db = setup();
+    let mut invalid = service_body("Color");
+    invalid["variants"] = json!([]);
+
+    let res = send(post("/api/v1/services/import", json!([service_body("Cut"), invalid]))).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["rows[1].variants"].is_array());
+
+    let res = send(get("/api/v1/services")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "0");
+}
+
+#[actix_rt::test]
+async fn import_rejects_unknown_content_type() {
+    let _db = setup();
+
+    let res = send(TestRequest::post().uri("/api/v1/services/import").header("content-type", "text/plain").set_payload("Cut")).await;
+
+    assert_eq!(res.status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(res.code(), "request.content_type");
+}
+
+#[actix_rt::test]
+async fn export_round_trips_through_import() {
+    let _db = setup();
+    send(csv_import("/api/v1/services/import", CATALOG_CSV)).await;
+
+    let res = send(get("/api/v1/services/export?format=csv")).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("content-type").unwrap(), "text/csv");
+    assert_eq!(res.text, CATALOG_CSV);
+
+    let exported = send(get("/api/v1/services/export")).await.body;
+    assert_eq!(exported.as_array().unwrap().len(), 2);
+
+    // the same names already exist, so a re-import is refused
+    let res = send(post("/api/v1/services/import?dry_run=true", exported)).await;
+    assert_eq!(res.body["errors"]["rows[0].name"][0], "a service with this name already exists");
+    assert_eq!(res.body["errors"]["rows[1].name"][0], "a service with this name already exists");
+}
+
+#[actix_rt::test]
+async fn add_ons_extend_quote_price_and_end_time() {
+    let _db = setup();
+    let service = fixtures::service("Cut");
+    let variant_id = Service::find_variants(service.service_id).unwrap()[0].service_variant_id;
+    let add_ons_uri = format!("/api/v1/services/{}/add_ons", service.service_id);
+
+    let res = send(post(&add_ons_uri, json!({
+        "name": "Deep conditioning", "price": 15.0, "extra_duration": "00:20:00", "is_active": 1
+    }))).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+    let conditioning = res.body["service_add_on_id"].clone();
+
+    let res = send(post(&add_ons_uri, json!({
+        "name": "Scalp massage", "price": 10.0, "extra_duration": "00:10:00", "is_active": 0
+    }))).await;
+    let massage = res.body["service_add_on_id"].clone();
+
+    let res = send(get(&format!("/api/v1/services/{}", service.service_id))).await;
+    assert_eq!(res.body["add_ons"].as_array().unwrap().len(), 2);
+
+    let quote_uri = format!("/api/v1/services/{}/quote?service_variant_id={}", service.service_id, variant_id);
+
+    let res = send(get(&format!("{}&add_on_ids={}&start_time=2020-11-03T10:00:00", quote_uri, conditioning))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["price"], 40.0);
+    assert_eq!(res.body["duration_minutes"], 50);
+    assert_eq!(res.body["end_time"], "2020-11-03T10:50:00");
+    assert_eq!(res.body["blocked_until"], "2020-11-03T11:00:00");
+
+    let res = send(get(&quote_uri)).await;
+    assert_eq!(res.body["price"], 25.0);
+    assert!(res.body["end_time"].is_null());
+
+    let res = send(get(&format!("{}&add_on_ids={},{},x", quote_uri, massage, conditioning))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["add_on_ids[2]"].is_array());
+
+    let res = send(get(&format!("{}&add_on_ids={},{}", quote_uri, massage, conditioning))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["add_on_ids[0]"].is_array());
+
+    let res = send(get(&format!("/api/v1/services/{}/quote?service_variant_id=999", service.service_id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "service.variant_not_found");
+
+    let res = send(delete(&format!("{}/{}", add_ons_uri, conditioning))).await;
+    assert_eq!(res.body["deleted"], 1);
+
+    let res = send(get(&add_ons_uri)).await;
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+}
\ No newline at end of file
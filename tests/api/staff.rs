@@ -0,0 +1,387 @@
+use super::{delete, fixtures, get, post, put, send, setup};
+use rust_scheduler::audit::STAFF_ID_HEADER;
+use actix_web::http::StatusCode;
+use actix_web::test::TestRequest;
+use serde_json::json;
+
+#[actix_rt::test]
+async fn find_all_excludes_deleted_staff() {
+    let _db = setup();
+    fixtures::staff("ada@example.com");
+    let deleted = fixtures::staff("grace@example.com");
+    send(delete(&format!("/staff/{}", deleted.staff_id))).await;
+
+    let res = send(get("/staff")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+    assert_eq!(res.body[0]["email"], "ada@example.com");
+
+    let res = send(get("/staff?include_deleted=true")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "2");
+}
+
+#[actix_rt::test]
+async fn find_all_rejects_bad_limit() {
+    let _db = setup();
+
+    let res = send(get("/staff?limit=0")).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.code(), "pagination.invalid_limit");
+}
+
+#[actix_rt::test]
+async fn find_returns_staff() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(get(&format!("/staff/{}", staff.staff_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["first_name"], "Ada");
+
+    let res = send(get("/staff/99")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "staff.not_found");
+}
+
+#[actix_rt::test]
+async fn find_all_services_lists_assigned_services() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    fixtures::service("Cut");
+
+    let res = send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 1, "is_active": 1 }
+    ]))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = send(get(&format!("/staff_services/{}", staff.staff_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["staff"]["staff_id"], staff.staff_id);
+    assert_eq!(res.body["services"][0]["service"]["name"], "Cut");
+    assert_eq!(res.body["services"][0]["variants"][0]["service_variant_id"], 1);
+}
+
+#[actix_rt::test]
+async fn find_staff_with_service_lists_assignments() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    let service = fixtures::service("Cut");
+
+    send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 1, "is_active": 1 }
+    ]))).await;
+
+    let res = send(get(&format!("/staff_with_service/{}", service.service_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body[0]["staff_id"], staff.staff_id);
+    assert_eq!(res.body[0]["service_id"], service.service_id);
+}
+
+#[actix_rt::test]
+async fn find_all_staff_hours_lists_every_day() {
+    let _db = setup();
+    fixtures::staff("ada@example.com");
+
+    let res = send(get("/staff_hours")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body[0]["staff_hours"].as_array().unwrap().len(), 7);
+}
+
+#[actix_rt::test]
+async fn find_staff_hours_returns_staff_and_hours() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(get(&format!("/staff_hours/{}", staff.staff_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["staff"]["email"], "ada@example.com");
+    assert_eq!(res.body["staff_hours"].as_array().unwrap().len(), 7);
+}
+
+#[actix_rt::test]
+async fn create_generates_hours() {
+    let _db = setup();
+
+    let res = send(post("/staff", fixtures::staff_body("grace@example.com"))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["staff_id"], 1);
+    assert_eq!(res.body["access"], "manager");
+
+    let res = send(get("/staff_hours/1")).await;
+    assert_eq!(res.body["staff_hours"].as_array().unwrap().len(), 7);
+}
+
+#[actix_rt::test]
+async fn create_rejects_invalid_fields() {
+    let _db = setup();
+    let mut body = fixtures::staff_body("not an email");
+    body["phone"] = json!("12");
+    body["first_name"] = json!("");
+
+    let res = send(post("/staff", body)).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.code(), "request.validation_failed");
+    assert!(res.body["errors"]["email"].is_array());
+    assert!(res.body["errors"]["phone"].is_array());
+    assert!(res.body["errors"]["first_name"].is_array());
+}
+
+#[actix_rt::test]
+async fn create_rejects_incomplete_body() {
+    let _db = setup();
+
+    let res = send(post("/staff", json!({ "first_name": "Grace" }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.code(), "request.invalid_body");
+
+    let res = send(TestRequest::post().uri("/staff").header("content-type", "application/json").set_payload("{")).await;
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+    assert_eq!(res.code(), "request.malformed_json");
+}
+
+#[actix_rt::test]
+async fn create_rejects_duplicate_email() {
+    let _db = setup();
+    fixtures::staff("ada@example.com");
+
+    let res = send(post("/staff", fixtures::staff_body("ada@example.com"))).await;
+
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "staff.email_taken");
+}
+
+#[actix_rt::test]
+async fn create_records_acting_staff_member() {
+    let _db = setup();
+    let admin = send(post("/staff", fixtures::staff_body("grace@example.com"))).await;
+    let admin_id = admin.body["staff_id"].to_string();
+
+    let res = send(post("/staff", fixtures::staff_body("ada@example.com")).header(STAFF_ID_HEADER, admin_id.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = send(get("/audit?entity=staff&entity_id=2").header(STAFF_ID_HEADER, admin_id.clone())).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body[0]["action"], "create");
+    assert_eq!(res.body[0]["actor"], format!("staff:{}", admin_id));
+}
+
+#[actix_rt::test]
+async fn update_replaces_fields() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(put(&format!("/staff/{}", staff.staff_id), fixtures::staff_body("ada.l@example.com"))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["first_name"], "Grace");
+    assert_eq!(res.body["email"], "ada.l@example.com");
+
+    let mut body = fixtures::staff_body("ada.l@example.com");
+    body.as_object_mut().unwrap().remove("password");
+    let res = send(put(&format!("/staff/{}", staff.staff_id), body)).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = send(put("/staff/99", fixtures::staff_body("ada.l@example.com"))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn update_hours_sets_each_day() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(put("/staff_hours", json!([
+        { "staff_id": staff.staff_id, "day_of_week": 5, "start_time": "09:00:00", "end_time": "13:00:00" }
+    ]))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = send(get(&format!("/staff_hours/{}", staff.staff_id))).await;
+    let friday = res.body["staff_hours"].as_array().unwrap().iter().find(|hour| hour["day_of_week"] == 5).unwrap().clone();
+    assert_eq!(friday["start_time"], "09:00:00");
+    assert_eq!(friday["end_time"], "13:00:00");
+}
+
+#[actix_rt::test]
+async fn update_one_hour_sets_row() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(put("/staff_hours/1", json!({
+        "staff_id": staff.staff_id, "day_of_week": 0, "start_time": "11:00:00", "end_time": "15:00:00"
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["staff_hours_id"], 1);
+    assert_eq!(res.body["end_time"], "15:00:00");
+
+    let res = send(put("/staff_hours/1", json!({
+        "staff_id": staff.staff_id, "day_of_week": 0, "start_time": "15:00:00", "end_time": "11:00:00"
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+async fn update_staff_services_replaces_assignments() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    fixtures::service("Cut");
+    fixtures::service("Colour");
+
+    let res = send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 1, "is_active": 1 }
+    ]))).await;
+    assert_eq!(res.body["services"].as_array().unwrap().len(), 1);
+
+    let res = send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 2, "is_active": 1 }
+    ]))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["services"].as_array().unwrap().len(), 1);
+    assert_eq!(res.body["services"][0]["service"]["name"], "Colour");
+}
+
+#[actix_rt::test]
+async fn update_staff_services_rejects_unknown_variant() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 99, "is_active": 1 }
+    ]))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = send(put(&format!("/staff_services/{}", staff.staff_id), json!([
+        { "service_variant_id": 1, "is_active": 5 }
+    ]))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+async fn delete_and_restore() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+
+    let res = send(delete(&format!("/staff/{}", staff.staff_id))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, json!({ "deleted": 1 }));
+
+    let res = send(delete(&format!("/staff/{}", staff.staff_id))).await;
+    assert_eq!(res.body, json!({ "deleted": 0 }));
+
+    let res = send(get(&format!("/staff/{}", staff.staff_id))).await;
+    assert!(res.body["deleted_at"].is_string());
+
+    let res = send(post(&format!("/staff/{}/restore", staff.staff_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert!(res.body["deleted_at"].is_null());
+
+    let res = send(post(&format!("/staff/{}/restore", staff.staff_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "staff.not_deleted");
+
+    let res = send(post("/staff/99/restore", json!({}))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn deleted_staff_email_can_be_reused() {
+    let _db = setup();
+    let staff = fixtures::staff("ada@example.com");
+    send(delete(&format!("/staff/{}", staff.staff_id))).await;
+
+    let res = send(post("/staff", fixtures::staff_body("ada@example.com"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    // the old member cannot come back while someone else has their email
+    let res = send(post(&format!("/staff/{}/restore", staff.staff_id), json!({}))).await;
+    assert_eq!(res.status, StatusCode::CONFLICT);
+    assert_eq!(res.code(), "staff.email_taken");
+}
+
+const ROSTER_CSV: &str = "\
+first_name,last_name,password,email,phone,access,calendar_color,sunday,monday,tuesday,wednesday,thursday,friday,saturday,services
+Ada,Lovelace,secret,ada@example.com,3125550101,staff,#336699,,09:00-17:00,09:00-17:00,,,,,Cut;Color
+Grace,Hopper,secret,grace@example.com,3125550102,manager,#993366,,,,10:00-18:00,10:00-18:00,10:00-18:00,,
+";
+
+fn roster_import(csv: &str) -> TestRequest {
+    TestRequest::post()
+        .uri("/api/v1/staff/import")
+        .header("content-type", "text/csv")
+        .set_payload(csv.to_string())
+}
+
+#[actix_rt::test]
+async fn roster_import_creates_staff_hours_and_services() {
+    let _db = setup();
+    fixtures::service("Cut");
+    fixtures::service("Color");
+
+    let res = send(roster_import(ROSTER_CSV)).await;
+
+    assert_eq!(res.status, StatusCode::CREATED);
+    assert_eq!(res.body[0]["row"], "rows[2]");
+    assert_eq!(res.body[0]["hours"], 2);
+    assert_eq!(res.body[0]["services"], 2);
+    assert_eq!(res.body[1]["hours"], 3);
+    assert_eq!(res.body[1]["services"], 0);
+
+    let ada = res.body[0]["staff_id"].as_i64().unwrap();
+    let res = send(get(&format!("/api/v1/staff/{}/hours", ada))).await;
+    let day = |day_of_week: i64| res.body["staff_hours"].as_array().unwrap().iter().find(|hours| hours["day_of_week"] == day_of_week).unwrap().clone();
+    assert_eq!(day(1)["start_time"], "09:00:00");
+    assert!(day(3)["start_time"].is_null());
+
+    let res = send(get(&format!("/api/v1/staff/{}/services", ada))).await;
+    assert_eq!(res.body["services"].as_array().unwrap().len(), 2);
+}
+
+#[actix_rt::test]
+async fn roster_import_reports_errors_by_row_and_creates_nothing() {
+    let _db = setup();
+    fixtures::service("Cut");
+    let csv = format!("{}Alan,Turing,secret,ada@example.com,3125550103,staff,#000000,,9am,,,,,,Massage\n", ROSTER_CSV);
+
+    let res = send(roster_import(&csv)).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.body["errors"]["rows[2].services"][0], "unknown service: Color");
+    assert_eq!(res.body["errors"]["rows[4].email"][0], "is already taken");
+    assert!(res.body["errors"]["rows[4].monday"].is_array());
+
+    let res = send(get("/api/v1/staff")).await;
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "0");
+}
+
+#[actix_rt::test]
+async fn roster_import_accepts_json() {
+    let _db = setup();
+
+    let res = send(post("/api/v1/staff/import", json!([{
+        "first_name": "Ada",
+        "last_name": "Lovelace",
+        "password": "secret",
+        "email": "ada@example.com",
+        "phone": "3125550101",
+        "access": "staff",
+        "calendar_color": "#336699",
+        "hours": [
+            { "day_of_week": 1, "start_time": "09:00:00", "end_time": "17:00:00" },
+            { "day_of_week": 1, "start_time": "17:00:00", "end_time": "09:00:00" }
+        ]
+    }]))).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["rows[0].hours[1].day_of_week"].is_array());
+    assert!(res.body["errors"]["rows[0].hours[1].end_time"].is_array());
+}
\ No newline at end of file
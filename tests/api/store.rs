@@ -0,0 +1,281 @@
+use super::{delete, fixtures, get, post, put, send, setup};
+use actix_web::http::StatusCode;
+use serde_json::json;
+
+#[actix_rt::test]
+async fn find_all_paginates() {
+    let _db = setup();
+    fixtures::store("Downtown");
+    fixtures::store("Uptown");
+
+    let res = send(get("/store?limit=1")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "2");
+    assert_eq!(res.body.as_array().unwrap().len(), 1);
+    assert_eq!(res.headers.get("deprecation").unwrap(), "true");
+
+    let links: Vec<_> = res.headers.get_all("link").map(|link| link.to_str().unwrap()).collect();
+    assert!(links.iter().any(|link| link.contains("rel=\"next\"")));
+    assert!(links.iter().any(|link| link.contains("rel=\"deprecation\"")));
+}
+
+#[actix_rt::test]
+async fn legacy_find_all_is_unpaged_by_default() {
+    let _db = setup();
+    for number in 0..51 {
+        fixtures::store(&format!("Store {}", number));
+    }
+
+    let res = send(get("/store")).await;
+    assert_eq!(res.body.as_array().unwrap().len(), 51);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "51");
+
+    let res = send(get("/api/v1/stores")).await;
+    assert_eq!(res.body.as_array().unwrap().len(), 50);
+}
+
+#[actix_rt::test]
+async fn find_all_filters_by_name() {
+    let _db = setup();
+    fixtures::store("Downtown");
+    fixtures::store("Uptown");
+
+    let res = send(get("/store?name=Uptown")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, json!([{ "store_id": 2, "name": "Uptown" }]));
+}
+
+#[actix_rt::test]
+async fn find_returns_store() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(get(&format!("/store/{}", store.store_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["name"], "Downtown");
+}
+
+#[actix_rt::test]
+async fn find_missing_store_is_not_found() {
+    let _db = setup();
+
+    let res = send(get("/store/99")).await;
+
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+    assert_eq!(res.code(), "store.not_found");
+    assert!(res.body["request_id"].is_string());
+}
+
+#[actix_rt::test]
+async fn find_all_info_includes_address_and_hours() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+    fixtures::store_address(store.store_id);
+
+    let res = send(get(&format!("/store_info/{}", store.store_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["name"], "Downtown");
+    assert_eq!(res.body["address"]["city"], "Springfield");
+    assert_eq!(res.body["hours"].as_array().unwrap().len(), 7);
+}
+
+#[actix_rt::test]
+async fn find_all_store_hours_lists_every_day() {
+    let _db = setup();
+    fixtures::store("Downtown");
+
+    let res = send(get("/store_hours")).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.headers.get("x-total-count").unwrap(), "1");
+    assert_eq!(res.body[0]["store_hours"].as_array().unwrap().len(), 7);
+}
+
+#[actix_rt::test]
+async fn find_store_hours_returns_store_and_hours() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(get(&format!("/store_hours/{}", store.store_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["store"]["name"], "Downtown");
+    assert_eq!(res.body["store_hours"].as_array().unwrap().len(), 7);
+}
+
+#[actix_rt::test]
+async fn find_address_returns_address() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+    fixtures::store_address(store.store_id);
+
+    let res = send(get(&format!("/store_address/{}", store.store_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["zip"], "62701");
+
+    let res = send(get("/store_address/99")).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn create_generates_hours() {
+    let _db = setup();
+
+    let res = send(post("/store", json!({ "name": "Downtown" }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, json!({ "store_id": 1, "name": "Downtown" }));
+
+    let res = send(get("/store_hours/1")).await;
+    let days: Vec<_> = res.body["store_hours"].as_array().unwrap().iter().map(|hour| hour["day_of_week"].as_i64().unwrap()).collect();
+    assert_eq!(days, vec![0, 1, 2, 3, 4, 5, 6]);
+}
+
+#[actix_rt::test]
+async fn create_rejects_blank_name() {
+    let _db = setup();
+
+    let res = send(post("/store", json!({ "name": " " }))).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.code(), "request.validation_failed");
+    assert!(res.body["errors"]["name"].is_array());
+}
+
+#[actix_rt::test]
+async fn create_address_validates_fields() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(post("/store_address", fixtures::address_body(store.store_id, "Chicago"))).await;
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["city"], "Chicago");
+
+    let mut body = fixtures::address_body(store.store_id, "Chicago");
+    body["zip"] = json!("123");
+    body["email"] = json!("not an email");
+
+    let res = send(post("/store_address", body)).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["zip"].is_array());
+    assert!(res.body["errors"]["email"].is_array());
+}
+
+#[actix_rt::test]
+async fn create_address_for_missing_store_is_rejected() {
+    let _db = setup();
+
+    let res = send(post("/store_address", fixtures::address_body(99, "Chicago"))).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(res.code(), "store_address.store_id_unknown");
+}
+
+#[actix_rt::test]
+async fn update_renames_store() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(put(&format!("/store/{}", store.store_id), json!({ "name": "Midtown" }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["name"], "Midtown");
+
+    let res = send(put("/store/99", json!({ "name": "Midtown" }))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+}
+
+#[actix_rt::test]
+async fn update_address_replaces_address() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+    fixtures::store_address(store.store_id);
+
+    let res = send(put(&format!("/store_address/{}", store.store_id), fixtures::address_body(store.store_id, "Peoria"))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["city"], "Peoria");
+    assert_eq!(res.body["zip"], "62702-1234");
+}
+
+#[actix_rt::test]
+async fn update_hours_sets_each_day() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(put("/store_hours", json!([
+        { "store_id": store.store_id, "day_of_week": 1, "start_time": "09:00:00", "end_time": "17:00:00" },
+        { "store_id": store.store_id, "day_of_week": 2, "start_time": "10:00:00", "end_time": "18:00:00" }
+    ]))).await;
+    assert_eq!(res.status, StatusCode::OK);
+
+    let res = send(get(&format!("/store_hours/{}", store.store_id))).await;
+    let hours = res.body["store_hours"].as_array().unwrap();
+    let monday = hours.iter().find(|hour| hour["day_of_week"] == 1).unwrap();
+    assert_eq!(monday["start_time"], "09:00:00");
+    assert_eq!(monday["end_time"], "17:00:00");
+}
+
+#[actix_rt::test]
+async fn update_hours_rejects_inverted_range() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(put("/store_hours", json!([
+        { "store_id": store.store_id, "day_of_week": 1, "start_time": "17:00:00", "end_time": "09:00:00" }
+    ]))).await;
+
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[actix_rt::test]
+async fn update_one_hour_sets_row() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+
+    let res = send(put("/store_hours/3", json!({
+        "store_id": store.store_id, "day_of_week": 2, "start_time": "08:00:00", "end_time": "12:00:00"
+    }))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body["store_hours_id"], 3);
+    assert_eq!(res.body["start_time"], "08:00:00");
+
+    let res = send(put("/store_hours/3", json!({
+        "store_id": store.store_id, "day_of_week": 9, "start_time": null, "end_time": null
+    }))).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["day_of_week"].is_array());
+}
+
+#[actix_rt::test]
+async fn delete_removes_store_and_hours() {
+    let _db = setup();
+    let store = fixtures::store("Downtown");
+    fixtures::store_address(store.store_id);
+
+    let res = send(delete(&format!("/store/{}", store.store_id))).await;
+
+    assert_eq!(res.status, StatusCode::OK);
+    assert_eq!(res.body, json!({ "deleted": 1 }));
+
+    let res = send(get(&format!("/store/{}", store.store_id))).await;
+    assert_eq!(res.status, StatusCode::NOT_FOUND);
+
+    let res = send(get("/store_hours")).await;
+    assert_eq!(res.body, json!([]));
+}
+
+#[actix_rt::test]
+async fn malformed_path_is_bad_request() {
+    let _db = setup();
+
+    let res = send(get("/store/abc")).await;
+
+    assert_eq!(res.status, StatusCode::BAD_REQUEST);
+}
\ No newline at end of file
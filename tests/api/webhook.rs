@@ -0,0 +1,48 @@
+use super::{connection, fixtures, get, post, send, setup};
+use actix_web::http::StatusCode;
+use rust_scheduler::audit::STAFF_ID_HEADER;
+use chrono::Duration;
+use rust_scheduler::webhook::{self, WebhookDelivery, WebhookSubscription, WebhookSubscriptionCreate};
+use serde_json::json;
+
+#[test]
+fn claimed_deliveries_are_not_handed_out_twice() {
+    let _db = setup();
+    WebhookSubscription::create(WebhookSubscriptionCreate {
+        url: "http://127.0.0.1:9/hooks".to_string(),
+        secret: "secret".to_string(),
+        event_types: vec![webhook::STAFF_CREATED.to_string()],
+        is_active: 1
+    }).unwrap();
+    webhook::emit(&connection(), webhook::STAFF_CREATED, json!({ "staff_id": 1 }));
+
+    let claimed = WebhookDelivery::claim_due(10, Duration::minutes(15)).unwrap();
+    assert_eq!(claimed.len(), 1);
+    assert!(WebhookDelivery::claim_due(10, Duration::minutes(15)).unwrap().is_empty());
+
+    // released deliveries are due again straight away
+    WebhookDelivery::release(&[claimed[0].0.webhook_delivery_id]).unwrap();
+    assert_eq!(WebhookDelivery::claim_due(10, Duration::minutes(15)).unwrap().len(), 1);
+}
+
+#[actix_rt::test]
+async fn subscriptions_are_manager_only_and_need_an_http_url() {
+    let _db = setup();
+    let manager = send(post("/staff", fixtures::staff_body("grace@example.com"))).await;
+    let manager_id = manager.body["staff_id"].to_string();
+    let body = json!({ "url": "https://example.com/hooks", "secret": "secret", "event_types": ["staff.created"], "is_active": 1 });
+
+    let res = send(post("/api/v1/webhooks", body.clone())).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = send(get("/api/v1/webhooks")).await;
+    assert_eq!(res.status, StatusCode::UNAUTHORIZED);
+
+    let res = send(post("/api/v1/webhooks", json!({ "url": "file:///etc/passwd", "secret": "secret", "event_types": [], "is_active": 1 }))
+        .header(STAFF_ID_HEADER, manager_id.clone())).await;
+    assert_eq!(res.status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert!(res.body["errors"]["url"].is_array());
+
+    let res = send(post("/api/v1/webhooks", body).header(STAFF_ID_HEADER, manager_id)).await;
+    assert_eq!(res.status, StatusCode::CREATED);
+}
\ No newline at end of file